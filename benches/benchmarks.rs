@@ -1,32 +1,38 @@
 // Performance benchmarks for ClipVault
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
 use clipboard_manager::storage::{
-    database::{Database, ClipboardItem},
+    database::{Database, ClipboardItem, ItemId, BlobId},
     encryption::Encryptor,
     search::SearchEngine,
     processor::DataProcessor,
+    config::AppConfig,
 };
 use tempfile::TempDir;
 
 // Helper to create test items
 fn create_test_item(id: i64, preview: &str, timestamp: i64) -> ClipboardItem {
     ClipboardItem {
-        id,
+        id: ItemId(id),
         timestamp,
         data_type: "text".to_string(),
         is_sensitive: false,
         is_encrypted: false,
         preview_text: Some(preview.to_string()),
         data_size: preview.len() as i64,
-        data_blob_id: id,
+        data_blob_id: BlobId(id),
         metadata: None,
         copy_count: 1,
         is_pinned: false,
+        thumbnail_blob_id: None,
+        source_app_name: None,
+        source_bundle_id: None,
+        sensitivity_override: None,
+        tags: Vec::new(),
     }
 }
 
 // Helper to insert an item into the database using the actual API
-fn insert_item(db: &Database, text: &str, timestamp: i64) -> i64 {
+fn insert_item(db: &Database, text: &str, timestamp: i64) -> ItemId {
     let blob_id = db.store_blob(text.as_bytes()).unwrap();
     db.store_item(
         timestamp,
@@ -81,6 +87,107 @@ fn bench_database_query(c: &mut Criterion) {
     });
 }
 
+// Benchmark: Recent-items query on a fragmented database, before and after VACUUM
+fn bench_database_vacuum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("database_vacuum");
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new(temp_dir.path().join("bench.db")).unwrap();
+
+    // Insert 10,000 items, then delete every other one
+    let base_ts = chrono::Utc::now().timestamp();
+    let ids: Vec<ItemId> = (0..10_000)
+        .map(|i| insert_item(&db, &format!("Fragmented item {}", i), base_ts + i))
+        .collect();
+    for id in ids.iter().step_by(2) {
+        db.delete_item(*id).unwrap();
+    }
+
+    group.bench_function("query_recent_20_fragmented", |b| {
+        b.iter(|| {
+            black_box(db.get_recent_items(20).unwrap());
+        });
+    });
+
+    db.vacuum().unwrap();
+
+    group.bench_function("query_recent_20_vacuumed", |b| {
+        b.iter(|| {
+            black_box(db.get_recent_items(20).unwrap());
+        });
+    });
+
+    group.finish();
+}
+
+// Benchmark: 100 copies of the same text, preview-based duplicate removal vs content-hash lookup
+fn bench_repeat_copy_dedup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("repeat_copy_dedup");
+    let processed = DataProcessor::process_text("The same snippet copied over and over", &[]);
+
+    group.bench_function("remove_duplicates_100", |b| {
+        b.iter(|| {
+            let temp_dir = TempDir::new().unwrap();
+            let db = Database::new(temp_dir.path().join("bench.db")).unwrap();
+            let base_ts = chrono::Utc::now().timestamp();
+            for i in 0..100 {
+                let (_, prev_count) = db
+                    .remove_duplicates(processed.preview_text.as_deref(), processed.data_type.as_str())
+                    .unwrap();
+                let blob_id = db.store_blob(&processed.blob).unwrap();
+                db.insert_item(&processed, blob_id, base_ts + i, prev_count + 1, false).unwrap();
+            }
+            black_box(db.count_items().unwrap());
+        });
+    });
+
+    group.bench_function("content_hash_100", |b| {
+        b.iter(|| {
+            let temp_dir = TempDir::new().unwrap();
+            let db = Database::new(temp_dir.path().join("bench.db")).unwrap();
+            let base_ts = chrono::Utc::now().timestamp();
+            for i in 0..100 {
+                match db.find_item_by_content_hash(&processed.content_hash).unwrap() {
+                    Some(existing) => db.record_repeat_copy(existing.id, base_ts + i).unwrap(),
+                    None => {
+                        let blob_id = db.store_blob(&processed.blob).unwrap();
+                        db.insert_item(&processed, blob_id, base_ts + i, 1, false).unwrap();
+                    }
+                }
+            }
+            black_box(db.count_items().unwrap());
+        });
+    });
+
+    group.finish();
+}
+
+// Benchmark: JSON export of a 100,000-row history, streamed in chunks vs loaded at once
+fn bench_database_export(c: &mut Criterion) {
+    let mut group = c.benchmark_group("database_export");
+    group.sample_size(10);
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new(temp_dir.path().join("bench.db")).unwrap();
+
+    let base_ts = chrono::Utc::now().timestamp();
+    for i in 0..100_000 {
+        insert_item(&db, &format!("Exported clipboard item number {}", i), base_ts + i);
+    }
+
+    group.bench_function("export_json_streaming_100k", |b| {
+        b.iter(|| {
+            black_box(db.export_json_streaming(&mut std::io::sink()).unwrap());
+        });
+    });
+
+    group.bench_function("export_json_full_100k", |b| {
+        b.iter(|| {
+            black_box(db.export_json(&mut std::io::sink()).unwrap());
+        });
+    });
+
+    group.finish();
+}
+
 // Benchmark: Encryption operations
 fn bench_encryption(c: &mut Criterion) {
     let mut group = c.benchmark_group("encryption");
@@ -158,6 +265,27 @@ fn bench_search(c: &mut Criterion) {
                 });
             },
         );
+
+        // Empty query via search() vs the recency fast path it delegates to
+        group.bench_with_input(
+            BenchmarkId::new("search_empty_query", item_count),
+            &items,
+            |b, items| {
+                b.iter(|| {
+                    black_box(engine.search(items, ""));
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("ranked_by_recency", item_count),
+            &items,
+            |b, items| {
+                b.iter(|| {
+                    black_box(SearchEngine::ranked_by_recency(items));
+                });
+            },
+        );
     }
 
     group.finish();
@@ -222,6 +350,82 @@ fn bench_image_processing(c: &mut Criterion) {
     group.finish();
 }
 
+// Benchmark: TIFF screenshot storage as PNG vs JPEG (also reports the stored sizes)
+fn bench_tiff_storage_format(c: &mut Criterion) {
+    use image::{ImageBuffer, Rgb};
+    use std::io::Cursor;
+
+    let mut group = c.benchmark_group("tiff_storage_format");
+
+    // Smooth gradient, similar to a screenshot of a window background
+    let img = ImageBuffer::from_fn(1440, 900, |x, y| {
+        Rgb([(x * 255 / 1440) as u8, (y * 255 / 900) as u8, 128u8])
+    });
+    let mut tiff = Vec::new();
+    image::DynamicImage::ImageRgb8(img)
+        .write_to(&mut Cursor::new(&mut tiff), image::ImageFormat::Tiff)
+        .unwrap();
+
+    for (name, to_jpeg) in [("tiff_to_png", false), ("tiff_to_jpeg", true)] {
+        let config = AppConfig { image_tiff_to_jpeg: to_jpeg, ..AppConfig::default() };
+        let stored = DataProcessor::process_image_with_config(&tiff, "public.tiff", &config).unwrap();
+        println!("{}: {} bytes TIFF -> {} bytes stored", name, tiff.len(), stored.blob.len());
+
+        group.bench_with_input(BenchmarkId::new(name, "1440x900"), &tiff, |b, data| {
+            b.iter(|| {
+                black_box(DataProcessor::process_image_with_config(data, "public.tiff", &config).unwrap());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+// Benchmark: storing blobs with and without compression (also reports the stored sizes)
+fn bench_blob_compression(c: &mut Criterion) {
+    use image::{ImageBuffer, Rgb};
+    use std::io::Cursor;
+
+    let mut group = c.benchmark_group("blob_compression");
+
+    // A pasted log excerpt, and a screenshot-like PNG (already deflated, so little gain)
+    let text: Vec<u8> = (0..2000)
+        .map(|i| format!("2026-01-27 12:00:{:02} INFO request {} served in {}ms\n", i % 60, i, i % 97))
+        .collect::<String>()
+        .into_bytes();
+    let img = ImageBuffer::from_fn(800, 600, |x, y| {
+        Rgb([(x * 255 / 800) as u8, (y * 255 / 600) as u8, 128u8])
+    });
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(img)
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+
+    for (name, data) in [("text", &text), ("png", &png)] {
+        for compress in [false, true] {
+            let mut db = Database::new_in_memory().unwrap();
+            db.set_compression(compress);
+            db.store_blob(data).unwrap();
+            let stored = db.get_storage_breakdown().unwrap().live_items_bytes;
+            let label = if compress { "deflate" } else { "none" };
+            println!("{} ({}): {} bytes -> {} bytes stored", name, label, data.len(), stored);
+
+            // Vary one byte per iteration so dedup doesn't skip the write
+            let mut input = data.clone();
+            let mut n = 0u8;
+            group.bench_function(BenchmarkId::new(name, label), |b| {
+                b.iter(|| {
+                    n = n.wrapping_add(1);
+                    input[0] = n;
+                    black_box(db.store_blob(&input).unwrap());
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
 // Benchmark: Full workflow (insert + search)
 fn bench_full_workflow(c: &mut Criterion) {
     c.bench_function("workflow_insert_and_search", |b| {
@@ -247,10 +451,15 @@ criterion_group!(
     benches,
     bench_database_insert,
     bench_database_query,
+    bench_database_vacuum,
+    bench_repeat_copy_dedup,
+    bench_database_export,
     bench_encryption,
     bench_search,
     bench_sensitive_detection,
     bench_image_processing,
+    bench_tiff_storage_format,
+    bench_blob_compression,
     bench_full_workflow,
 );
 
@@ -0,0 +1,95 @@
+#!/usr/bin/env python3
+"""Generate the status bar template icons in src/assets (standard library only).
+
+Template images only use the alpha channel: macOS tints them for light/dark
+menu bars. Each icon is drawn on a 32x32 grid (the @2x file) and box-filtered
+down to 16x16 for non-Retina displays.
+"""
+
+import os
+import struct
+import zlib
+
+GRID = 32
+OUT_DIR = os.path.normpath(os.path.join(os.path.dirname(__file__), "..", "src", "assets"))
+
+
+def clipboard():
+    """Clipboard outline with the clip at the top. Shapes sit on even
+    coordinates so the 16x16 version stays sharp."""
+    alpha = [[0] * GRID for _ in range(GRID)]
+
+    def fill(x0, y0, x1, y1):
+        for y in range(y0, y1):
+            for x in range(x0, x1):
+                alpha[y][x] = 255
+
+    # Board outline, 2px thick
+    fill(6, 4, 26, 6)
+    fill(6, 28, 26, 30)
+    fill(6, 4, 8, 30)
+    fill(24, 4, 26, 30)
+    # Clip
+    fill(10, 2, 22, 8)
+    return alpha, fill
+
+
+def recording():
+    alpha, fill = clipboard()
+    # Lines of text
+    for y in (12, 18, 24):
+        fill(10, y, 22, y + 2)
+    return alpha
+
+
+def paused():
+    alpha, fill = clipboard()
+    # Pause bars
+    fill(10, 12, 14, 26)
+    fill(18, 12, 22, 26)
+    return alpha
+
+
+def downsample(alpha):
+    half = GRID // 2
+    return [
+        [
+            (alpha[2 * y][2 * x] + alpha[2 * y][2 * x + 1]
+             + alpha[2 * y + 1][2 * x] + alpha[2 * y + 1][2 * x + 1]) // 4
+            for x in range(half)
+        ]
+        for y in range(half)
+    ]
+
+
+def write_png(path, alpha):
+    """Black RGBA PNG whose alpha channel is `alpha`."""
+    height, width = len(alpha), len(alpha[0])
+    raw = b"".join(
+        b"\x00" + b"".join(struct.pack("4B", 0, 0, 0, a) for a in row)
+        for row in alpha
+    )
+
+    def chunk(kind, data):
+        body = kind + data
+        return struct.pack(">I", len(data)) + body + struct.pack(">I", zlib.crc32(body) & 0xFFFFFFFF)
+
+    png = b"\x89PNG\r\n\x1a\n"
+    png += chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+    png += chunk(b"IDAT", zlib.compress(raw, 9))
+    png += chunk(b"IEND", b"")
+    with open(path, "wb") as f:
+        f.write(png)
+    print(f"Wrote {path} ({width}x{height}, {len(png)} bytes)")
+
+
+def main():
+    os.makedirs(OUT_DIR, exist_ok=True)
+    for name, draw in (("statusbar", recording), ("statusbar-paused", paused)):
+        alpha = draw()
+        write_png(os.path.join(OUT_DIR, f"{name}@2x.png"), alpha)
+        write_png(os.path.join(OUT_DIR, f"{name}.png"), downsample(alpha))
+
+
+if __name__ == "__main__":
+    main()
@@ -1,4 +1,4 @@
 // Clipboard module - handles NSPasteboard monitoring and data extraction
 pub mod monitor;
 
-pub use monitor::ClipboardMonitor;
+pub use monitor::{ClipboardChange, ClipboardMonitor};
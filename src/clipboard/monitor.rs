@@ -1,21 +1,80 @@
 // NSPasteboard monitoring implementation using objc2
-use log::{debug, info};
-use objc2_app_kit::NSPasteboard;
-use objc2_foundation::NSString;
+use block2::RcBlock;
+use log::{debug, info, warn};
+use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, ProtocolObject};
+use objc2_app_kit::{NSPasteboard, NSWorkspace, NSWorkspaceDidWakeNotification, NSWorkspaceWillSleepNotification};
+use objc2_foundation::{NSNotification, NSNotificationCenter, NSObjectProtocol, NSString};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use tokio::sync::mpsc;
-use tokio::time::{interval, Duration};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{interval, Duration, Interval};
+use crate::storage::SourceApp;
+
+/// Default capacity of the bounded change channel (see `start_bounded`)
+pub const DEFAULT_QUEUE_DEPTH: usize = 32;
+
+/// Sleep/wake observers from `ClipboardMonitor::observe_sleep_wake`.
+/// Dropping this unregisters them.
+pub struct SleepWakeObservers {
+    center: Retained<NSNotificationCenter>,
+    tokens: Vec<Retained<ProtocolObject<dyn NSObjectProtocol>>>,
+}
+
+impl Drop for SleepWakeObservers {
+    fn drop(&mut self) {
+        for token in self.tokens.drain(..) {
+            unsafe {
+                // The token is the observer object the center handed out
+                let observer: Retained<AnyObject> = Retained::cast(token);
+                self.center.removeObserver(&observer);
+            }
+        }
+    }
+}
 
 /// Represents a clipboard change event
 #[derive(Debug, Clone)]
 pub struct ClipboardChange {
     pub change_count: i64,
     pub types: Vec<String>,
+    /// App that was frontmost when the change was noticed (normally the one that copied)
+    pub source_app: SourceApp,
+}
+
+impl ClipboardChange {
+    /// Snapshot of `pasteboard` right now, read synchronously for code outside
+    /// the async monitor. `None` when the pasteboard holds nothing.
+    pub fn from_pasteboard(pasteboard: &NSPasteboard) -> Option<ClipboardChange> {
+        objc2::rc::autoreleasepool(|_| {
+            let types = ClipboardMonitor::get_available_types(pasteboard);
+            if types.is_empty() {
+                return None;
+            }
+            Some(ClipboardChange {
+                change_count: unsafe { pasteboard.changeCount() } as i64,
+                types,
+                source_app: ClipboardMonitor::frontmost_app(),
+            })
+        })
+    }
 }
 
 /// ClipboardMonitor polls NSPasteboard for changes
 pub struct ClipboardMonitor {
     last_change_count: i64,
-    poll_interval_ms: u64,
+    /// Shared so the settings can change it while the monitor runs
+    poll_interval_ms: Arc<AtomicU64>,
+    /// Changes discarded because the bounded channel was full
+    dropped_events: Arc<AtomicU64>,
+    /// While set, changes are seen but not sent (e.g. while typing a password)
+    paused: Arc<AtomicBool>,
+    /// Times the Mac has woken from sleep, bumped by the wake observer
+    wake_count: Arc<AtomicI64>,
+    /// `wake_count` as of the last reset of `last_change_count`
+    seen_wakes: i64,
 }
 
 impl ClipboardMonitor {
@@ -36,8 +95,90 @@ impl ClipboardMonitor {
 
         Self {
             last_change_count,
-            poll_interval_ms: interval_ms,
+            poll_interval_ms: Arc::new(AtomicU64::new(interval_ms)),
+            dropped_events: Arc::new(AtomicU64::new(0)),
+            paused: Arc::new(AtomicBool::new(false)),
+            wake_count: Arc::new(AtomicI64::new(0)),
+            seen_wakes: 0,
+        }
+    }
+
+    /// Take the pasteboard's current change count as already seen, so
+    /// whatever it holds now is not reported as a change
+    pub fn reset_change_count(&mut self) {
+        let current = Self::change_count();
+        if current != self.last_change_count {
+            info!("Change count reset: {} -> {}", self.last_change_count, current);
         }
+        self.last_change_count = current;
+    }
+
+    /// Observe system sleep and wake. After a wake the change count may have
+    /// jumped or reset, so the next tick re-reads it without sending a change.
+    /// The observers are removed when the returned guard is dropped.
+    pub fn observe_sleep_wake(&self) -> SleepWakeObservers {
+        let wake_count = Arc::clone(&self.wake_count);
+        let on_wake = RcBlock::new(move |_notification: NonNull<NSNotification>| {
+            info!("System woke from sleep, resyncing clipboard change count");
+            wake_count.fetch_add(1, Ordering::Relaxed);
+        });
+        let on_sleep = RcBlock::new(|_notification: NonNull<NSNotification>| {
+            info!("System going to sleep");
+        });
+
+        unsafe {
+            let center = NSWorkspace::sharedWorkspace().notificationCenter();
+            let tokens = vec![
+                center.addObserverForName_object_queue_usingBlock(
+                    Some(NSWorkspaceDidWakeNotification), None, None, &on_wake,
+                ),
+                center.addObserverForName_object_queue_usingBlock(
+                    Some(NSWorkspaceWillSleepNotification), None, None, &on_sleep,
+                ),
+            ];
+            SleepWakeObservers { center, tokens }
+        }
+    }
+
+    pub fn poll_interval_ms(&self) -> u64 {
+        self.poll_interval_ms.load(Ordering::Relaxed)
+    }
+
+    /// Shared handle to the poll interval, for the settings menu. A new value
+    /// applies from the tick after next.
+    pub fn poll_interval_handle(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.poll_interval_ms)
+    }
+
+    /// Stop capturing changes until `resume`
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+        info!("Clipboard monitoring paused");
+    }
+
+    /// Capture changes again. Anything copied while paused stays skipped.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+        info!("Clipboard monitoring resumed");
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Shared handle to the pause flag, for the menu bar toggle
+    pub fn paused_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.paused)
+    }
+
+    /// Number of changes dropped by `start_bounded` so far
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Shared handle to the drop counter, readable after the monitor moves into a task
+    pub fn dropped_events_handle(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.dropped_events)
     }
 
     /// Start monitoring clipboard changes, sending events to the provided channel.
@@ -46,12 +187,64 @@ impl ClipboardMonitor {
         &mut self,
         tx: mpsc::UnboundedSender<ClipboardChange>,
     ) {
+        self.run(|change| {
+            // Send change notification (non-fatal: log error but continue monitoring)
+            if let Err(e) = tx.send(change) {
+                log::error!("Failed to send clipboard change (channel error, continuing): {}", e);
+            }
+        }).await
+    }
+
+    /// Like `start`, but over a bounded channel of `queue_depth`: when the
+    /// receiver falls behind, new changes are dropped (and counted) instead
+    /// of queueing without limit.
+    pub async fn start_bounded(
+        &mut self,
+        tx: mpsc::Sender<ClipboardChange>,
+        queue_depth: usize,
+    ) {
+        let dropped = Arc::clone(&self.dropped_events);
+        self.run(|change| {
+            Self::send_or_drop(&tx, change, queue_depth, &dropped);
+        }).await
+    }
+
+    /// Try to queue a change; returns false if it was dropped
+    fn send_or_drop(
+        tx: &mpsc::Sender<ClipboardChange>,
+        change: ClipboardChange,
+        queue_depth: usize,
+        dropped: &AtomicU64,
+    ) -> bool {
+        match tx.try_send(change) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                dropped.fetch_add(1, Ordering::Relaxed);
+                warn!("Clipboard change dropped: channel full (queue depth {})", queue_depth);
+                false
+            }
+            Err(TrySendError::Closed(_)) => {
+                log::error!("Failed to send clipboard change (channel closed, continuing)");
+                false
+            }
+        }
+    }
+
+    /// Poll the pasteboard forever, handing each change to `send`
+    async fn run(&mut self, mut send: impl FnMut(ClipboardChange)) {
         info!("Starting clipboard monitor...");
-        let mut tick = interval(Duration::from_millis(self.poll_interval_ms));
+        let mut tick = PollTimer::new(Arc::clone(&self.poll_interval_ms));
 
         loop {
             tick.tick().await;
 
+            let wakes = self.wake_count.load(Ordering::Relaxed);
+            if wakes != self.seen_wakes {
+                self.seen_wakes = wakes;
+                self.reset_change_count();
+                continue;
+            }
+
             let (current_count, types) = objc2::rc::autoreleasepool(|_| {
                 unsafe {
                     let pasteboard = NSPasteboard::generalPasteboard();
@@ -61,7 +254,10 @@ impl ClipboardMonitor {
                 }
             });
 
-            if current_count != self.last_change_count {
+            if current_count != self.last_change_count && self.is_paused() {
+                debug!("Clipboard changed while paused, skipping");
+                self.last_change_count = current_count;
+            } else if current_count != self.last_change_count {
                 debug!(
                     "Clipboard changed: {} -> {}",
                     self.last_change_count, current_count
@@ -70,20 +266,33 @@ impl ClipboardMonitor {
                 let change = ClipboardChange {
                     change_count: current_count,
                     types: types.clone(),
+                    source_app: Self::frontmost_app(),
                 };
 
                 info!("Clipboard change detected: {:?}", types);
-
-                // Send change notification (non-fatal: log error but continue monitoring)
-                if let Err(e) = tx.send(change) {
-                    log::error!("Failed to send clipboard change (channel error, continuing): {}", e);
-                }
+                send(change);
 
                 self.last_change_count = current_count;
             }
         }
     }
 
+    /// Name and bundle ID of the frontmost app. The pasteboard doesn't record
+    /// its writer, so this is read as soon as a change is seen.
+    pub fn frontmost_app() -> SourceApp {
+        objc2::rc::autoreleasepool(|_| {
+            unsafe {
+                let Some(app) = NSWorkspace::sharedWorkspace().frontmostApplication() else {
+                    return SourceApp::default();
+                };
+                SourceApp {
+                    name: app.localizedName().map(|name| name.to_string()),
+                    bundle_id: app.bundleIdentifier().map(|id| id.to_string()),
+                }
+            }
+        })
+    }
+
     /// Get list of available UTI types on the pasteboard
     fn get_available_types(pasteboard: &NSPasteboard) -> Vec<String> {
         unsafe {
@@ -121,6 +330,20 @@ impl ClipboardMonitor {
         })
     }
 
+    /// Extract a copied file's `file://` URL (Finder puts the file icon and name
+    /// on the pasteboard too, so check this before images and text)
+    pub fn get_file_url() -> Option<String> {
+        objc2::rc::autoreleasepool(|_| {
+            unsafe {
+                let pasteboard = NSPasteboard::generalPasteboard();
+                let file_url_type = NSString::from_str("public.file-url");
+                pasteboard
+                    .stringForType(&file_url_type)
+                    .map(|ns_str| ns_str.to_string())
+            }
+        })
+    }
+
     /// Extract image data from clipboard (TIFF, PNG, JPEG)
     pub fn get_image() -> Option<(Vec<u8>, String)> {
         objc2::rc::autoreleasepool(|_| {
@@ -162,6 +385,39 @@ impl ClipboardMonitor {
     }
 }
 
+/// Poll timer that follows a shared interval setting
+struct PollTimer {
+    setting: Arc<AtomicU64>,
+    current_interval_ms: u64,
+    interval: Interval,
+}
+
+impl PollTimer {
+    fn new(setting: Arc<AtomicU64>) -> Self {
+        let current_interval_ms = setting.load(Ordering::Relaxed).max(1);
+        PollTimer {
+            setting,
+            current_interval_ms,
+            interval: interval(Duration::from_millis(current_interval_ms)),
+        }
+    }
+
+    /// Wait for the next tick. A new setting is picked up as this tick fires
+    /// and applies from the one after it.
+    async fn tick(&mut self) {
+        self.interval.tick().await;
+
+        let wanted = self.setting.load(Ordering::Relaxed).max(1);
+        if wanted != self.current_interval_ms {
+            info!("Clipboard polling interval: {}ms -> {}ms", self.current_interval_ms, wanted);
+            self.current_interval_ms = wanted;
+            self.interval = interval(Duration::from_millis(wanted));
+            // A fresh interval's first tick fires at once; wait a full period instead
+            self.interval.reset();
+        }
+    }
+}
+
 impl Default for ClipboardMonitor {
     fn default() -> Self {
         Self::new()
@@ -175,13 +431,78 @@ mod tests {
     #[test]
     fn test_monitor_creation() {
         let monitor = ClipboardMonitor::new();
-        assert_eq!(monitor.poll_interval_ms, 500);
+        assert_eq!(monitor.poll_interval_ms(), 500);
     }
 
     #[test]
     fn test_custom_interval() {
         let monitor = ClipboardMonitor::with_poll_interval(100);
-        assert_eq!(monitor.poll_interval_ms, 100);
+        assert_eq!(monitor.poll_interval_ms(), 100);
+
+        monitor.poll_interval_handle().store(250, Ordering::Relaxed);
+        assert_eq!(monitor.poll_interval_ms(), 250);
+    }
+
+    #[tokio::test]
+    async fn test_poll_timer_adapts_within_two_ticks() {
+        let setting = Arc::new(AtomicU64::new(20));
+        let mut timer = PollTimer::new(Arc::clone(&setting));
+        timer.tick().await;
+        timer.tick().await;
+
+        setting.store(300, Ordering::Relaxed);
+        let start = std::time::Instant::now();
+        timer.tick().await; // Already scheduled at the old period
+        timer.tick().await; // First one at the new period
+        let elapsed = start.elapsed();
+        assert_eq!(timer.current_interval_ms, 300);
+        assert!(elapsed >= Duration::from_millis(300), "took {:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(1000), "took {:?}", elapsed);
+    }
+
+    #[test]
+    fn test_full_channel_drops_changes() {
+        let monitor = ClipboardMonitor::new();
+        let (tx, _rx) = mpsc::channel(DEFAULT_QUEUE_DEPTH); // never consumed
+
+        let sent = (0..64)
+            .filter(|i| {
+                let change = ClipboardChange { change_count: *i, types: Vec::new(), source_app: SourceApp::default() };
+                ClipboardMonitor::send_or_drop(&tx, change, DEFAULT_QUEUE_DEPTH, &monitor.dropped_events)
+            })
+            .count();
+
+        assert_eq!(sent, 32);
+        assert_eq!(monitor.dropped_events(), 32);
+        assert_eq!(monitor.dropped_events_handle().load(Ordering::Relaxed), 32);
+    }
+
+    #[test]
+    fn test_pause_resume_round_trips_through_handle() {
+        let monitor = ClipboardMonitor::new();
+        let paused = monitor.paused_handle();
+        assert!(!monitor.is_paused());
+
+        monitor.pause();
+        assert!(paused.load(Ordering::Relaxed));
+
+        // The menu bar flips the shared flag directly
+        paused.store(false, Ordering::Relaxed);
+        assert!(!monitor.is_paused());
+
+        paused.store(true, Ordering::Relaxed);
+        assert!(monitor.is_paused());
+        monitor.resume();
+        assert!(!paused.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_reset_change_count_reads_pasteboard() {
+        let mut monitor = ClipboardMonitor::new();
+        monitor.last_change_count = -1;
+
+        monitor.reset_change_count();
+        assert_eq!(monitor.last_change_count, ClipboardMonitor::change_count());
     }
 
     #[test]
@@ -189,4 +510,24 @@ mod tests {
         let count = ClipboardMonitor::change_count();
         assert!(count >= 0);
     }
+
+    #[test]
+    fn test_from_pasteboard_reports_types() {
+        // A private pasteboard, so the test never replaces what the user copied
+        let pasteboard = unsafe { NSPasteboard::pasteboardWithUniqueName() };
+        objc2::rc::autoreleasepool(|_| unsafe {
+            pasteboard.clearContents();
+            pasteboard.setString_forType(
+                &NSString::from_str("snapshot test"),
+                &NSString::from_str("public.utf8-plain-text"),
+            );
+        });
+
+        let snapshot = ClipboardChange::from_pasteboard(&pasteboard).expect("pasteboard has content");
+        assert!(!snapshot.types.is_empty());
+        assert!(snapshot.types.iter().any(|t| t == "public.utf8-plain-text"));
+        assert_eq!(snapshot.change_count, unsafe { pasteboard.changeCount() } as i64);
+        // Not in the generated bindings; frees the pasteboard server's copy
+        let _: () = unsafe { objc2::msg_send![&pasteboard, releaseGlobally] };
+    }
 }
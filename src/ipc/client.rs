@@ -0,0 +1,83 @@
+// Client side of the IPC socket, used by the CLI
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::UnixStream;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use crate::ipc::protocol::{IpcRequest, IpcResponse, DEFAULT_LIMIT};
+use crate::storage::{ClipboardItem, ItemId};
+
+pub struct IpcClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl IpcClient {
+    /// Connect to a running daemon's IPC socket
+    pub async fn connect(socket_path: &Path) -> Result<Self, String> {
+        let stream = UnixStream::connect(socket_path).await
+            .map_err(|e| format!("Failed to connect to {:?}: {}", socket_path, e))?;
+        let (reader, writer) = stream.into_split();
+        Ok(IpcClient {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    /// Most recent items (pinned first)
+    pub async fn list(&mut self, limit: i32) -> Result<Vec<ClipboardItem>, String> {
+        self.request_items(IpcRequest::List { limit }).await
+    }
+
+    /// Fuzzy search over recent items, best match first
+    pub async fn search(&mut self, query: &str) -> Result<Vec<ClipboardItem>, String> {
+        self.request_items(IpcRequest::Search {
+            query: query.to_string(),
+            limit: DEFAULT_LIMIT,
+        }).await
+    }
+
+    /// Put an item back on the system clipboard
+    pub async fn paste(&mut self, id: ItemId) -> Result<(), String> {
+        self.request_ok(IpcRequest::Paste { id }).await
+    }
+
+    /// Soft-delete an item (recoverable for 7 days)
+    pub async fn delete(&mut self, id: ItemId) -> Result<(), String> {
+        self.request_ok(IpcRequest::Delete { id }).await
+    }
+
+    /// Open the popup with an item highlighted
+    pub async fn focus(&mut self, id: ItemId) -> Result<(), String> {
+        self.request_ok(IpcRequest::Focus { id }).await
+    }
+
+    async fn request_items(&mut self, request: IpcRequest) -> Result<Vec<ClipboardItem>, String> {
+        match self.send(&request).await? {
+            IpcResponse::Items(items) => Ok(items),
+            IpcResponse::Error { error } => Err(error),
+            other => Err(format!("Unexpected response: {:?}", other)),
+        }
+    }
+
+    async fn request_ok(&mut self, request: IpcRequest) -> Result<(), String> {
+        match self.send(&request).await? {
+            IpcResponse::Ok { ok: true } => Ok(()),
+            IpcResponse::Error { error } => Err(error),
+            other => Err(format!("Unexpected response: {:?}", other)),
+        }
+    }
+
+    async fn send(&mut self, request: &IpcRequest) -> Result<IpcResponse, String> {
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| format!("Failed to encode request: {}", e))?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        let response = self.lines.next_line().await
+            .map_err(|e| format!("Failed to read response: {}", e))?
+            .ok_or("Daemon closed the connection")?;
+        serde_json::from_str(&response)
+            .map_err(|e| format!("Invalid response: {}", e))
+    }
+}
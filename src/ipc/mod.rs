@@ -0,0 +1,8 @@
+// IPC module - Unix socket protocol between the CLI and the running daemon
+pub mod protocol;
+pub mod server;
+pub mod client;
+
+pub use protocol::{IpcRequest, IpcResponse};
+pub use server::{FocusHandler, IpcServer};
+pub use client::IpcClient;
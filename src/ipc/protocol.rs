@@ -0,0 +1,54 @@
+// Newline-delimited JSON messages exchanged over the IPC socket
+use serde::{Deserialize, Serialize};
+use crate::storage::{ClipboardItem, ItemId};
+
+/// Default number of items returned by `list` and `search`
+pub const DEFAULT_LIMIT: i32 = 20;
+
+fn default_limit() -> i32 {
+    DEFAULT_LIMIT
+}
+
+/// A single request line, e.g. `{"cmd":"list","limit":10}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+pub enum IpcRequest {
+    List {
+        #[serde(default = "default_limit")]
+        limit: i32,
+    },
+    Paste {
+        id: ItemId,
+    },
+    Search {
+        query: String,
+        #[serde(default = "default_limit")]
+        limit: i32,
+    },
+    Delete {
+        id: ItemId,
+    },
+    /// Open the popup with this item selected
+    Focus {
+        id: ItemId,
+    },
+}
+
+/// A single response line: an item array, `{"ok":true}` or `{"error":"…"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IpcResponse {
+    Items(Vec<ClipboardItem>),
+    Ok { ok: bool },
+    Error { error: String },
+}
+
+impl IpcResponse {
+    pub fn ok() -> Self {
+        IpcResponse::Ok { ok: true }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        IpcResponse::Error { error: message.into() }
+    }
+}
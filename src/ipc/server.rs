@@ -0,0 +1,183 @@
+// Unix domain socket server so the CLI can talk to the running daemon
+use log::{error, info, warn};
+use objc2_app_kit::NSPasteboard;
+use objc2_foundation::{NSData, NSString};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use crate::clipboard::ClipboardChange;
+use crate::ipc::protocol::{IpcRequest, IpcResponse};
+use crate::storage::{ClipboardItem, Database, Encryptor, ItemId, ItemMetadata};
+use crate::storage::search::SearchEngine;
+use crate::LockExt;
+
+/// Opens the popup on an item; installed by the UI once the popup exists
+pub type FocusHandler = Box<dyn Fn(ItemId) -> Result<(), String> + Send + Sync>;
+
+static FOCUS_HANDLER: OnceLock<FocusHandler> = OnceLock::new();
+
+pub struct IpcServer;
+
+impl IpcServer {
+    /// Bind the socket (owner-only, mode 0700) and serve requests until the runtime shuts down.
+    /// Must be called from within a tokio runtime.
+    pub fn start(
+        socket_path: &Path,
+        db: Arc<Mutex<Database>>,
+        enc: Arc<Mutex<Encryptor>>,
+    ) -> tokio::task::JoinHandle<()> {
+        let socket_path = socket_path.to_path_buf();
+
+        tokio::spawn(async move {
+            // A stale socket from a previous run would make bind fail
+            let _ = std::fs::remove_file(&socket_path);
+
+            let listener = match UnixListener::bind(&socket_path) {
+                Ok(l) => l,
+                Err(e) => {
+                    error!("Failed to bind IPC socket {:?}: {}", socket_path, e);
+                    return;
+                }
+            };
+
+            if let Err(e) = std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o700)) {
+                error!("Failed to restrict IPC socket permissions: {}", e);
+                return;
+            }
+
+            info!("✓ IPC server listening on {:?}", socket_path);
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let db = Arc::clone(&db);
+                        let enc = Arc::clone(&enc);
+                        tokio::spawn(async move {
+                            Self::handle_connection(stream, db, enc).await;
+                        });
+                    }
+                    Err(e) => {
+                        warn!("IPC accept failed: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Install the handler for `focus` requests (only the first call takes effect)
+    pub fn set_focus_handler(handler: FocusHandler) {
+        if FOCUS_HANDLER.set(handler).is_err() {
+            warn!("IPC focus handler already installed");
+        }
+    }
+
+    /// Read one JSON request per line and write one JSON response per line
+    async fn handle_connection(stream: UnixStream, db: Arc<Mutex<Database>>, enc: Arc<Mutex<Encryptor>>) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<IpcRequest>(&line) {
+                Ok(request) => Self::handle_request(request, &db, &enc),
+                Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+            };
+
+            let mut out = serde_json::to_string(&response)
+                .unwrap_or_else(|e| format!("{{\"error\":\"Failed to encode response: {}\"}}", e));
+            out.push('\n');
+
+            if writer.write_all(out.as_bytes()).await.is_err() {
+                break; // Client went away
+            }
+        }
+    }
+
+    fn handle_request(request: IpcRequest, db: &Arc<Mutex<Database>>, enc: &Arc<Mutex<Encryptor>>) -> IpcResponse {
+        let unavailable = || IpcResponse::error("Database unavailable");
+
+        match request {
+            IpcRequest::List { limit } => {
+                let Some(db) = db.lock_or_log() else { return unavailable() };
+                match db.get_recent_items(limit) {
+                    Ok(items) => IpcResponse::Items(items),
+                    Err(e) => IpcResponse::error(format!("Failed to list items: {}", e)),
+                }
+            }
+            IpcRequest::Search { query, limit } => {
+                let Some(db) = db.lock_or_log() else { return unavailable() };
+                match SearchEngine::new().search_database(&db, &query, limit) {
+                    Ok(items) => IpcResponse::Items(items),
+                    Err(e) => IpcResponse::error(format!("Search failed: {}", e)),
+                }
+            }
+            IpcRequest::Paste { id } => {
+                let (item, data) = {
+                    let Some(db) = db.lock_or_log() else { return unavailable() };
+                    let item = match db.get_item(id) {
+                        Ok(item) => item,
+                        Err(e) => return IpcResponse::error(format!("Item #{} not found: {}", id, e)),
+                    };
+                    let Some(enc) = enc.lock_or_log() else { return IpcResponse::error("Encryptor unavailable") };
+                    match db.read_item_data(&item, Some(&enc)) {
+                        Ok(data) => (item, data),
+                        Err(e) => return IpcResponse::error(format!("Failed to read item #{}: {}", id, e)),
+                    }
+                };
+
+                Self::write_to_pasteboard(&item, &data);
+                match ClipboardChange::from_pasteboard(&unsafe { NSPasteboard::generalPasteboard() }) {
+                    Some(snapshot) => {
+                        info!("📋 IPC paste: item #{} ({})", id, snapshot.types.join(", "));
+                        IpcResponse::ok()
+                    }
+                    None => IpcResponse::error(format!("Clipboard is empty after pasting item #{}", id)),
+                }
+            }
+            IpcRequest::Delete { id } => {
+                let Some(db) = db.lock_or_log() else { return unavailable() };
+                match db.soft_delete_item(id) {
+                    Ok(()) => IpcResponse::ok(),
+                    Err(e) => IpcResponse::error(format!("Failed to delete item #{}: {}", id, e)),
+                }
+            }
+            // No database lock here: the popup takes it on the main thread to load items
+            IpcRequest::Focus { id } => match FOCUS_HANDLER.get() {
+                Some(focus) => match focus(id) {
+                    Ok(()) => IpcResponse::ok(),
+                    Err(e) => IpcResponse::error(e),
+                },
+                None => IpcResponse::error("Popup is not available"),
+            },
+        }
+    }
+
+    fn write_to_pasteboard(item: &ClipboardItem, data: &[u8]) {
+        objc2::rc::autoreleasepool(|_| unsafe {
+            let pb = NSPasteboard::generalPasteboard();
+            pb.clearContents();
+
+            match item.data_type.as_str() {
+                "image" => {
+                    let ns_data = NSData::with_bytes(data);
+                    let uti = item.metadata.as_deref()
+                        .map(|m| ItemMetadata::from_json(m).image_pasteboard_type())
+                        .unwrap_or("public.png");
+                    let type_str = NSString::from_str(uti);
+                    pb.setData_forType(Some(&ns_data), &type_str);
+                }
+                _ => {
+                    let text = String::from_utf8_lossy(data);
+                    let ns_str = NSString::from_str(&text);
+                    let type_str = NSString::from_str("public.utf8-plain-text");
+                    pb.setString_forType(&ns_str, &type_str);
+                }
+            }
+        })
+    }
+}
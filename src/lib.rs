@@ -1,3 +1,52 @@
 // Clipboard Manager Library
 pub mod clipboard;
+pub mod ipc;
 pub mod storage;
+
+use std::sync::{Mutex, MutexGuard};
+
+/// Mutex locking that survives poisoning and says where it happened
+pub trait LockExt<T> {
+    /// Lock the mutex. If a previous holder panicked, log the caller's
+    /// location and recover the guard instead of propagating the poison.
+    fn lock_or_log(&self) -> Option<MutexGuard<'_, T>>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    #[track_caller]
+    fn lock_or_log(&self) -> Option<MutexGuard<'_, T>> {
+        match self.lock() {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                let caller = std::panic::Location::caller();
+                log::error!("[{}:{}] Mutex poisoned: {}", caller.file(), caller.line(), e);
+                self.clear_poison();
+                Some(e.into_inner())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lock_or_log_recovers_poisoned_mutex() {
+        let mutex = Arc::new(Mutex::new(41));
+        let poisoner = Arc::clone(&mutex);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the mutex");
+        }).join();
+        assert!(mutex.is_poisoned());
+
+        let mut guard = mutex.lock_or_log().expect("poisoned mutex should still lock");
+        *guard += 1;
+        drop(guard);
+
+        assert_eq!(*mutex.lock_or_log().unwrap(), 42);
+        assert!(!mutex.is_poisoned());
+    }
+}
@@ -2,19 +2,31 @@
 // Phase 4: Menu Bar UI
 
 mod clipboard;
+mod ipc;
 mod storage;
 mod ui;
 
 use cacao::appkit::App;
-use clipboard::ClipboardMonitor;
-use storage::{Database, DataProcessor, Encryptor, LicenseManager};
+use clipboard::{ClipboardChange, ClipboardMonitor};
+use clipboard_manager::LockExt;
+use ipc::IpcServer;
+use storage::{AppConfig, Database, DataProcessor, Encryptor, ItemId, Keychain, LicenseManager, PendingItem, SensitivityAnalyzer};
+use storage::{KeyStoreError, KEYCHAIN_ACCOUNT, KEYCHAIN_SERVICE};
 use storage::license::FREE_HISTORY_LIMIT;
+use storage::processor::{ProcessedData, ProcessedDataType};
+use storage::sensitivity::CompiledRules;
 use ui::MenuBarApp;
-use log::{error, info};
+use ui::hotkey::HotkeyManager;
+use block2::{Block, RcBlock};
+use log::{debug, error, info};
+use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, Bool};
+use objc2::{class, msg_send, msg_send_id};
+use objc2_foundation::{NSBundle, NSString};
 use std::fs::File;
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::mpsc;
 
@@ -33,6 +45,302 @@ fn acquire_instance_lock(data_dir: &Path) -> Option<File> {
     }
 }
 
+#[link(name = "UserNotifications", kind = "framework")]
+extern "C" {}
+
+/// Title of the notification posted for a captured sensitive item
+const SENSITIVE_NOTIFICATION_TITLE: &str = "ClipVault: Sensitive item captured";
+/// Characters of the preview shown in that notification
+const SENSITIVE_PREVIEW_CHARS: usize = 20;
+
+/// Notification body for a sensitive item: the start of its preview followed
+/// by `***`. Short previews show at most half their characters, so the whole
+/// secret never appears on screen.
+fn sensitive_notification_body(preview: &str) -> String {
+    let char_count = preview.chars().count();
+    let shown: String = preview.chars()
+        .take(SENSITIVE_PREVIEW_CHARS.min(char_count / 2))
+        .collect();
+    format!("{}***", shown)
+}
+
+/// UNUserNotificationCenter throws when the process has no bundle (e.g. `cargo run`)
+fn notification_center() -> Option<Retained<AnyObject>> {
+    if unsafe { NSBundle::mainBundle().bundleIdentifier() }.is_none() {
+        debug!("Not running from an app bundle, notifications unavailable");
+        return None;
+    }
+    Some(unsafe { msg_send_id![class!(UNUserNotificationCenter), currentNotificationCenter] })
+}
+
+/// Ask for permission to post alerts. macOS only prompts the first time;
+/// later calls return the user's earlier answer.
+fn request_notification_permission() {
+    let Some(center) = notification_center() else { return };
+    let handler = RcBlock::new(|granted: Bool, _error: *mut AnyObject| {
+        if !granted.as_bool() {
+            info!("  Notifications not allowed, sensitive captures won't be announced");
+        }
+    });
+    // UNAuthorizationOptionSound | UNAuthorizationOptionAlert
+    let options: usize = (1 << 1) | (1 << 2);
+    unsafe {
+        let _: () = msg_send![&center, requestAuthorizationWithOptions: options, completionHandler: &*handler];
+    }
+}
+
+/// Post a local notification that a sensitive item was captured, showing
+/// only a redacted `preview`
+fn notify_sensitive(preview: &str) {
+    let Some(center) = notification_center() else { return };
+    unsafe {
+        let content: Retained<AnyObject> = msg_send_id![class!(UNMutableNotificationContent), new];
+        let title = NSString::from_str(SENSITIVE_NOTIFICATION_TITLE);
+        let body = NSString::from_str(&sensitive_notification_body(preview));
+        let _: () = msg_send![&content, setTitle: &*title];
+        let _: () = msg_send![&content, setBody: &*body];
+
+        let identifier = NSString::from_str(&format!("sensitive-capture-{}", chrono::Utc::now().timestamp_millis()));
+        let no_trigger: *const AnyObject = std::ptr::null();
+        let request: Retained<AnyObject> = msg_send_id![
+            class!(UNNotificationRequest),
+            requestWithIdentifier: &*identifier,
+            content: &*content,
+            trigger: no_trigger
+        ];
+        let no_handler: Option<&Block<dyn Fn(*mut AnyObject)>> = None;
+        let _: () = msg_send![&center, addNotificationRequest: &*request, withCompletionHandler: no_handler];
+    }
+}
+
+/// True when the app in front at the time of the copy is in `excluded_bundle_ids`
+fn is_from_excluded_app(change: &ClipboardChange, config: &AppConfig) -> bool {
+    change.source_app.bundle_id.as_deref().is_some_and(|bundle_id| config.is_excluded(bundle_id))
+}
+
+/// Compile the sensitivity rules in `config` and use them from now on.
+/// Rules that don't compile leave the ones in use in place.
+fn install_sensitivity_rules(config: &AppConfig) {
+    match CompiledRules::compile(&config.sensitivity_rules()) {
+        Ok(rules) => {
+            if !config.custom_sensitivity_rules.is_empty() {
+                info!("  Loaded {} custom sensitivity rules", config.custom_sensitivity_rules.len());
+            }
+            SensitivityAnalyzer::install_rules(Arc::new(rules));
+        }
+        Err(e) => error!("Keeping the current sensitivity rules: {}", e),
+    }
+}
+
+/// Read what was copied: a file reference first, then image data, then text
+fn read_pasteboard(change: &ClipboardChange, config: &AppConfig) -> Option<ProcessedData> {
+    if let Some(file_url) = ClipboardMonitor::get_file_url() {
+        info!("   📄 File reference detected");
+        match DataProcessor::process_file_reference(&file_url) {
+            Ok(processed) => Some(processed),
+            Err(e) => {
+                error!("   ✗ Failed to process file reference: {}", e);
+                None
+            }
+        }
+    } else if let Some((image_data, uti_type)) = ClipboardMonitor::get_image() {
+        info!("   🖼️  Image detected: {} ({} bytes)", uti_type, image_data.len());
+        if image_data.len() > config.max_image_size_bytes {
+            debug!("   Skipped image: {} bytes exceeds {}", image_data.len(), config.max_image_size_bytes);
+            None
+        } else {
+            match DataProcessor::process_image_with_config(&image_data, &uti_type, config) {
+                Ok(processed) => Some(processed),
+                Err(e) => {
+                    error!("   ✗ Failed to process image: {}", e);
+                    None
+                }
+            }
+        }
+    } else if let Some(text) = ClipboardMonitor::get_string() {
+        Some(DataProcessor::process_text_with_config(&text, &change.types, config))
+    } else {
+        info!("   (Unsupported content type)");
+        None
+    }
+}
+
+/// What `change` should add to history, if anything. Copies from excluded
+/// apps are dropped before `read` touches the pasteboard; excluded types and
+/// out-of-bounds sizes are dropped after.
+fn capture_change(
+    change: &ClipboardChange,
+    config: &AppConfig,
+    read: impl FnOnce() -> Option<ProcessedData>,
+) -> Option<ProcessedData> {
+    if is_from_excluded_app(change, config) {
+        info!("   (Skipped: copied from an excluded app)");
+        return None;
+    }
+
+    read()
+        // Skip types the user chose not to keep
+        .filter(|processed| {
+            let keep = config.should_store(processed.data_type.as_str());
+            if !keep {
+                debug!("   Skipping excluded data type '{}'", processed.data_type.as_str());
+            }
+            keep
+        })
+        // Skip accidental tiny selections and oversized blobs
+        .filter(|processed| {
+            let size = processed.blob.len();
+            let keep = config.blob_size_allowed(size);
+            if !keep {
+                debug!("   Skipped item: size {} bytes outside [{}, {}]",
+                       size, config.min_blob_size_bytes, config.max_blob_size_bytes);
+            }
+            keep
+        })
+}
+
+/// Save a captured item: bump an identical one already in history, or insert
+/// it (sealed if sensitive) and trim history to the tier's limit
+fn store_processed(
+    db: &mut Database,
+    encryptor: &Mutex<Encryptor>,
+    mut processed: ProcessedData,
+    change: &ClipboardChange,
+    config: &AppConfig,
+    is_pro: bool,
+) {
+    if processed.data_type == ProcessedDataType::Email && config.email_is_sensitive {
+        processed.is_sensitive = true;
+    }
+
+    // In free tier, disable sensitive detection (Pro feature)
+    if !is_pro {
+        processed.is_sensitive = false;
+    }
+
+    let timestamp = chrono::Utc::now().timestamp();
+
+    // Sensitive blobs are encrypted, and hashed with a key rather than plain SHA-256
+    let is_encrypted = processed.is_sensitive;
+    let content_hash = encryptor.lock_or_log()
+        .and_then(|enc| Database::content_hash_for(&processed.blob, is_encrypted, &enc));
+
+    // Same content copied again: bump the existing item instead of storing it twice
+    let existing = match content_hash.as_deref().map(|hash| db.find_item_by_content_hash(hash)) {
+        Some(Ok(existing)) => existing,
+        Some(Err(e)) => {
+            error!("   ✗ Failed to look up duplicates: {}", e);
+            None
+        }
+        None => None,
+    };
+
+    if let Some(existing) = existing {
+        match db.record_repeat_copy(existing.id, timestamp) {
+            Ok(()) => info!("   ♻️  Same as item #{} (copied {} times)",
+                            existing.id, existing.copy_count + 1),
+            Err(e) => error!("   ✗ Failed to update item #{}: {}", existing.id, e),
+        }
+        return;
+    }
+
+    // Sensitive blobs are encrypted with the item ID as AAD, which is only
+    // known after insert: store a placeholder, then overwrite it
+    let blob_data = if is_encrypted { Vec::new() } else { processed.blob.clone() };
+    let pending = PendingItem::from_processed(&processed, blob_data, timestamp, is_encrypted)
+        .with_content_hash(content_hash)
+        .with_source_app(&change.source_app);
+
+    let stored = db.store_item_transactional(&pending)
+        .map_err(|e| format!("Failed to store item: {}", e))
+        .and_then(|(blob_id, item_id)| {
+            if !is_encrypted {
+                return Ok((blob_id, item_id));
+            }
+            // Hold the encryptor until the blob is written, so a key
+            // rotation can't slip in between and strand it under the old key
+            let sealed = encryptor.lock_or_log()
+                .ok_or_else(|| "Encryptor unavailable".to_string())
+                .and_then(|enc| {
+                    let stored = db.seal_blob(blob_id, item_id, &processed.blob, &enc)
+                        .map_err(|e| format!("Failed to store encrypted blob: {}", e))?;
+                    info!("   🔐 Encrypted sensitive data ({} → {} bytes)",
+                          processed.blob.len(), stored);
+                    Ok(())
+                });
+            if let Err(e) = sealed {
+                // Never leave a sensitive item behind without its data
+                let _ = db.delete_item(item_id);
+                return Err(format!("Encryption failed: {}, item not stored", e));
+            }
+            Ok((blob_id, item_id))
+        });
+    match stored {
+        Ok((blob_id, item_id)) => {
+            let sensitive_marker = if processed.is_sensitive { " 🔒" } else { "" };
+            info!("   ✓ Stored as {} item #{} (blob #{}){}",
+                  processed.data_type.as_str(), item_id, blob_id, sensitive_marker);
+            if let Some(preview) = &processed.preview_text {
+                info!("   Preview: {}", preview);
+            }
+            if processed.is_sensitive && config.notify_sensitive {
+                notify_sensitive(processed.preview_text.as_deref().unwrap_or(""));
+            }
+
+            // Only a new item can push history past the limit for the current tier
+            let history_limit = config.effective_history_limit(is_pro);
+            if history_limit < usize::MAX {
+                if let Err(e) = db.enforce_max_items(history_limit) {
+                    error!("   ✗ Failed to enforce history limit: {}", e);
+                }
+            }
+        }
+        Err(e) => error!("   ✗ {}", e),
+    }
+}
+
+/// Hash items stored before content hashes existed, one batch per lock so
+/// captures and the UI aren't held up on a large history, then merge the
+/// duplicates the schema upgrade had to leave alone
+fn backfill_content_hashes(db: &Mutex<Database>) {
+    match db.lock_or_log().map(|db| db.content_hash_migration_pending()) {
+        Some(Ok(true)) => {}
+        Some(Ok(false)) | None => return,
+        Some(Err(e)) => {
+            error!("  Failed to check content hash migration: {}", e);
+            return;
+        }
+    }
+
+    let mut updated = 0;
+    let mut last_id = ItemId(0);
+    loop {
+        let Some(db) = db.lock_or_log() else { return };
+        match db.compute_content_hash_batch(last_id) {
+            Ok(Some((count, batch_last_id))) => {
+                updated += count;
+                last_id = batch_last_id;
+                info!("  Computed content hashes for {} existing items...", updated);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("  Content hash migration failed (will retry next launch): {}", e);
+                return;
+            }
+        }
+    }
+
+    let Some(mut db) = db.lock_or_log() else { return };
+    if let Err(e) = db.finish_content_hash_migration() {
+        error!("  Failed to record content hash migration: {}", e);
+        return;
+    }
+    info!("✓ Content hashes computed for {} existing items", updated);
+    if let Err(e) = db.recount_all_copy_counts() {
+        error!("  Failed to merge duplicates after hashing: {}", e);
+    }
+}
+
 fn main() {
     // Initialize logger
     env_logger::Builder::from_default_env()
@@ -42,10 +350,17 @@ fn main() {
     info!("🚀 Clipboard Manager - Phase 4: Menu Bar UI");
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
-    // Initialize data directory
-    let data_dir = dirs::data_local_dir()
+    // Initialize data directory (--data-dir, then the bootstrap file, then the default)
+    let default_data_dir = dirs::data_local_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("clipboard-manager");
+    let args: Vec<String> = std::env::args().collect();
+    let data_dir = storage::config::resolve_data_dir(
+        &args,
+        storage::config::read_bootstrap_data_dir(),
+        default_data_dir,
+    );
+    info!("📁 Data directory: {}", data_dir.display());
 
     std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
 
@@ -71,11 +386,12 @@ fn main() {
 
     // Initialize database
     let db_path = data_dir.join("clipboard.db");
-    let db = Database::new(db_path.clone())
+    let mut db = Database::new(db_path.clone())
         .expect("Failed to initialize database");
 
     info!("✓ Database initialized at: {}", db_path.display());
     info!("  Items in history: {}", db.count_items().unwrap_or(0));
+    info!("  {} sensitive items in history", db.count_sensitive_items().unwrap_or(0));
     info!("  Database size: {} KB", db.get_db_size().unwrap_or(0) / 1024);
 
     // Purge soft-deleted items older than 7 days
@@ -85,18 +401,21 @@ fn main() {
         Err(e) => log::error!("  Failed to purge deleted items: {}", e),
     }
 
-    // Initialize encryptor
+    // Initialize encryptor: the key lives in the Keychain, with the key file as a fallback
     let key_path = data_dir.join("encryption.key");
-    let encryptor = Encryptor::new(key_path)
-        .expect("Failed to initialize encryptor");
-
-    info!("✓ Encryption initialized");
-
-    // Run cleanup on startup (remove items older than 7 days)
-    match db.cleanup_old_items(7) {
-        Ok(count) if count > 0 => info!("  Cleaned up {} old items", count),
-        _ => {}
-    }
+    let keychain = Arc::new(Keychain::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT));
+    let encryptor = match Encryptor::open(&key_path, keychain) {
+        Ok(encryptor) => {
+            info!("✓ Encryption initialized");
+            encryptor
+        }
+        // Never make up a new key here: existing sensitive items would be lost for good
+        Err(KeyStoreError::Denied(e)) => {
+            error!("✗ Encryption key unavailable ({}); sensitive items will not be saved this session", e);
+            Encryptor::disabled()
+        }
+        Err(KeyStoreError::Failed(e)) => panic!("Failed to initialize encryptor: {}", e),
+    };
 
     // Check license status
     let pro_flag = Arc::new(AtomicBool::new(false));
@@ -105,7 +424,21 @@ fn main() {
     if is_pro_on_startup {
         info!("✓ ClipVault Pro license active");
     } else {
-        info!("  ClipVault Free (25 item limit, no encryption)");
+        info!("  ClipVault Free ({} item limit, no encryption)", FREE_HISTORY_LIMIT);
+    }
+
+    // Run cleanup on startup (remove items past their retention period for this tier)
+    let config_generation = AppConfig::save_generation();
+    let config = AppConfig::load(&data_dir);
+    install_sensitivity_rules(&config);
+    if config.notify_sensitive {
+        request_notification_permission();
+    }
+    db.set_compression(config.compress_blobs);
+    let retention = config.effective_retention_policy(is_pro_on_startup);
+    match db.cleanup_with_policy(&retention, config.sensitive_retention_days) {
+        Ok(count) if count > 0 => info!("  Cleaned up {} old items", count),
+        _ => {}
     }
 
     info!("");
@@ -115,147 +448,103 @@ fn main() {
     let db_shared = Arc::new(Mutex::new(db));
     let encryptor_shared = Arc::new(Mutex::new(encryptor));
 
+    // Scheduled maintenance: vacuum weekly (or when oversized) off the main thread
+    let db_maintenance = Arc::clone(&db_shared);
+    std::thread::spawn(move || {
+        let Some(db) = db_maintenance.lock_or_log() else { return };
+        match db.needs_vacuum() {
+            Ok(true) => {
+                if let Err(e) = db.vacuum() {
+                    error!("  Failed to vacuum database: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => error!("  Failed to check vacuum schedule: {}", e),
+        }
+    });
+
     // Clone for background thread
     let db_clone = Arc::clone(&db_shared);
     let encryptor_clone = Arc::clone(&encryptor_shared);
     let pro_flag_monitor = Arc::clone(&pro_flag);
+    let socket_path = data_dir.join("clipvault.sock");
+    let config_dir = data_dir.clone();
+    let queue_depth = config.monitor_queue_depth;
+    let mut monitor_config = config.clone();
+    let mut monitor_config_generation = config_generation;
+
+    // Shutdown coordination: the app delegate sets `shutdown_requested` on quit
+    // and waits on `in_flight` until the current item has been stored
+    let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let in_flight = Arc::new((Mutex::new(false), Condvar::new()));
+    let shutdown_monitor = Arc::clone(&shutdown_requested);
+    let in_flight_monitor = Arc::clone(&in_flight);
+
+    // Created here so the menu bar can share its pause flag and poll interval
+    let mut monitor = ClipboardMonitor::with_poll_interval(config.polling_interval_ms);
+    let monitoring_paused = monitor.paused_handle();
+    let poll_interval = monitor.poll_interval_handle();
 
     // Spawn background thread for clipboard monitoring
     std::thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            let (tx, mut rx) = mpsc::unbounded_channel();
-            let mut monitor = ClipboardMonitor::new();
+            let (tx, mut rx) = mpsc::channel(queue_depth);
+            let dropped_events = monitor.dropped_events_handle();
+            // Kept for the life of this thread so the observers stay registered
+            let _sleep_wake_observers = monitor.observe_sleep_wake();
 
-            info!("✓ Clipboard monitor initialized (polling every 500ms)");
+            info!("✓ Clipboard monitor initialized (polling every {}ms)", monitor.poll_interval_ms());
             info!("   Auto-detecting and encrypting sensitive data");
             info!("");
 
+            // IPC socket for the CLI (list/paste/search/delete)
+            let _ipc_handle = IpcServer::start(
+                &socket_path,
+                Arc::clone(&db_clone),
+                Arc::clone(&encryptor_clone),
+            );
+
+            // One-time backfill of content hashes for items stored before dedup by hash
+            let db_migration = Arc::clone(&db_clone);
+            tokio::task::spawn_blocking(move || backfill_content_hashes(&db_migration));
+
             // Spawn monitor task (runs forever)
             let monitor_handle = tokio::spawn(async move {
-                monitor.start(tx).await;
+                monitor.start_bounded(tx, queue_depth).await;
             });
 
             // Process clipboard changes and store them
             let mut item_count = 0;
             while let Some(change) = rx.recv().await {
+                if let Some(mut busy) = in_flight_monitor.0.lock_or_log() {
+                    *busy = true;
+                }
                 item_count += 1;
 
                 info!("📋 Clipboard changed (count: {})", change.change_count);
                 info!("   Types: {:?}", change.types);
+                if let Some(app) = &change.source_app.name {
+                    info!("   From: {}", app);
+                }
 
-                // Try to get image data first
-                let processed_opt = if let Some((image_data, uti_type)) = ClipboardMonitor::get_image() {
-                    info!("   🖼️  Image detected: {} ({} bytes)", uti_type, image_data.len());
-                    match DataProcessor::process_image(&image_data, &uti_type) {
-                        Ok(processed) => Some(processed),
-                        Err(e) => {
-                            error!("   ✗ Failed to process image: {}", e);
-                            None
-                        }
+                // Settings saved from the menu take effect from the next change on
+                let generation = AppConfig::save_generation();
+                if generation != monitor_config_generation {
+                    monitor_config_generation = generation;
+                    monitor_config = AppConfig::load(&config_dir);
+                    install_sensitivity_rules(&monitor_config);
+                    if let Some(mut db) = db_clone.lock_or_log() {
+                        db.set_compression(monitor_config.compress_blobs);
                     }
-                } else if let Some(text) = ClipboardMonitor::get_string() {
-                    // Process text data
-                    Some(DataProcessor::process_text(&text, &change.types))
-                } else {
-                    info!("   (Unsupported content type)");
-                    None
-                };
+                }
+                let config = &monitor_config;
 
-                // Store processed data
-                if let Some(mut processed) = processed_opt {
+                // Don't even read the pasteboard for apps the user opted out
+                if let Some(processed) = capture_change(&change, config, || read_pasteboard(&change, config)) {
                     let is_pro = pro_flag_monitor.load(Ordering::Relaxed);
-
-                    // In free tier, disable sensitive detection (Pro feature)
-                    if !is_pro {
-                        processed.is_sensitive = false;
-                    }
-
-                    // Encrypt if sensitive (handle poisoned mutex gracefully)
-                    let (blob_data, is_encrypted) = if processed.is_sensitive {
-                        let enc_result = encryptor_clone.lock();
-                        let enc_guard = match enc_result {
-                            Ok(guard) => Some(guard),
-                            Err(poisoned) => {
-                                error!("   ✗ Encryptor mutex poisoned, recovering...");
-                                Some(poisoned.into_inner())
-                            }
-                        };
-                        if let Some(enc) = enc_guard {
-                            match enc.encrypt(&processed.blob) {
-                                Ok(encrypted) => {
-                                    info!("   🔐 Encrypted sensitive data ({} → {} bytes)",
-                                          processed.blob.len(), encrypted.len());
-                                    (encrypted, true)
-                                }
-                                Err(e) => {
-                                    error!("   ✗ Encryption failed: {}, storing unencrypted", e);
-                                    (processed.blob.clone(), false)
-                                }
-                            }
-                        } else {
-                            (processed.blob.clone(), false)
-                        }
-                    } else {
-                        (processed.blob.clone(), false)
-                    };
-
-                    // Store to database (handle poisoned mutex gracefully)
-                    let db_result = db_clone.lock();
-                    let db = match db_result {
-                        Ok(guard) => guard,
-                        Err(poisoned) => {
-                            error!("   ✗ Database mutex poisoned, recovering...");
-                            poisoned.into_inner()
-                        }
-                    };
-                    {
-                        // Remove existing duplicates before inserting the new entry
-                        let prev_copy_count = match db.remove_duplicates(
-                            processed.preview_text.as_deref(),
-                            processed.data_type.as_str(),
-                        ) {
-                            Ok((_removed, prev_count)) => prev_count,
-                            Err(e) => {
-                                error!("   ✗ Failed to remove duplicates: {}", e);
-                                0
-                            }
-                        };
-
-                        match db.store_blob(&blob_data) {
-                            Ok(blob_id) => {
-                                let timestamp = chrono::Utc::now().timestamp();
-                                match db.store_item(
-                                    timestamp,
-                                    processed.data_type.as_str(),
-                                    processed.is_sensitive,
-                                    is_encrypted,
-                                    processed.preview_text.as_deref(),
-                                    processed.blob.len() as i64,
-                                    blob_id,
-                                    processed.metadata.as_deref(),
-                                    prev_copy_count + 1,
-                                ) {
-                                    Ok(item_id) => {
-                                        let sensitive_marker = if processed.is_sensitive { " 🔒" } else { "" };
-                                        info!("   ✓ Stored as {} item #{} (blob #{}){}",
-                                              processed.data_type.as_str(), item_id, blob_id, sensitive_marker);
-                                        if let Some(preview) = &processed.preview_text {
-                                            info!("   Preview: {}", preview);
-                                        }
-                                    }
-                                    Err(e) => error!("   ✗ Failed to store item metadata: {}", e),
-                                }
-                            }
-                            Err(e) => error!("   ✗ Failed to store blob: {}", e),
-                        }
-
-                        // Enforce history limit for free tier
-                        if !is_pro {
-                            if let Err(e) = db.enforce_history_limit(FREE_HISTORY_LIMIT) {
-                                error!("   ✗ Failed to enforce history limit: {}", e);
-                            }
-                        }
+                    if let Some(mut db) = db_clone.lock_or_log() {
+                        store_processed(&mut db, &encryptor_clone, processed, &change, config, is_pro);
                     }
                 }
 
@@ -263,25 +552,38 @@ fn main() {
 
                 // Show stats every 10 items (handle poisoned mutex gracefully)
                 if item_count % 10 == 0 {
-                    let db_result = db_clone.lock();
-                    let db_guard = match db_result {
-                        Ok(guard) => Some(guard),
-                        Err(poisoned) => {
-                            error!("   ✗ Database mutex poisoned in stats, recovering...");
-                            Some(poisoned.into_inner())
-                        }
-                    };
-                    if let Some(db) = db_guard {
-                        if let Ok(count) = db.count_items() {
+                    if let Some(db) = db_clone.lock_or_log() {
+                        if let Ok(stats) = db.get_statistics() {
                             if let Ok(size) = db.get_db_size() {
-                                info!("📊 Stats: {} items stored, {} KB database size", count, size / 1024);
+                                let mut by_type: Vec<_> = stats.items_by_type.iter()
+                                    .map(|(data_type, count)| format!("{} {}", count, data_type))
+                                    .collect();
+                                by_type.sort();
+                                info!("📊 Stats: {} items stored ({}), {} sensitive, {} KB of item data, {} KB database size, {} changes dropped",
+                                      stats.total_items, by_type.join(", "), stats.sensitive_items,
+                                      stats.total_blob_bytes / 1024, size / 1024,
+                                      dropped_events.load(Ordering::Relaxed));
                                 info!("");
                             }
                         }
                     }
                 }
+
+                // Item fully stored — wake a pending shutdown, then stop if requested
+                {
+                    let (busy, idle) = &*in_flight_monitor;
+                    if let Some(mut busy) = busy.lock_or_log() {
+                        *busy = false;
+                    }
+                    idle.notify_all();
+                }
+                if shutdown_monitor.load(Ordering::Relaxed) {
+                    info!("Shutdown requested, stopping clipboard processing");
+                    break;
+                }
             }
 
+            monitor_handle.abort();
             monitor_handle.await.ok();
         });
     });
@@ -289,20 +591,41 @@ fn main() {
     // Create menu bar app with database and encryptor access
     // Need to create separate connections for UI thread
     let db_path2 = data_dir.join("clipboard.db");
-    let db_for_ui = Database::new(db_path2)
+    let mut db_for_ui = Database::new(db_path2)
         .expect("Failed to initialize database for UI");
-
-    let key_path2 = data_dir.join("encryption.key");
-    let encryptor_for_ui = Encryptor::new(key_path2)
-        .expect("Failed to initialize encryptor for UI");
-
-    let app = MenuBarApp::new(db_for_ui, encryptor_for_ui, data_dir, pro_flag);
+    db_for_ui.set_compression(config.compress_blobs);
+
+    // The UI shares the monitor's encryptor, so a key rotation reaches both
+    let app = MenuBarApp::new(
+        db_for_ui,
+        Arc::clone(&encryptor_shared),
+        data_dir,
+        pro_flag,
+        monitoring_paused,
+        poll_interval,
+        shutdown_requested,
+        in_flight,
+    );
 
     info!("Launching menu bar app...");
 
+    // IPC `focus`: open the popup on an item (runs on the main thread)
+    let popup_for_ipc = app.get_popup_arc();
+    IpcServer::set_focus_handler(Box::new(move |id| {
+        let popup = Arc::clone(&popup_for_ipc);
+        dispatch::Queue::main().exec_sync(move || {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                match popup.lock_or_log() {
+                    Some(mut popup) => popup.jump_to_item(id),
+                    None => Err("Popup is unavailable".to_string()),
+                }
+            }))
+            .unwrap_or_else(|_| Err("Popup panicked while focusing item".to_string()))
+        })
+    }));
+
     // Start background thread that polls global hotkey events directly
-    // and dispatches toggle to main thread
-    let popup_for_polling = app.get_popup_arc();
+    // and dispatches their actions to the main thread
     std::thread::spawn(move || {
         use global_hotkey::GlobalHotKeyEvent;
         use std::time::{Duration, Instant};
@@ -324,14 +647,11 @@ fn main() {
                     last_toggle = now;
 
                     // Dispatch to main thread using dispatch queue
-                    let popup_clone = Arc::clone(&popup_for_polling);
-
+                    let hotkey_id = event.id;
                     Queue::main().exec_async(move || {
                         // Catch any panics to prevent crashes through Obj-C boundary
                         let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                            if let Ok(mut popup) = popup_clone.lock() {
-                                popup.toggle();
-                            }
+                            HotkeyManager::dispatch(hotkey_id);
                         }));
                     });
                 } else {
@@ -346,3 +666,68 @@ fn main() {
     // Run the app (this blocks)
     App::new("com.clipboard-manager.app", app).run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensitive_notification_body_is_redacted() {
+        let token = "sk-proj-abcdefghijklmnopqrstuvwxyz0123456789";
+        let body = sensitive_notification_body(token);
+        assert_eq!(body, "sk-proj-abcdefghijkl***");
+        assert!(!body.contains(token));
+
+        // A short secret only shows its first half
+        assert_eq!(sensitive_notification_body("hunter2!"), "hunt***");
+        assert_eq!(sensitive_notification_body("pässwörd"), "päss***");
+        assert_eq!(sensitive_notification_body(""), "***");
+    }
+
+    #[test]
+    fn test_copies_from_excluded_apps_are_not_stored() {
+        let config = AppConfig::merge(AppConfig::default(), serde_json::json!({
+            "excluded_bundle_ids": ["com.1password.1password"]
+        }));
+        let change = |bundle_id: Option<&str>| ClipboardChange {
+            change_count: 1,
+            types: Vec::new(),
+            source_app: storage::SourceApp { name: None, bundle_id: bundle_id.map(str::to_string) },
+        };
+        let changes = [
+            (change(Some("com.1password.1password")), "hunter2"),
+            (change(Some("com.apple.Safari")), "https://example.com"),
+            (change(None), "unknown source"),
+        ];
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let encryptor = Mutex::new(Encryptor::new(temp_dir.path().join("key")).unwrap());
+        let mut db = Database::new_in_memory().unwrap();
+        for (change, text) in &changes {
+            let read = || Some(DataProcessor::process_text(text, &change.types));
+            if let Some(processed) = capture_change(change, &config, read) {
+                store_processed(&mut db, &encryptor, processed, change, &config, false);
+            }
+        }
+
+        assert_eq!(db.count_items().unwrap(), 2);
+        let previews: Vec<_> = db.get_recent_items(10).unwrap().into_iter()
+            .filter_map(|item| item.preview_text)
+            .collect();
+        assert!(!previews.iter().any(|preview| preview.contains("hunter2")));
+    }
+
+    #[test]
+    fn test_excluded_app_copy_never_reads_pasteboard() {
+        let config = AppConfig::merge(AppConfig::default(), serde_json::json!({
+            "excluded_bundle_ids": ["com.1password.1password"]
+        }));
+        let change = ClipboardChange {
+            change_count: 1,
+            types: Vec::new(),
+            source_app: storage::SourceApp { name: None, bundle_id: Some("com.1password.1password".to_string()) },
+        };
+        let read = || -> Option<ProcessedData> { panic!("read the pasteboard for an excluded app") };
+        assert!(capture_change(&change, &config, read).is_none());
+    }
+}
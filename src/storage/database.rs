@@ -1,12 +1,251 @@
 // SQLite database management for clipboard history
-use rusqlite::{Connection, Result, params};
-use std::path::PathBuf;
-use log::info;
+use rusqlite::{Connection, Result, Row, Transaction, params};
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use std::fmt;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use crate::storage::config::{AppConfig, RetentionPolicy};
+use crate::storage::encryption::Encryptor;
+use crate::storage::metadata::{ItemMetadata, SourceApp};
+use crate::storage::migrations::Migrator;
+use crate::storage::processor::ProcessedData;
+use objc2_app_kit::{NSImage, NSWorkspace};
+use objc2_foundation::NSString;
+
+/// Side of the app icons returned by `get_app_icon`, in pixels (16pt at 2x)
+pub const APP_ICON_PIXELS: u32 = 32;
+
+/// Icon used when an app's bundle can't be found
+const GENERIC_APP_TYPE: &str = "com.apple.application-bundle";
+
+/// Config key recording when `compute_content_hash_for_existing_items` finished
+pub const HASH_MIGRATION_KEY: &str = "migration_hash_computed_at";
+
+/// Rows hashed per query (and per progress log line) by the content hash backfill
+const HASH_MIGRATION_BATCH: i64 = 100;
+
+/// Vacuum at least this often during startup maintenance
+const VACUUM_INTERVAL_SECS: i64 = 7 * 86400;
+
+/// Vacuum regardless of schedule once the database grows past this size
+const VACUUM_SIZE_THRESHOLD: i64 = 50 * 1024 * 1024;
+
+macro_rules! item_columns {
+    () => {
+        "id, timestamp, data_type, is_sensitive, is_encrypted, \
+         preview_text, data_size, data_blob_id, metadata, \
+         COALESCE(copy_count, 1), COALESCE(is_pinned, 0), thumbnail_blob_id, \
+         sensitivity_override, tags"
+    };
+}
+
+/// Canonical column order read by `ClipboardItem::from_row`.
+/// Every query that returns items must select exactly these columns.
+pub const ITEM_COLUMNS: &str = item_columns!();
+
+/// `clipboard_data.compression` for blobs stored as-is
+const COMPRESSION_NONE: &str = "none";
+
+/// `clipboard_data.compression` for raw-deflate blobs. Encrypted blobs are
+/// compressed before encryption, so the value describes the plaintext.
+const COMPRESSION_DEFLATE: &str = "deflate";
+
+/// Appended to `DELETE FROM clipboard_data WHERE ...` once an item row is gone:
+/// deduplicated blobs stay while another item still points at them
+const BLOB_UNREFERENCED: &str =
+    "NOT EXISTS (SELECT 1 FROM clipboard_items WHERE data_blob_id = clipboard_data.id)";
+
+/// Rows fetched per query when streaming an export
+const EXPORT_CHUNK_SIZE: u32 = 500;
+
+/// Surface I/O and serialization failures during export through rusqlite's error type
+fn export_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Insert `data` into `clipboard_data`, or return the row already holding the
+/// same bytes (matched by SHA-256 of the uncompressed data). Empty blobs are
+/// placeholders that get overwritten (see `Database::seal_blob`), so they are
+/// never shared.
+fn store_blob_dedup(conn: &Connection, data: &[u8], compress: bool) -> Result<BlobId> {
+    if data.is_empty() {
+        conn.execute("INSERT INTO clipboard_data (data) VALUES (?1)", params![data])?;
+        return Ok(BlobId(conn.last_insert_rowid()));
+    }
+
+    let hash = ProcessedData::hash_content(data);
+    match conn.query_row("SELECT id FROM clipboard_data WHERE hash = ?1 LIMIT 1", params![hash], |row| row.get(0)) {
+        Ok(existing) => return Ok(existing),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {}
+        Err(e) => return Err(e),
+    }
+    let (stored, compression) = encode_blob(data, compress);
+    conn.execute(
+        "INSERT INTO clipboard_data (data, hash, compression) VALUES (?1, ?2, ?3)",
+        params![stored, hash, compression],
+    )?;
+    Ok(BlobId(conn.last_insert_rowid()))
+}
+
+/// Body of `Database::seal_blob`, shared with imports running in a transaction
+fn seal_blob_in(
+    conn: &Connection,
+    blob_id: BlobId,
+    item_id: ItemId,
+    plaintext: &[u8],
+    encryptor: &Encryptor,
+    compress: bool,
+) -> Result<usize> {
+    let (payload, compression) = encode_blob(plaintext, compress);
+    let sealed = encryptor.encrypt_for_item(&payload, item_id)
+        .map_err(|e| crypto_error(format!("Failed to encrypt item #{}: {}", item_id, e)))?;
+    let updated = conn.execute(
+        "UPDATE clipboard_data SET data = ?1, hash = NULL, compression = ?2 WHERE id = ?3",
+        params![sealed, compression, blob_id],
+    )?;
+    if updated == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(sealed.len())
+}
+
+/// `data` as it should be stored, with its `compression` value. Compressed
+/// only when asked to and when it actually saves space.
+fn encode_blob(data: &[u8], compress: bool) -> (Cow<'_, [u8]>, &'static str) {
+    if compress && !data.is_empty() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        if let Ok(compressed) = encoder.write_all(data).and_then(|()| encoder.finish()) {
+            if compressed.len() < data.len() {
+                return (Cow::Owned(compressed), COMPRESSION_DEFLATE);
+            }
+        }
+    }
+    (Cow::Borrowed(data), COMPRESSION_NONE)
+}
+
+/// Undo `encode_blob`
+fn decompress_blob(stored: Vec<u8>, compression: &str) -> Result<Vec<u8>> {
+    match compression {
+        COMPRESSION_NONE => Ok(stored),
+        COMPRESSION_DEFLATE => {
+            let mut data = Vec::new();
+            DeflateDecoder::new(stored.as_slice()).read_to_end(&mut data)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Blob, Box::new(e)))?;
+            Ok(data)
+        }
+        other => Err(rusqlite::Error::FromSqlConversionFailure(
+            1, rusqlite::types::Type::Text, format!("unknown blob compression {:?}", other).into(),
+        )),
+    }
+}
+
+/// Standard (padded) base64, for blobs in history exports
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Inverse of `base64_encode`; `None` if `text` isn't valid padded base64
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for chunk in text.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &c) in chunk[..4 - padding].iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+/// Surface encryption failures during key rotation through rusqlite's error type
+fn crypto_error(e: String) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(e.into())
+}
+
+/// Row ID of a `clipboard_items` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub i64);
+
+/// Row ID of a `clipboard_data` blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BlobId(pub i64);
+
+impl ItemId {
+    /// Convert to an `NSMenuItem` tag
+    pub fn as_tag(&self) -> isize {
+        self.0 as isize
+    }
+
+    /// Recover an item ID from an `NSMenuItem` tag
+    pub fn from_tag(tag: isize) -> Self {
+        ItemId(tag as i64)
+    }
+}
+
+macro_rules! impl_sql_id {
+    ($ty:ident) => {
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl ToSql for $ty {
+            fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+                self.0.to_sql()
+            }
+        }
 
-const SCHEMA_VERSION: i32 = 1;
+        impl FromSql for $ty {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                i64::column_result(value).map($ty)
+            }
+        }
+    };
+}
+
+impl_sql_id!(ItemId);
+impl_sql_id!(BlobId);
 
 pub struct Database {
     conn: Connection,
+    /// Deflate new blobs (see `set_compression`)
+    compress: bool,
+    /// App icon PNGs by bundle ID (see `get_app_icon`)
+    app_icons: RefCell<HashMap<String, Vec<u8>>>,
 }
 
 impl Database {
@@ -14,14 +253,26 @@ impl Database {
     pub fn new(db_path: PathBuf) -> Result<Self> {
         let conn = Connection::open(db_path)?;
 
-        let mut db = Database { conn };
+        let db = Database { conn, compress: false, app_icons: RefCell::new(HashMap::new()) };
+        db.initialize_schema()?;
+
+        Ok(db)
+    }
+
+    /// Open a fresh database that lives only in memory, for tests that
+    /// don't care about the file on disk. Use `new` everywhere else.
+    #[doc(hidden)]
+    pub fn new_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+
+        let db = Database { conn, compress: false, app_icons: RefCell::new(HashMap::new()) };
         db.initialize_schema()?;
 
         Ok(db)
     }
 
     /// Initialize database schema
-    fn initialize_schema(&mut self) -> Result<()> {
+    fn initialize_schema(&self) -> Result<()> {
         // Enable WAL mode for concurrent reads/writes (returns a row, so use query_row)
         let _: String = self.conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))?;
 
@@ -31,116 +282,15 @@ impl Database {
         // Enable foreign keys
         self.conn.execute("PRAGMA foreign_keys = ON", [])?;
 
-        // Create clipboard_items table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS clipboard_items (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp INTEGER NOT NULL,
-                data_type TEXT NOT NULL,
-                is_sensitive BOOLEAN DEFAULT 0,
-                is_encrypted BOOLEAN DEFAULT 0,
-                preview_text TEXT,
-                data_size INTEGER,
-                data_blob_id INTEGER,
-                metadata TEXT,
-                FOREIGN KEY(data_blob_id) REFERENCES clipboard_data(id)
-            )",
-            [],
-        )?;
-
-        // Create indexes for efficient queries
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_timestamp
-             ON clipboard_items(timestamp DESC)",
-            [],
-        )?;
-
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_data_type
-             ON clipboard_items(data_type)",
-            [],
-        )?;
-
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_preview_search
-             ON clipboard_items(preview_text)",
-            [],
-        )?;
-
-        // Create clipboard_data table (blob storage)
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS clipboard_data (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                data BLOB NOT NULL
-            )",
-            [],
-        )?;
-
-        // Create deleted_items table (soft-delete trash, mirrors clipboard_items)
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS deleted_items (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                original_id INTEGER NOT NULL,
-                timestamp INTEGER NOT NULL,
-                deleted_at INTEGER NOT NULL,
-                data_type TEXT NOT NULL,
-                is_sensitive BOOLEAN DEFAULT 0,
-                is_encrypted BOOLEAN DEFAULT 0,
-                preview_text TEXT,
-                data_size INTEGER,
-                deleted_blob_id INTEGER,
-                metadata TEXT
-            )",
-            [],
-        )?;
-
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_deleted_at
-             ON deleted_items(deleted_at)",
-            [],
-        )?;
-
-        // Create deleted_data table (blob storage for soft-deleted items)
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS deleted_data (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                data BLOB NOT NULL
-            )",
-            [],
-        )?;
-
-        // Create config table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS config (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
-
-        // Migration: add copy_count column (ignore error if column already exists)
-        let _ = self.conn.execute(
-            "ALTER TABLE clipboard_items ADD COLUMN copy_count INTEGER DEFAULT 1",
-            [],
-        );
-
-        // Migration: add is_pinned column (ignore error if column already exists)
-        let _ = self.conn.execute(
-            "ALTER TABLE clipboard_items ADD COLUMN is_pinned BOOLEAN DEFAULT 0",
-            [],
-        );
-
-        // Set schema version
-        self.conn.execute(
-            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
-            params!["schema_version", SCHEMA_VERSION.to_string()],
-        )?;
+        // Connection settings above can't change inside a transaction, so they
+        // stay out of the migrations that create and alter the tables
+        let version = Migrator::new().run(&self.conn)?;
 
         // Set default config values
         self.set_config_default("retention_days", "7")?;
         self.set_config_default("polling_interval_ms", "500")?;
 
-        info!("✓ Database schema initialized (version {})", SCHEMA_VERSION);
+        info!("✓ Database schema initialized (version {})", version);
 
         Ok(())
     }
@@ -154,26 +304,97 @@ impl Database {
         Ok(())
     }
 
-    /// Store clipboard data blob
-    pub fn store_blob(&self, data: &[u8]) -> Result<i64> {
+    /// Get a value from the config table
+    pub fn get_config(&self, key: &str) -> Result<Option<String>> {
+        match self.conn.query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set (insert or overwrite) a value in the config table
+    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO clipboard_data (data) VALUES (?1)",
-            params![data],
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+            params![key, value],
         )?;
-        Ok(self.conn.last_insert_rowid())
+        Ok(())
     }
 
-    /// Retrieve clipboard data blob
-    pub fn get_blob(&self, blob_id: i64) -> Result<Vec<u8>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT data FROM clipboard_data WHERE id = ?1"
+    /// Compress blobs stored from now on (set from `AppConfig::compress_blobs`).
+    /// Blobs already stored keep their encoding and stay readable either way.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    /// Store clipboard data blob, reusing an existing blob with identical bytes
+    pub fn store_blob(&self, data: &[u8]) -> Result<BlobId> {
+        store_blob_dedup(&self.conn, data, self.compress)
+    }
+
+    /// Overwrite the contents of an existing blob, uncompressed. Only meant for
+    /// unshared blobs; to seal an item's placeholder use `seal_blob`.
+    pub fn update_blob(&self, blob_id: BlobId, data: &[u8]) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_data SET data = ?1, hash = NULL, compression = 'none' WHERE id = ?2",
+            params![data, blob_id],
         )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
 
-        let data = stmt.query_row(params![blob_id], |row| {
-            row.get(0)
-        })?;
+    /// Encrypt `plaintext` for `item_id` into the item's placeholder blob,
+    /// compressing it first when compression is on. Returns the stored size.
+    pub fn seal_blob(&self, blob_id: BlobId, item_id: ItemId, plaintext: &[u8], encryptor: &Encryptor) -> Result<usize> {
+        seal_blob_in(&self.conn, blob_id, item_id, plaintext, encryptor, self.compress)
+    }
+
+    /// Retrieve clipboard data blob, decompressed. The blob of an encrypted
+    /// item is returned as stored (ciphertext); read it with `read_item_data`.
+    pub fn get_blob(&self, blob_id: BlobId) -> Result<Vec<u8>> {
+        let (data, compression, sealed): (Vec<u8>, String, bool) = self.conn.query_row(
+            "SELECT data, compression,
+                    EXISTS(SELECT 1 FROM clipboard_items WHERE data_blob_id = ?1 AND is_encrypted = 1)
+             FROM clipboard_data WHERE id = ?1",
+            params![blob_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        if sealed {
+            return Ok(data);
+        }
+        decompress_blob(data, &compression)
+    }
+
+    /// An item's original bytes: decrypted with `encryptor` if the item is
+    /// encrypted, then decompressed. Empty while an encrypted item's blob is
+    /// still a placeholder.
+    pub fn read_item_data(&self, item: &ClipboardItem, encryptor: Option<&Encryptor>) -> Result<Vec<u8>> {
+        let (stored, compression): (Vec<u8>, String) = self.conn.query_row(
+            "SELECT data, compression FROM clipboard_data WHERE id = ?1",
+            params![item.data_blob_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if !item.is_encrypted || stored.is_empty() {
+            return decompress_blob(stored, &compression);
+        }
+
+        let encryptor = encryptor
+            .ok_or_else(|| crypto_error(format!("Item #{} is encrypted and the key is unavailable", item.id)))?;
+        let payload = encryptor.decrypt_for_item(&stored, item.id)
+            .map_err(|e| crypto_error(format!("Failed to decrypt item #{}: {}", item.id, e)))?;
+        decompress_blob(payload, &compression)
+    }
 
-        Ok(data)
+    /// PNG bytes of an item's list thumbnail (its `thumbnail_blob_id`)
+    pub fn get_thumbnail(&self, blob_id: BlobId) -> Result<Vec<u8>> {
+        self.get_blob(blob_id)
     }
 
     /// Store clipboard item metadata
@@ -185,14 +406,39 @@ impl Database {
         is_encrypted: bool,
         preview_text: Option<&str>,
         data_size: i64,
-        data_blob_id: i64,
-        metadata: Option<&str>,
+        data_blob_id: BlobId,
+        metadata: Option<&ItemMetadata>,
         copy_count: i64,
-    ) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO clipboard_items
-             (timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, data_blob_id, metadata, copy_count)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    ) -> Result<ItemId> {
+        self.store_item_returning(
+            timestamp, data_type, is_sensitive, is_encrypted, preview_text,
+            data_size, data_blob_id, metadata, copy_count,
+        ).map(|item| item.id)
+    }
+
+    /// `store_item` that returns the stored row (defaults filled in) from the
+    /// insert itself, instead of reading it back with `get_item`
+    #[allow(clippy::too_many_arguments)]
+    pub fn store_item_returning(
+        &self,
+        timestamp: i64,
+        data_type: &str,
+        is_sensitive: bool,
+        is_encrypted: bool,
+        preview_text: Option<&str>,
+        data_size: i64,
+        data_blob_id: BlobId,
+        metadata: Option<&ItemMetadata>,
+        copy_count: i64,
+    ) -> Result<ClipboardItem> {
+        let metadata = metadata.map(|m| m.to_json());
+        self.conn.query_row(
+            concat!(
+                "INSERT INTO clipboard_items
+                 (timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, data_blob_id, metadata, copy_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 RETURNING ", item_columns!()
+            ),
             params![
                 timestamp,
                 data_type,
@@ -204,160 +450,657 @@ impl Database {
                 metadata,
                 copy_count,
             ],
-        )?;
-        Ok(self.conn.last_insert_rowid())
+            ClipboardItem::from_row,
+        )
     }
 
-    /// Get recent clipboard items (limit by count), pinned items first
-    pub fn get_recent_items(&self, limit: i32) -> Result<Vec<ClipboardItem>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, timestamp, data_type, is_sensitive, is_encrypted,
-                    preview_text, data_size, data_blob_id, metadata,
-                    COALESCE(copy_count, 1), COALESCE(is_pinned, 0)
-             FROM clipboard_items
-             ORDER BY is_pinned DESC, timestamp DESC
-             LIMIT ?1"
-        )?;
+    /// Start a write transaction. Taking `&mut self` means no other
+    /// statement can run on this connection until it is committed or dropped.
+    pub fn begin_write_transaction(&mut self) -> Result<DatabaseTransaction<'_>> {
+        Ok(DatabaseTransaction { tx: self.conn.transaction()?, compress: self.compress })
+    }
 
-        let items = stmt.query_map(params![limit], |row| {
-            Ok(ClipboardItem {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                data_type: row.get(2)?,
-                is_sensitive: row.get(3)?,
-                is_encrypted: row.get(4)?,
-                preview_text: row.get(5)?,
-                data_size: row.get(6)?,
-                data_blob_id: row.get(7)?,
-                metadata: row.get(8)?,
-                copy_count: row.get(9)?,
-                is_pinned: row.get(10)?,
-            })
-        })?;
+    /// Store a blob and the item row that references it in one transaction, so
+    /// a failure between the two never leaves an orphaned blob behind
+    pub fn store_item_transactional(&mut self, item: &PendingItem) -> Result<(BlobId, ItemId)> {
+        self.store_pending_item(item, || {})
+    }
 
-        items.collect()
+    /// `store_item_transactional` with a hook that runs between the two inserts
+    fn store_pending_item(&mut self, item: &PendingItem, after_blob: impl FnOnce()) -> Result<(BlobId, ItemId)> {
+        let tx = self.begin_write_transaction()?;
+        let blob_id = tx.store_blob_tx(&item.blob)?;
+        after_blob();
+        let item_id = tx.store_item_tx(item, blob_id)?;
+        tx.commit()?;
+        Ok((blob_id, item_id))
     }
 
-    /// Clean up items older than retention period (in days)
-    pub fn cleanup_old_items(&self, retention_days: i64) -> Result<usize> {
-        let cutoff_timestamp = chrono::Utc::now().timestamp() - (retention_days * 86400);
+    /// Store the metadata row for processed clipboard data whose blob is already
+    /// stored as `blob_id`. `data_size` is the unencrypted size of `processed.blob`.
+    /// The thumbnail, if any, is stored too unless the item is encrypted.
+    pub fn insert_item(
+        &self,
+        processed: &ProcessedData,
+        blob_id: BlobId,
+        timestamp: i64,
+        copy_count: i64,
+        is_encrypted: bool,
+    ) -> Result<ItemId> {
+        let metadata = processed.metadata.as_ref().map(|m| m.to_json());
+        let tags = join_tags(&processed.suggested_tags());
         let tx = self.conn.unchecked_transaction()?;
-
-        // Get blob IDs to delete (scoped so stmt is dropped before commit)
-        let blob_ids: Vec<i64> = {
-            let mut stmt = tx.prepare(
-                "SELECT data_blob_id FROM clipboard_items WHERE timestamp < ?1"
-            )?;
-            let result = stmt.query_map(params![cutoff_timestamp], |row| {
-                row.get(0)
-            })?.collect::<Result<Vec<_>>>()?;
-            result
+        let thumbnail_blob_id = match processed.thumbnail.as_deref() {
+            Some(thumbnail) if !is_encrypted => {
+                tx.execute("INSERT INTO clipboard_data (data) VALUES (?1)", params![thumbnail])?;
+                Some(BlobId(tx.last_insert_rowid()))
+            }
+            _ => None,
         };
-
-        // Delete clipboard items
-        let deleted_items = tx.execute(
-            "DELETE FROM clipboard_items WHERE timestamp < ?1",
-            params![cutoff_timestamp],
+        tx.execute(
+            "INSERT INTO clipboard_items
+             (timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, data_blob_id, metadata, copy_count, content_hash, thumbnail_blob_id, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                timestamp,
+                processed.data_type.as_str(),
+                processed.is_sensitive,
+                is_encrypted,
+                processed.preview_text.as_deref(),
+                processed.blob.len() as i64,
+                blob_id,
+                metadata,
+                copy_count,
+                (!is_encrypted).then_some(&processed.content_hash),
+                thumbnail_blob_id,
+                tags,
+            ],
         )?;
+        let item_id = ItemId(tx.last_insert_rowid());
+        tx.commit()?;
+        Ok(item_id)
+    }
 
-        // Delete orphaned blobs in a single statement when possible
-        if !blob_ids.is_empty() {
-            tx.execute(
-                "DELETE FROM clipboard_data WHERE id IN (SELECT data_blob_id FROM clipboard_items WHERE 0) OR id IN (SELECT value FROM json_each(?1))",
-                params![serde_json::to_string(&blob_ids).unwrap_or_default()],
-            ).or_else(|_| {
-                // Fallback: delete one by one if json_each not available
-                for blob_id in &blob_ids {
-                    tx.execute(
-                        "DELETE FROM clipboard_data WHERE id = ?1",
-                        params![blob_id],
-                    )?;
-                }
-                Ok::<usize, rusqlite::Error>(blob_ids.len())
-            })?;
+    /// `content_hash` as stored for an item holding `data`: the plain SHA-256
+    /// for unencrypted items, and for encrypted ones a keyed hash that only
+    /// `encryptor`'s key can reproduce (`None` without a key). Look items up
+    /// with the same value.
+    pub fn content_hash_for(data: &[u8], is_encrypted: bool, encryptor: &Encryptor) -> Option<String> {
+        if is_encrypted {
+            encryptor.keyed_content_hash(data)
+        } else {
+            Some(ProcessedData::hash_content(data))
         }
+    }
 
-        tx.commit()?;
-
-        if deleted_items > 0 {
-            info!("🗑️  Cleaned up {} old clipboard items", deleted_items);
+    /// Most recent item whose content hashes to `content_hash` (as computed
+    /// by `content_hash_for`), if any
+    pub fn find_item_by_content_hash(&self, content_hash: &str) -> Result<Option<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE content_hash = ?1 ORDER BY timestamp DESC LIMIT 1",
+            ClipboardItem::select_sql()
+        ))?;
+        match stmt.query_row(params![content_hash], ClipboardItem::from_row) {
+            Ok(item) => Ok(Some(item)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
         }
+    }
 
-        Ok(deleted_items)
+    /// Fill in `content_hash` for items stored before the column existed, then
+    /// record completion under `HASH_MIGRATION_KEY`. Runs
+    /// `compute_content_hash_batch` until nothing is left; callers sharing the
+    /// database should run the batches themselves and release it in between.
+    pub fn compute_content_hash_for_existing_items(&self) -> Result<usize> {
+        let mut updated = 0;
+        let mut last_id = ItemId(0);
+        while let Some((count, batch_last_id)) = self.compute_content_hash_batch(last_id)? {
+            updated += count;
+            last_id = batch_last_id;
+            info!("  Computed content hashes for {} existing items...", updated);
+        }
+        self.finish_content_hash_migration()?;
+        Ok(updated)
     }
 
-    /// Soft-delete all clipboard items (move to deleted_items/deleted_data tables)
-    pub fn soft_delete_all_items(&self) -> Result<usize> {
-        let now = chrono::Utc::now().timestamp();
+    /// Hash up to `HASH_MIGRATION_BATCH` items without a content hash and with
+    /// IDs above `after`, saved in one transaction so an interrupted backfill
+    /// picks up where it stopped. Encrypted items are skipped: their hash is of
+    /// the plaintext, which needs the key. Returns how many were hashed and the
+    /// last ID, or `None` once no items are left.
+    pub fn compute_content_hash_batch(&self, after: ItemId) -> Result<Option<(usize, ItemId)>> {
         let tx = self.conn.unchecked_transaction()?;
-
-        // Get all items with their blob IDs (scope stmt so it's dropped before commit)
-        let items: Vec<(i64, i64, String, bool, bool, Option<String>, i64, i64, Option<String>)> = {
+        let batch: Vec<(ItemId, Vec<u8>, String)> = {
             let mut stmt = tx.prepare(
-                "SELECT id, timestamp, data_type, is_sensitive, is_encrypted,
-                        preview_text, data_size, data_blob_id, metadata
-                 FROM clipboard_items"
+                "SELECT i.id, d.data, d.compression FROM clipboard_items i
+                 JOIN clipboard_data d ON d.id = i.data_blob_id
+                 WHERE i.content_hash IS NULL AND COALESCE(i.is_encrypted, 0) = 0 AND i.id > ?1
+                 ORDER BY i.id
+                 LIMIT ?2"
             )?;
-            let result = stmt.query_map([], |row| {
-                Ok((
-                    row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
-                    row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?,
-                ))
-            })?.collect::<Result<Vec<_>>>()?;
-            result
+            let rows = stmt.query_map(params![after, HASH_MIGRATION_BATCH], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+            rows.collect::<Result<Vec<_>>>()?
         };
+        let Some(&(last_id, ..)) = batch.last() else { return Ok(None) };
+        let count = batch.len();
 
-        let count = items.len();
-        if count == 0 {
-            tx.commit()?;
-            return Ok(0);
-        }
-
-        for (id, timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, blob_id, metadata) in &items {
-            // Copy blob data to deleted_data
-            let blob_data: Vec<u8> = tx.query_row(
-                "SELECT data FROM clipboard_data WHERE id = ?1",
-                params![blob_id],
-                |row| row.get(0),
-            )?;
-            tx.execute(
-                "INSERT INTO deleted_data (data) VALUES (?1)",
-                params![blob_data],
-            )?;
-            let deleted_blob_id = tx.last_insert_rowid();
-
-            // Copy item to deleted_items
+        for (item_id, blob, compression) in batch {
+            let blob = decompress_blob(blob, &compression)?;
             tx.execute(
-                "INSERT INTO deleted_items
-                 (original_id, timestamp, deleted_at, data_type, is_sensitive, is_encrypted,
-                  preview_text, data_size, deleted_blob_id, metadata)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                params![id, timestamp, now, data_type, is_sensitive, is_encrypted,
-                        preview_text, data_size, deleted_blob_id, metadata],
+                "UPDATE clipboard_items SET content_hash = ?1 WHERE id = ?2",
+                params![ProcessedData::hash_content(&blob), item_id],
             )?;
         }
+        tx.commit()?;
+        Ok(Some((count, last_id)))
+    }
 
-        // Delete originals
-        let blob_ids: Vec<i64> = items.iter().map(|i| i.7).collect();
-        tx.execute("DELETE FROM clipboard_items", [])?;
-        for blob_id in blob_ids {
-            tx.execute("DELETE FROM clipboard_data WHERE id = ?1", params![blob_id])?;
-        }
+    /// Record that every item stored before content hashes has been hashed
+    pub fn finish_content_hash_migration(&self) -> Result<()> {
+        self.set_config(HASH_MIGRATION_KEY, &chrono::Utc::now().timestamp().to_string())
+    }
 
-        tx.commit()?;
-        info!("🗑️  Soft-deleted {} clipboard items (recoverable for 7 days)", count);
-        Ok(count)
+    /// Whether `compute_content_hash_for_existing_items` still has to run
+    pub fn content_hash_migration_pending(&self) -> Result<bool> {
+        Ok(self.get_config(HASH_MIGRATION_KEY)?.is_none())
     }
 
-    /// Permanently purge deleted items older than 7 days
-    pub fn purge_deleted_items(&self) -> Result<usize> {
-        let cutoff = chrono::Utc::now().timestamp() - (7 * 86400);
-        let tx = self.conn.unchecked_transaction()?;
+    /// Count another copy of an existing item and move it to the top of the history
+    pub fn record_repeat_copy(&self, item_id: ItemId, timestamp: i64) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET copy_count = COALESCE(copy_count, 1) + 1, timestamp = ?1 WHERE id = ?2",
+            params![timestamp, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
 
-        // Get blob IDs of expired deleted items (scoped so stmt is dropped before commit)
-        let blob_ids: Vec<i64> = {
-            let mut stmt = tx.prepare(
-                "SELECT deleted_blob_id FROM deleted_items WHERE deleted_at < ?1"
+    /// Get recent clipboard items (limit by count), pinned items first
+    pub fn get_recent_items(&self, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY is_pinned DESC, timestamp DESC LIMIT ?1",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![limit], ClipboardItem::from_row)?;
+
+        items.collect()
+    }
+
+    /// One page of items in `get_recent_items` order, skipping the first `offset`.
+    /// Ties are broken by ID so pages never overlap or skip items.
+    pub fn get_items_paginated(&self, offset: i64, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY is_pinned DESC, timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![limit, offset], ClipboardItem::from_row)?;
+
+        items.collect()
+    }
+
+    /// Get a single item by ID
+    pub fn get_item(&self, item_id: ItemId) -> Result<ClipboardItem> {
+        self.conn.query_row(
+            &format!("{} WHERE id = ?1", ClipboardItem::select_sql()),
+            params![item_id],
+            ClipboardItem::from_row,
+        )
+    }
+
+    /// Iterate over every item in ID order, `chunk_size` rows at a time.
+    /// Each chunk is its own keyset query, so only one chunk is held in memory.
+    pub fn iter_items(&self, chunk_size: u32) -> impl Iterator<Item = Result<Vec<ClipboardItem>>> + '_ {
+        let mut last_id = ItemId(0);
+        let mut done = false;
+
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let chunk = self.get_items_after(last_id, chunk_size);
+            match &chunk {
+                Ok(items) if items.is_empty() => return None,
+                Ok(items) => {
+                    last_id = items[items.len() - 1].id;
+                    done = items.len() < chunk_size as usize;
+                }
+                Err(_) => done = true,
+            }
+            Some(chunk)
+        })
+    }
+
+    /// Items with an ID greater than `after`, in ID order
+    fn get_items_after(&self, after: ItemId, limit: u32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "{} WHERE id > ?1 ORDER BY id LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![after, limit], ClipboardItem::from_row)?;
+
+        items.collect()
+    }
+
+    /// Export all items as a JSON array, loading the whole history at once.
+    /// Prefer `export_json_streaming` for large databases.
+    pub fn export_json(&self, writer: &mut impl Write) -> Result<usize> {
+        let items = self.get_items_after(ItemId(0), u32::MAX)?;
+        serde_json::to_writer(&mut *writer, &items).map_err(export_error)?;
+        Ok(items.len())
+    }
+
+    /// Export all items as a JSON array, writing one chunk at a time.
+    /// Produces byte-identical output to `export_json`.
+    pub fn export_json_streaming(&self, writer: &mut impl Write) -> Result<usize> {
+        let mut count = 0;
+        writer.write_all(b"[").map_err(export_error)?;
+        for chunk in self.iter_items(EXPORT_CHUNK_SIZE) {
+            for item in chunk? {
+                if count > 0 {
+                    writer.write_all(b",").map_err(export_error)?;
+                }
+                serde_json::to_writer(&mut *writer, &item).map_err(export_error)?;
+                count += 1;
+            }
+        }
+        writer.write_all(b"]").map_err(export_error)?;
+        Ok(count)
+    }
+
+    /// Export every item to a JSON file at `path`, replacing it atomically (the
+    /// array is written to a temp file that is renamed into place).
+    ///
+    /// With `include_blobs`, each entry also carries the item's data as base64
+    /// (see `ExportedItem`). Encrypted data is decrypted with `encryptor`; without
+    /// one those entries are exported without data.
+    pub fn export_json_file(&self, path: &Path, include_blobs: bool, encryptor: Option<&Encryptor>) -> Result<usize> {
+        let file_name = path.file_name()
+            .ok_or_else(|| rusqlite::Error::InvalidPath(path.to_path_buf()))?;
+        let mut tmp_name = file_name.to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let result = self.write_export(&tmp_path, include_blobs, encryptor)
+            .and_then(|count| fs::rename(&tmp_path, path).map(|_| count).map_err(export_error));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        let count = result?;
+
+        info!("📤 Exported {} items to {}", count, path.display());
+        Ok(count)
+    }
+
+    fn write_export(&self, path: &Path, include_blobs: bool, encryptor: Option<&Encryptor>) -> Result<usize> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600); // Owner read/write only: the export holds decrypted data
+        }
+        let file = options.open(path).map_err(export_error)?;
+        #[cfg(unix)]
+        {
+            // `mode` only applies on creation; tighten a temp file left by an earlier run
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600)).map_err(export_error)?;
+        }
+        let mut writer = BufWriter::new(file);
+        let mut count = 0;
+        let mut skipped_encrypted = 0;
+
+        writer.write_all(b"[").map_err(export_error)?;
+        for chunk in self.iter_items(EXPORT_CHUNK_SIZE) {
+            for item in chunk? {
+                let data = if !include_blobs {
+                    None
+                } else if item.is_encrypted && encryptor.is_none() {
+                    skipped_encrypted += 1;
+                    None
+                } else {
+                    // Empty while an encrypted item is still being sealed
+                    Some(self.read_item_data(&item, encryptor)?).filter(|data| !data.is_empty())
+                };
+
+                if count > 0 {
+                    writer.write_all(b",").map_err(export_error)?;
+                }
+                let entry = ExportedItem { item, data: data.map(|d| base64_encode(&d)) };
+                serde_json::to_writer(&mut writer, &entry).map_err(export_error)?;
+                count += 1;
+            }
+        }
+        writer.write_all(b"]").map_err(export_error)?;
+
+        let file = writer.into_inner().map_err(|e| export_error(e.into_error()))?;
+        file.sync_all().map_err(export_error)?;
+
+        if skipped_encrypted > 0 {
+            warn!("Encryption key unavailable: exported {} encrypted items without their data", skipped_encrypted);
+        }
+        Ok(count)
+    }
+
+    /// Import a file written by `export_json_file`, in one transaction.
+    ///
+    /// Entries without data are skipped (there is nothing to paste), as are
+    /// entries already in the history: same content hash, or the same capture
+    /// time, type and preview. Sensitive items are encrypted with `encryptor`.
+    /// Returns the number of items added, or an error if the file has entries
+    /// but none of them carry data (it was exported without blobs).
+    pub fn import_json(&self, path: &Path, encryptor: &Encryptor) -> Result<usize> {
+        let file = fs::File::open(path).map_err(export_error)?;
+        let entries: Vec<ExportedItem> = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(export_error)?;
+
+        if !entries.is_empty() && entries.iter().all(|entry| entry.data.is_none()) {
+            return Err(export_error(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "no entries carry data; export with blobs included to import",
+            )));
+        }
+
+        let tx = DatabaseTransaction { tx: self.conn.unchecked_transaction()?, compress: self.compress };
+        let mut imported = 0;
+        let mut skipped = 0;
+        for entry in entries {
+            let item = entry.item;
+            let Some(data) = entry.data.as_deref().and_then(base64_decode) else {
+                skipped += 1;
+                continue;
+            };
+            let is_encrypted = item.effective_sensitivity();
+            let content_hash = Self::content_hash_for(&data, is_encrypted, encryptor);
+            let exists: bool = tx.conn().query_row(
+                "SELECT EXISTS(SELECT 1 FROM clipboard_items
+                 WHERE content_hash = ?1
+                    OR (timestamp = ?2 AND data_type = ?3 AND preview_text IS ?4))",
+                params![content_hash, item.timestamp, item.data_type, item.preview_text],
+                |row| row.get(0),
+            )?;
+            if exists {
+                skipped += 1;
+                continue;
+            }
+
+            let pending = PendingItem {
+                blob: Vec::new(),
+                timestamp: item.timestamp,
+                data_type: item.data_type,
+                is_sensitive: item.is_sensitive,
+                is_encrypted,
+                preview_text: item.preview_text,
+                data_size: data.len() as i64,
+                metadata: item.metadata,
+                copy_count: item.copy_count,
+                content_hash,
+                thumbnail: None,
+                tags: item.tags,
+            };
+            // The item ID is the ciphertext's AAD, so sealed data is filled in after the row exists
+            let blob_id = tx.store_blob_tx(if is_encrypted { &[] } else { &data })?;
+            let item_id = tx.store_item_tx(&pending, blob_id)?;
+            if is_encrypted {
+                seal_blob_in(tx.conn(), blob_id, item_id, &data, encryptor, tx.compress)?;
+            }
+            tx.conn().execute(
+                "UPDATE clipboard_items SET is_pinned = ?1, sensitivity_override = ?2 WHERE id = ?3",
+                params![item.is_pinned, item.sensitivity_override, item_id],
+            )?;
+            imported += 1;
+        }
+        tx.commit()?;
+
+        info!("📥 Imported {} items from {} ({} skipped)", imported, path.display(), skipped);
+        Ok(imported)
+    }
+
+    /// Clean up items older than their retention period. Sensitive items use
+    /// `sensitive_retention_days` when set, everything else `retention_days`.
+    /// Pinned items are never removed.
+    pub fn cleanup_old_items(&self, config: &AppConfig) -> Result<usize> {
+        self.cleanup_with_policy(
+            &RetentionPolicy::uniform(config.retention_days),
+            config.sensitive_retention_days,
+        )
+    }
+
+    /// Clean up items older than the window for their type, all in one
+    /// transaction. Sensitive items use `sensitive_retention_days` when set;
+    /// pinned items are never removed.
+    pub fn cleanup_with_policy(
+        &self,
+        policy: &RetentionPolicy,
+        sensitive_retention_days: Option<u32>,
+    ) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let cutoff = |days: u32| now - i64::from(days) * 86400;
+        let windows = [
+            ("data_type = 'image'", policy.image_days),
+            ("data_type = 'url'", policy.url_days),
+            ("data_type = 'rtf'", policy.rtf_days),
+            ("data_type NOT IN ('image', 'url', 'rtf')", policy.text_days),
+        ];
+        let tx = self.conn.unchecked_transaction()?;
+
+        let mut blob_ids: Vec<i64> = Vec::new();
+        let mut deleted_items = 0;
+        for (type_filter, days) in windows {
+            let filter = format!(
+                "{} AND is_pinned = 0 AND ((COALESCE(sensitivity_override, is_sensitive) = 0 AND timestamp < ?1)
+                 OR (COALESCE(sensitivity_override, is_sensitive) = 1 AND timestamp < ?2))",
+                type_filter
+            );
+            let cutoffs = params![cutoff(days), cutoff(sensitive_retention_days.unwrap_or(days))];
+
+            // Get blob IDs to delete (scoped so stmt is dropped before the delete)
+            {
+                let mut stmt = tx.prepare(&format!("SELECT data_blob_id FROM clipboard_items WHERE {}", filter))?;
+                let ids = stmt.query_map(cutoffs, |row| row.get(0))?;
+                for id in ids {
+                    blob_ids.push(id?);
+                }
+            }
+
+            deleted_items += tx.execute(&format!("DELETE FROM clipboard_items WHERE {}", filter), cutoffs)?;
+        }
+
+        // Delete orphaned blobs in a single statement when possible
+        if !blob_ids.is_empty() {
+            tx.execute(
+                &format!("DELETE FROM clipboard_data WHERE id IN (SELECT value FROM json_each(?1)) AND {}", BLOB_UNREFERENCED),
+                params![serde_json::to_string(&blob_ids).unwrap_or_default()],
+            ).or_else(|_| {
+                // Fallback: delete one by one if json_each not available
+                for blob_id in &blob_ids {
+                    tx.execute(
+                        &format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED),
+                        params![blob_id],
+                    )?;
+                }
+                Ok::<usize, rusqlite::Error>(blob_ids.len())
+            })?;
+        }
+
+        tx.commit()?;
+
+        if deleted_items > 0 {
+            info!("🗑️  Cleaned up {} old clipboard items", deleted_items);
+        }
+
+        Ok(deleted_items)
+    }
+
+    /// Soft-delete all clipboard items (move to deleted_items/deleted_data tables)
+    pub fn soft_delete_all_items(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let tx = self.begin_write_transaction()?;
+
+        // Get all items with their blob IDs (scope stmt so it's dropped before commit)
+        let items: Vec<(i64, i64, String, bool, bool, Option<String>, i64, i64, Option<String>)> = {
+            let mut stmt = tx.conn().prepare(
+                "SELECT id, timestamp, data_type, is_sensitive, is_encrypted,
+                        preview_text, data_size, data_blob_id, metadata
+                 FROM clipboard_items"
+            )?;
+            let result = stmt.query_map([], |row| {
+                Ok((
+                    row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+                    row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?,
+                ))
+            })?.collect::<Result<Vec<_>>>()?;
+            result
+        };
+
+        let count = items.len();
+        if count == 0 {
+            tx.commit()?;
+            return Ok(0);
+        }
+
+        for (id, timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, blob_id, metadata) in &items {
+            // Copy blob data to deleted_data
+            tx.conn().execute(
+                "INSERT INTO deleted_data (data, compression) SELECT data, compression FROM clipboard_data WHERE id = ?1",
+                params![blob_id],
+            )?;
+            let deleted_blob_id = tx.conn().last_insert_rowid();
+
+            // Copy item to deleted_items
+            tx.conn().execute(
+                "INSERT INTO deleted_items
+                 (original_id, timestamp, deleted_at, data_type, is_sensitive, is_encrypted,
+                  preview_text, data_size, deleted_blob_id, metadata)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![id, timestamp, now, data_type, is_sensitive, is_encrypted,
+                        preview_text, data_size, deleted_blob_id, metadata],
+            )?;
+        }
+
+        // Delete originals
+        for (id, ..) in &items {
+            tx.delete_item_tx(ItemId(*id))?;
+        }
+
+        tx.commit()?;
+        info!("🗑️  Soft-deleted {} clipboard items (recoverable for 7 days)", count);
+        Ok(count)
+    }
+
+    /// Soft-delete every item of one data type (e.g. "image"), recoverable for 7 days
+    pub fn delete_items_by_type(&self, data_type: &str) -> Result<usize> {
+        let count = self.soft_delete_items_where("data_type = ?1", data_type)?;
+        if count > 0 {
+            info!("🗑️  Soft-deleted {} '{}' items (recoverable for 7 days)", count, data_type);
+        }
+        Ok(count)
+    }
+
+    /// Soft-delete every item copied from the app with `bundle_id` (e.g.
+    /// "com.tinyspeck.slackmacgap"), recoverable for 7 days
+    pub fn soft_delete_items_by_source_app(&self, bundle_id: &str) -> Result<usize> {
+        let count = self.soft_delete_items_where(
+            "json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.source_bundle_id') = ?1",
+            bundle_id,
+        )?;
+        if count > 0 {
+            info!("🗑️  Soft-deleted {} items from {} (recoverable for 7 days)", count, bundle_id);
+        }
+        Ok(count)
+    }
+
+    /// Move the items matching `condition` (with `?1` bound to `value`) and
+    /// their blobs to the deleted tables, in one transaction
+    fn soft_delete_items_where(&self, condition: &str, value: &str) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let tx = self.conn.unchecked_transaction()?;
+
+        let items: Vec<(ItemId, BlobId)> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT id, data_blob_id FROM clipboard_items WHERE {}", condition
+            ))?;
+            let result = stmt.query_map(params![value], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>>>()?;
+            result
+        };
+
+        for (item_id, blob_id) in &items {
+            tx.execute(
+                "INSERT INTO deleted_data (data, compression) SELECT data, compression FROM clipboard_data WHERE id = ?1",
+                params![blob_id],
+            )?;
+            let deleted_blob_id = tx.last_insert_rowid();
+
+            tx.execute(
+                "INSERT INTO deleted_items
+                 (original_id, timestamp, deleted_at, data_type, is_sensitive, is_encrypted,
+                  preview_text, data_size, deleted_blob_id, metadata)
+                 SELECT id, timestamp, ?2, data_type, is_sensitive, is_encrypted,
+                        preview_text, data_size, ?3, metadata
+                 FROM clipboard_items WHERE id = ?1",
+                params![item_id, now, deleted_blob_id],
+            )?;
+
+            tx.execute("DELETE FROM clipboard_items WHERE id = ?1", params![item_id])?;
+            tx.execute(&format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED), params![blob_id])?;
+        }
+
+        tx.commit()?;
+        Ok(items.len())
+    }
+
+    /// Soft-delete a single item (recoverable for 7 days, like soft_delete_all_items)
+    pub fn soft_delete_item(&self, item_id: ItemId) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let tx = self.conn.unchecked_transaction()?;
+
+        let blob_id: BlobId = tx.query_row(
+            "SELECT data_blob_id FROM clipboard_items WHERE id = ?1",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+
+        tx.execute(
+            "INSERT INTO deleted_data (data, compression) SELECT data, compression FROM clipboard_data WHERE id = ?1",
+            params![blob_id],
+        )?;
+        let deleted_blob_id = tx.last_insert_rowid();
+
+        tx.execute(
+            "INSERT INTO deleted_items
+             (original_id, timestamp, deleted_at, data_type, is_sensitive, is_encrypted,
+              preview_text, data_size, deleted_blob_id, metadata)
+             SELECT id, timestamp, ?2, data_type, is_sensitive, is_encrypted,
+                    preview_text, data_size, ?3, metadata
+             FROM clipboard_items WHERE id = ?1",
+            params![item_id, now, deleted_blob_id],
+        )?;
+
+        tx.execute("DELETE FROM clipboard_items WHERE id = ?1", params![item_id])?;
+        tx.execute(&format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED), params![blob_id])?;
+
+        tx.commit()?;
+        info!("🗑️  Soft-deleted item #{}", item_id);
+        Ok(())
+    }
+
+    /// Permanently purge deleted items older than 7 days
+    pub fn purge_deleted_items(&self) -> Result<usize> {
+        let cutoff = chrono::Utc::now().timestamp() - (7 * 86400);
+        let tx = self.conn.unchecked_transaction()?;
+
+        // Get blob IDs of expired deleted items (scoped so stmt is dropped before commit)
+        let blob_ids: Vec<i64> = {
+            let mut stmt = tx.prepare(
+                "SELECT deleted_blob_id FROM deleted_items WHERE deleted_at < ?1"
             )?;
             let result = stmt.query_map(params![cutoff], |row| {
                 row.get(0)
@@ -385,6 +1128,64 @@ impl Database {
         Ok(purged)
     }
 
+    /// Soft-deleted items still in the trash, most recently deleted first
+    pub fn get_deleted_items(&self, limit: i32) -> Result<Vec<DeletedItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, original_id, timestamp, deleted_at, data_type, is_sensitive, preview_text
+             FROM deleted_items
+             ORDER BY deleted_at DESC, id DESC
+             LIMIT ?1"
+        )?;
+
+        let items = stmt.query_map(params![limit], |row| {
+            Ok(DeletedItem {
+                id: row.get(0)?,
+                original_id: row.get(1)?,
+                timestamp: row.get(2)?,
+                deleted_at: row.get(3)?,
+                data_type: row.get(4)?,
+                is_sensitive: row.get(5)?,
+                preview_text: row.get(6)?,
+            })
+        })?;
+
+        items.collect()
+    }
+
+    /// Data of the trashed item that had `original_id`. Like `get_blob`,
+    /// encrypted data is returned still sealed.
+    pub fn get_deleted_blob(&self, original_id: ItemId) -> Result<Vec<u8>> {
+        let (data, compression, sealed): (Vec<u8>, String, bool) = self.conn.query_row(
+            "SELECT d.data, d.compression, i.is_encrypted FROM deleted_items i
+             JOIN deleted_data d ON d.id = i.deleted_blob_id
+             WHERE i.original_id = ?1",
+            params![original_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        if sealed {
+            return Ok(data);
+        }
+        decompress_blob(data, &compression)
+    }
+
+    /// Number of soft-deleted items waiting in the trash
+    pub fn get_deleted_item_count(&self) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM deleted_items",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Total unencrypted size in bytes of the items in the trash
+    pub fn get_total_deleted_size(&self) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(data_size), 0) FROM deleted_items",
+            [],
+            |row| row.get(0),
+        )
+    }
+
     /// Remove existing items that match the given preview_text and data_type (deduplication).
     /// Skips dedup when preview_text is None (can't reliably compare NULL values).
     /// Returns (removed_count, max_copy_count) so the caller can increment the count.
@@ -417,7 +1218,7 @@ impl Database {
                 params![item_id],
             )?;
             self.conn.execute(
-                "DELETE FROM clipboard_data WHERE id = ?1",
+                &format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED),
                 params![blob_id],
             )?;
         }
@@ -430,45 +1231,300 @@ impl Database {
         Ok((count, max_copy_count))
     }
 
-    /// Delete oldest items beyond the given limit (for free tier enforcement).
-    pub fn enforce_history_limit(&self, max_items: usize) -> Result<usize> {
+    /// Merge every group of duplicate items (same data type, preview and content
+    /// hash) into its newest item, whose `copy_count` becomes the group's total.
+    /// Items without a content hash are left alone (see
+    /// `compute_content_hash_for_existing_items`). Missing or non-positive counts
+    /// are reset to 1. Returns the number of duplicates removed.
+    pub fn recount_all_copy_counts(&mut self) -> Result<usize> {
+        let tx = self.begin_write_transaction()?;
+        let removed = merge_duplicate_items(tx.conn())?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// `(preview, expected_count, actual_count)` for items whose stored count looks
+    /// wrong: the newest of a duplicate group (expected: the group's total) and
+    /// items with a missing or non-positive count (expected: 1)
+    pub fn find_copy_count_inconsistencies(&self) -> Result<Vec<(String, i64, i64)>> {
+        let mut found: Vec<(String, i64, i64)> = Self::duplicate_groups(&self.conn)?
+            .iter()
+            .filter_map(|group| {
+                let (_, newest_count, _) = group.items.first()?;
+                Some((group.preview.clone(), group.total_count(), *newest_count))
+            })
+            .collect();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(preview_text, ''), COALESCE(copy_count, 0) FROM clipboard_items
+             WHERE copy_count IS NULL OR copy_count < 1
+             ORDER BY id"
+        )?;
+        let invalid = stmt.query_map([], |row| Ok((row.get(0)?, 1, row.get(1)?)))?;
+        for entry in invalid {
+            found.push(entry?);
+        }
+        Ok(found)
+    }
+
+    /// Groups of 2+ items sharing data type, preview and content hash, newest first.
+    /// Items without a content hash are never grouped: previews are truncated or
+    /// redacted, so equal previews don't mean equal content.
+    fn duplicate_groups(conn: &Connection) -> Result<Vec<DuplicateGroup>> {
+        let mut stmt = conn.prepare(
+            "SELECT data_type, preview_text, content_hash, id, copy_count, COALESCE(is_pinned, 0)
+             FROM clipboard_items
+             WHERE preview_text IS NOT NULL AND content_hash IS NOT NULL
+               AND (data_type, preview_text, content_hash) IN (
+                   SELECT data_type, preview_text, content_hash
+                   FROM clipboard_items
+                   WHERE preview_text IS NOT NULL AND content_hash IS NOT NULL
+                   GROUP BY 1, 2, 3
+                   HAVING COUNT(*) > 1
+               )
+             ORDER BY data_type, preview_text, content_hash, timestamp DESC, id DESC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                (row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?),
+                (row.get::<_, ItemId>(3)?, row.get::<_, Option<i64>>(4)?.unwrap_or(0), row.get::<_, bool>(5)?),
+            ))
+        })?;
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        let mut last_key = None;
+        for row in rows {
+            let (key, item) = row?;
+            if last_key.as_ref() != Some(&key) {
+                groups.push(DuplicateGroup { preview: key.1.clone(), items: Vec::new() });
+                last_key = Some(key);
+            }
+            if let Some(group) = groups.last_mut() {
+                group.items.push(item);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Delete the oldest unpinned items beyond `limit`, along with their blobs.
+    /// Pinned items are never evicted and don't count toward the limit.
+    pub fn enforce_max_items(&self, limit: usize) -> Result<usize> {
+        // Saturate so "unlimited" (usize::MAX) doesn't wrap to a negative offset
+        let offset = i64::try_from(limit).unwrap_or(i64::MAX);
         let tx = self.conn.unchecked_transaction()?;
 
-        let to_delete: Vec<(i64, i64)> = {
+        let blob_ids: Vec<BlobId> = {
             let mut stmt = tx.prepare(
-                "SELECT id, data_blob_id FROM clipboard_items
-                 ORDER BY timestamp DESC
-                 LIMIT -1 OFFSET ?1"
+                "DELETE FROM clipboard_items WHERE id IN (
+                     SELECT id FROM clipboard_items WHERE is_pinned = 0
+                     ORDER BY timestamp DESC, id DESC
+                     LIMIT -1 OFFSET ?1
+                 )
+                 RETURNING data_blob_id"
             )?;
-            let result = stmt.query_map(params![max_items as i64], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })?.collect::<Result<Vec<_>>>()?;
+            let result = stmt.query_map(params![offset], |row| row.get(0))?
+                .collect::<Result<Vec<_>>>()?;
             result
         };
 
-        if to_delete.is_empty() {
-            tx.commit()?;
-            return Ok(0);
+        if !blob_ids.is_empty() {
+            tx.execute(
+                &format!("DELETE FROM clipboard_data WHERE id IN (SELECT value FROM json_each(?1)) AND {}", BLOB_UNREFERENCED),
+                params![serde_json::to_string(&blob_ids).unwrap_or_default()],
+            )?;
+        }
+        tx.commit()?;
+
+        if !blob_ids.is_empty() {
+            info!("📦 Trimmed {} items (history limit: {})", blob_ids.len(), limit);
+        }
+        Ok(blob_ids.len())
+    }
+
+    /// Re-encrypt the blobs of encrypted items, live and in the trash, from
+    /// `old_enc` to `new_enc`, and recompute the live items' keyed content hashes
+    /// (see `content_hash_for`) so dedup keeps matching them. Runs in one
+    /// transaction: if any blob fails, nothing is changed and the old key stays
+    /// valid. Returns the number of blobs rewritten.
+    pub fn reencrypt_all_blobs(&self, old_enc: &Encryptor, new_enc: &Encryptor) -> Result<usize> {
+        // Trashed blobs keep the AAD of the item they were encrypted for
+        const ENCRYPTED_BLOBS: [(&str, &str); 2] = [
+            ("clipboard_data",
+             "SELECT d.id, i.id, d.data, d.compression FROM clipboard_items i
+              JOIN clipboard_data d ON d.id = i.data_blob_id
+              WHERE i.is_encrypted = 1 AND length(d.data) > 0"),
+            ("deleted_data",
+             "SELECT d.id, i.original_id, d.data, d.compression FROM deleted_items i
+              JOIN deleted_data d ON d.id = i.deleted_blob_id
+              WHERE i.is_encrypted = 1 AND length(d.data) > 0"),
+        ];
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut count = 0;
+        for (table, query) in ENCRYPTED_BLOBS {
+            let blobs: Vec<(i64, ItemId, Vec<u8>, String)> = {
+                let mut stmt = tx.prepare(query)?;
+                let result = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+                    .collect::<Result<Vec<_>>>()?;
+                result
+            };
+
+            let mut update = tx.prepare(&format!("UPDATE {} SET data = ?1 WHERE id = ?2", table))?;
+            let mut rehash = tx.prepare("UPDATE clipboard_items SET content_hash = ?1 WHERE id = ?2")?;
+            for (blob_id, item_id, data, compression) in blobs {
+                let plaintext = old_enc.decrypt_for_item(&data, item_id)
+                    .map_err(|e| crypto_error(format!("Failed to decrypt item #{}: {}", item_id, e)))?;
+                let sealed = new_enc.encrypt_for_item(&plaintext, item_id)
+                    .map_err(|e| crypto_error(format!("Failed to encrypt item #{}: {}", item_id, e)))?;
+                update.execute(params![sealed, blob_id])?;
+                // Trashed items have no content hash
+                if table == "clipboard_data" {
+                    let content = decompress_blob(plaintext, &compression)?;
+                    rehash.execute(params![new_enc.keyed_content_hash(&content), item_id])?;
+                }
+                count += 1;
+            }
+        }
+        tx.commit()?;
+
+        info!("🔑 Re-encrypted {} blobs with the new key", count);
+        Ok(count)
+    }
+
+    /// Items copied from the app with `bundle_id` (e.g. "com.apple.Safari"), newest first
+    pub fn get_items_from_app(&self, bundle_id: &str, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE json_extract(CASE WHEN json_valid(metadata) THEN metadata END, '$.source_bundle_id') = ?1
+             ORDER BY timestamp DESC LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+        let items = stmt.query_map(params![bundle_id, limit], ClipboardItem::from_row)?;
+        items.collect()
+    }
+
+    /// Add `tag` (normalized, see `normalize_tag`) to an item; adding a tag it already has is a no-op
+    pub fn add_tag(&self, item_id: ItemId, tag: &str) -> Result<()> {
+        let tag = Self::checked_tag(tag)?;
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET tags = CASE
+                 WHEN tags IS NULL OR tags = '' THEN ?1
+                 WHEN instr(',' || tags || ',', ',' || ?1 || ',') > 0 THEN tags
+                 ELSE tags || ',' || ?1
+             END
+             WHERE id = ?2",
+            params![tag, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
         }
+        Ok(())
+    }
+
+    /// Remove `tag` from an item; removing a tag it doesn't have is a no-op
+    pub fn remove_tag(&self, item_id: ItemId, tag: &str) -> Result<()> {
+        let tag = Self::checked_tag(tag)?;
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items
+             SET tags = NULLIF(trim(replace(',' || COALESCE(tags, '') || ',', ',' || ?1 || ',', ','), ','), '')
+             WHERE id = ?2",
+            params![tag, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Replace all of an item's tags. Invalid tags are an error; duplicates are dropped.
+    pub fn set_tags(&self, item_id: ItemId, tags: &[String]) -> Result<()> {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = Self::checked_tag(tag)?;
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET tags = ?1 WHERE id = ?2",
+            params![join_tags(&normalized), item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Items tagged `tag`, newest first
+    pub fn get_items_by_tag(&self, tag: &str, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let tag = Self::checked_tag(tag)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE instr(',' || tags || ',', ',' || ?1 || ',') > 0
+             ORDER BY timestamp DESC LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+        let items = stmt.query_map(params![tag, limit], ClipboardItem::from_row)?;
+        items.collect()
+    }
+
+    fn checked_tag(tag: &str) -> Result<String> {
+        normalize_tag(tag).ok_or_else(|| rusqlite::Error::InvalidParameterName(tag.to_string()))
+    }
+
+    /// Items whose text contained `url` (see `ItemMetadata::Text::embedded_urls`), newest first
+    pub fn get_items_with_embedded_url(&self, url: &str, limit: i32) -> Result<Vec<ClipboardItem>> {
+        // json_each(NULL) yields no rows, which skips malformed metadata instead of erroring
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE EXISTS (
+                 SELECT 1 FROM json_each(
+                     CASE WHEN json_valid(metadata) THEN metadata END, '$.embedded_urls'
+                 ) WHERE value = ?1
+             )
+             ORDER BY timestamp DESC LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![url, limit], ClipboardItem::from_row)?;
+        items.collect()
+    }
 
-        for (item_id, blob_id) in &to_delete {
-            tx.execute(
-                "DELETE FROM clipboard_items WHERE id = ?1",
-                params![item_id],
-            )?;
-            tx.execute(
-                "DELETE FROM clipboard_data WHERE id = ?1",
-                params![blob_id],
-            )?;
-        }
+    /// Items whose top-level metadata field `key` matches `value_pattern`
+    /// (SQL `LIKE`: `%`/`_` wildcards, ASCII case-insensitive), newest first
+    pub fn search_metadata_field(&self, key: &str, value_pattern: &str) -> Result<Vec<ClipboardItem>> {
+        let path = Self::metadata_key_path(key)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE json_extract(CASE WHEN json_valid(metadata) THEN metadata END, ?1) LIKE ?2
+             ORDER BY timestamp DESC",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![path, value_pattern], ClipboardItem::from_row)?;
+        items.collect()
+    }
 
-        let count = to_delete.len();
-        tx.commit()?;
+    /// Items whose array-valued metadata field `key` contains exactly `array_value`, newest first
+    pub fn search_metadata_contains(&self, key: &str, array_value: &str) -> Result<Vec<ClipboardItem>> {
+        let path = Self::metadata_key_path(key)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE EXISTS (
+                 SELECT 1 FROM json_each(
+                     CASE WHEN json_valid(metadata) THEN metadata END, ?1
+                 ) WHERE value = ?2
+             )
+             ORDER BY timestamp DESC",
+            ClipboardItem::select_sql()
+        ))?;
+
+        let items = stmt.query_map(params![path, array_value], ClipboardItem::from_row)?;
+        items.collect()
+    }
 
-        if count > 0 {
-            info!("📦 Trimmed {} items (free tier limit: {})", count, max_items);
+    /// JSON path for a top-level metadata key. Only plain identifiers are
+    /// accepted so a key can't reach into nested objects or array indices.
+    fn metadata_key_path(key: &str) -> Result<String> {
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(rusqlite::Error::InvalidParameterName(key.to_string()));
         }
-        Ok(count)
+        Ok(format!("$.{}", key))
     }
 
     /// Search clipboard items with optional type and time filters
@@ -478,13 +1534,55 @@ impl Database {
         time_after: Option<i64>,
         limit: i32,
     ) -> Result<Vec<ClipboardItem>> {
-        let mut sql = String::from(
-            "SELECT id, timestamp, data_type, is_sensitive, is_encrypted, \
-             preview_text, data_size, data_blob_id, metadata, \
-             COALESCE(copy_count, 1), COALESCE(is_pinned, 0) \
-             FROM clipboard_items"
+        self.search_items_page(type_filter, time_after, false, 0, limit)
+    }
+
+    /// One page of `search_items` results, optionally only sensitive items
+    /// (counting manual overrides). Ordered like `get_items_paginated`.
+    pub fn search_items_page(
+        &self,
+        type_filter: Option<&str>,
+        time_after: Option<i64>,
+        sensitive_only: bool,
+        offset: i64,
+        limit: i32,
+    ) -> Result<Vec<ClipboardItem>> {
+        let (where_clause, mut values) = Self::filter_clause(type_filter, time_after, sensitive_only);
+        let sql = format!(
+            "{}{} ORDER BY is_pinned DESC, timestamp DESC, id DESC LIMIT ? OFFSET ?",
+            ClipboardItem::select_sql(),
+            where_clause
         );
+        values.push(rusqlite::types::Value::Integer(limit as i64));
+        values.push(rusqlite::types::Value::Integer(offset));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let items = stmt.query_map(rusqlite::params_from_iter(values), ClipboardItem::from_row)?;
+
+        items.collect()
+    }
+
+    /// Number of items `search_items_page` pages through
+    pub fn count_matching_items(
+        &self,
+        type_filter: Option<&str>,
+        time_after: Option<i64>,
+        sensitive_only: bool,
+    ) -> Result<i64> {
+        let (where_clause, values) = Self::filter_clause(type_filter, time_after, sensitive_only);
+        self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM clipboard_items{}", where_clause),
+            rusqlite::params_from_iter(values),
+            |row| row.get(0),
+        )
+    }
 
+    /// ` WHERE …` clause (empty without filters) and its bound values
+    fn filter_clause(
+        type_filter: Option<&str>,
+        time_after: Option<i64>,
+        sensitive_only: bool,
+    ) -> (String, Vec<rusqlite::types::Value>) {
         let mut conditions = Vec::new();
         let mut values: Vec<rusqlite::types::Value> = Vec::new();
 
@@ -498,61 +1596,210 @@ impl Database {
             values.push(rusqlite::types::Value::Integer(after));
         }
 
-        if !conditions.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&conditions.join(" AND "));
+        if sensitive_only {
+            conditions.push("COALESCE(sensitivity_override, is_sensitive) = 1");
         }
 
-        sql.push_str(" ORDER BY is_pinned DESC, timestamp DESC LIMIT ?");
-        values.push(rusqlite::types::Value::Integer(limit as i64));
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let items = stmt.query_map(rusqlite::params_from_iter(values), |row| {
-            Ok(ClipboardItem {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                data_type: row.get(2)?,
-                is_sensitive: row.get(3)?,
-                is_encrypted: row.get(4)?,
-                preview_text: row.get(5)?,
-                data_size: row.get(6)?,
-                data_blob_id: row.get(7)?,
-                metadata: row.get(8)?,
-                copy_count: row.get(9)?,
-                is_pinned: row.get(10)?,
-            })
-        })?;
+    /// Full-text search over previews and data types, best BM25 match first.
+    /// Each word of `query` must appear, matched as a token prefix, so
+    /// "git conf" finds "git config --global". An empty query returns nothing.
+    pub fn fts_search(&self, query: &str, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let Some(fts_query) = Self::fts_match_expression(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "{} JOIN (
+                 SELECT rowid, bm25(clipboard_fts) AS rank FROM clipboard_fts WHERE clipboard_fts MATCH ?1
+             ) AS fts ON fts.rowid = clipboard_items.id
+             ORDER BY fts.rank, timestamp DESC LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+        let items = stmt.query_map(params![fts_query, limit], ClipboardItem::from_row)?;
+        items.collect()
+    }
 
+    /// Turn free text into an FTS5 query: each word becomes a quoted prefix
+    /// phrase, so punctuation and FTS operators in the input are matched literally
+    fn fts_match_expression(query: &str) -> Option<String> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric)) // Bare punctuation has no tokens
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+
+    /// Rebuild the full-text index from clipboard_items (recovery after corruption)
+    pub fn rebuild_fts_index(&self) -> Result<()> {
+        self.conn.execute("INSERT INTO clipboard_fts (clipboard_fts) VALUES ('rebuild')", [])?;
+        Ok(())
+    }
+
+    /// Most recent items of one data type (e.g. "email"), pinned items first
+    pub fn get_items_by_type(&self, data_type: &str, limit: i32) -> Result<Vec<ClipboardItem>> {
+        self.search_items(Some(data_type), None, limit)
+    }
+
+    /// Most recent items that are (or aren't) sensitive after any manual
+    /// override, pinned items first
+    pub fn get_items_by_sensitivity(&self, sensitive: bool, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE COALESCE(sensitivity_override, is_sensitive) = ?1
+             ORDER BY is_pinned DESC, timestamp DESC, id DESC LIMIT ?2",
+            ClipboardItem::select_sql()
+        ))?;
+        let items = stmt.query_map(params![sensitive, limit], ClipboardItem::from_row)?;
         items.collect()
     }
 
+    /// Items copied between `from_ts` and `to_ts` (both inclusive), newest first
+    pub fn get_items_by_date_range(&self, from_ts: i64, to_ts: i64, limit: i32) -> Result<Vec<ClipboardItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE timestamp BETWEEN ?1 AND ?2
+             ORDER BY timestamp DESC, id DESC LIMIT ?3",
+            ClipboardItem::select_sql()
+        ))?;
+        let items = stmt.query_map(params![from_ts, to_ts, limit], ClipboardItem::from_row)?;
+        items.collect()
+    }
+
+    /// Move an item into a named clipboard slot (each item is in exactly one;
+    /// new items start in `default`)
+    pub fn move_item_to_slot(&self, item_id: ItemId, slot: &str) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET slot = ?1 WHERE id = ?2",
+            params![slot, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// List the names of all non-default slots in use, alphabetically
+    pub fn list_slots(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT slot FROM clipboard_items
+             WHERE slot IS NOT NULL AND slot != 'default'
+             ORDER BY slot"
+        )?;
+        let slots = stmt.query_map([], |row| row.get(0))?;
+        slots.collect()
+    }
+
+    /// Replace the preview text shown (and searched) for an item. The stored blob is unchanged.
+    pub fn update_preview_text(&self, item_id: ItemId, new_preview: &str) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET preview_text = ?1 WHERE id = ?2",
+            params![new_preview, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Pin or unpin an item. Pinned items list first in `get_recent_items`.
+    pub fn set_pinned(&self, item_id: ItemId, pinned: bool) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET is_pinned = ?1 WHERE id = ?2",
+            params![pinned, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Mark an item sensitive or not by hand, or `None` to go back to the
+    /// detected value. Doesn't encrypt or decrypt the stored blob, so the
+    /// lists' 🔒 (shown for `is_encrypted`) doesn't change either.
+    pub fn set_sensitivity_override(&self, item_id: ItemId, sensitive: Option<bool>) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE clipboard_items SET sensitivity_override = ?1 WHERE id = ?2",
+            params![sensitive, item_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    /// Pin an item
+    pub fn pin_item(&self, item_id: ItemId) -> Result<()> {
+        self.set_pinned(item_id, true)
+    }
+
+    /// Unpin an item
+    pub fn unpin_item(&self, item_id: ItemId) -> Result<()> {
+        self.set_pinned(item_id, false)
+    }
+
     /// Toggle pin status of an item
-    pub fn toggle_pin(&self, item_id: i64) -> Result<bool> {
+    pub fn toggle_pin(&self, item_id: ItemId) -> Result<bool> {
         let current: bool = self.conn.query_row(
             "SELECT COALESCE(is_pinned, 0) FROM clipboard_items WHERE id = ?1",
             params![item_id],
             |row| row.get(0),
         )?;
-        let new_val = !current;
-        self.conn.execute(
-            "UPDATE clipboard_items SET is_pinned = ?1 WHERE id = ?2",
-            params![new_val, item_id],
-        )?;
-        Ok(new_val)
+        self.set_pinned(item_id, !current)?;
+        Ok(!current)
     }
 
     /// Delete a single item and its blob
-    pub fn delete_item(&self, item_id: i64) -> Result<()> {
-        let blob_id: i64 = self.conn.query_row(
+    pub fn delete_item(&self, item_id: ItemId) -> Result<()> {
+        let blob_id: BlobId = self.conn.query_row(
             "SELECT data_blob_id FROM clipboard_items WHERE id = ?1",
             params![item_id],
             |row| row.get(0),
         )?;
         self.conn.execute("DELETE FROM clipboard_items WHERE id = ?1", params![item_id])?;
-        self.conn.execute("DELETE FROM clipboard_data WHERE id = ?1", params![blob_id])?;
+        self.conn.execute(&format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED), params![blob_id])?;
         Ok(())
     }
 
+    /// PNG of the icon of the app with `bundle_id`, `APP_ICON_PIXELS` square.
+    /// Falls back to the generic application icon when the app isn't installed.
+    /// Cached per bundle ID for the life of the database.
+    pub fn get_app_icon(&self, bundle_id: &str) -> Option<Vec<u8>> {
+        if let Some(png) = self.app_icons.borrow().get(bundle_id) {
+            return Some(png.clone());
+        }
+
+        let png = objc2::rc::autoreleasepool(|_| unsafe {
+            let workspace = NSWorkspace::sharedWorkspace();
+            let icon = match workspace.URLForApplicationWithBundleIdentifier(&NSString::from_str(bundle_id))
+                .and_then(|url| url.path())
+            {
+                Some(path) => workspace.iconForFile(&path),
+                #[allow(deprecated)]
+                None => workspace.iconForFileType(&NSString::from_str(GENERIC_APP_TYPE)),
+            };
+            icon_to_png(&icon)
+        });
+        match png {
+            Ok(png) => {
+                self.app_icons.borrow_mut().insert(bundle_id.to_string(), png.clone());
+                Some(png)
+            }
+            Err(e) => {
+                warn!("Failed to load icon for {}: {}", bundle_id, e);
+                None
+            }
+        }
+    }
+
     /// Get total item count
     pub fn count_items(&self) -> Result<i64> {
         let count: i64 = self.conn.query_row(
@@ -563,6 +1810,112 @@ impl Database {
         Ok(count)
     }
 
+    /// Number of items flagged as sensitive, counting manual overrides
+    pub fn count_sensitive_items(&self) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM clipboard_items WHERE COALESCE(sensitivity_override, is_sensitive) = 1",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Share of items that are sensitive (0.0 when history is empty)
+    pub fn sensitive_item_fraction(&self) -> Result<f64> {
+        let total = self.count_items()?;
+        if total == 0 {
+            return Ok(0.0);
+        }
+        Ok(self.count_sensitive_items()? as f64 / total as f64)
+    }
+
+    /// Summary counts for the status bar, the Statistics window and logs
+    pub fn get_statistics(&self) -> Result<DatabaseStatistics> {
+        let mut items_by_type = HashMap::new();
+        let mut bytes_by_type = HashMap::new();
+        let mut stmt = self.conn.prepare(
+            "SELECT data_type, COUNT(*), COALESCE(SUM(data_size), 0)
+             FROM clipboard_items GROUP BY data_type"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?;
+        for row in rows {
+            let (data_type, count, bytes) = row?;
+            items_by_type.insert(data_type.clone(), count);
+            bytes_by_type.insert(data_type, bytes);
+        }
+
+        let (total_items, sensitive_items, encrypted_items, oldest_timestamp, newest_timestamp, total_blob_bytes) =
+            self.conn.query_row(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(COALESCE(sensitivity_override, is_sensitive) = 1), 0),
+                        COALESCE(SUM(is_encrypted = 1), 0),
+                        MIN(timestamp),
+                        MAX(timestamp),
+                        (SELECT COALESCE(SUM(LENGTH(data)), 0) FROM clipboard_data)
+                 FROM clipboard_items",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+            )?;
+
+        let sensitive_fraction = if total_items == 0 {
+            0.0
+        } else {
+            sensitive_items as f64 / total_items as f64
+        };
+        Ok(DatabaseStatistics {
+            total_items,
+            sensitive_items,
+            sensitive_fraction,
+            encrypted_items,
+            total_blob_bytes,
+            items_by_type,
+            bytes_by_type,
+            oldest_timestamp,
+            newest_timestamp,
+        })
+    }
+
+    /// Rows in `clipboard_data` (item data and thumbnails)
+    pub fn get_blob_count(&self) -> Result<i64> {
+        self.conn.query_row("SELECT COUNT(*) FROM clipboard_data", [], |row| row.get(0))
+    }
+
+    /// Distinct contents among the `clipboard_data` rows. Lower than
+    /// `get_blob_count` only for duplicates stored before blobs were hashed.
+    /// Compares every blob, so it's for diagnostics only.
+    pub fn get_unique_blob_count(&self) -> Result<i64> {
+        self.conn.query_row("SELECT COUNT(DISTINCT data) FROM clipboard_data", [], |row| row.get(0))
+    }
+
+    /// How blob storage is split between live and deleted (trash) items
+    pub fn get_storage_breakdown(&self) -> Result<StorageBreakdown> {
+        let (live_items_bytes, blob_count, largest_blob_bytes): (i64, i64, i64) = self.conn.query_row(
+            "SELECT COALESCE(SUM(length(data)), 0), COUNT(*), COALESCE(MAX(length(data)), 0)
+             FROM clipboard_data",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let (deleted_items_bytes, deleted_blob_count): (i64, i64) = self.conn.query_row(
+            "SELECT COALESCE(SUM(length(data)), 0), COUNT(*) FROM deleted_data",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let avg_blob_bytes = if blob_count == 0 {
+            0.0
+        } else {
+            live_items_bytes as f64 / blob_count as f64
+        };
+        Ok(StorageBreakdown {
+            live_items_bytes,
+            deleted_items_bytes,
+            blob_count,
+            deleted_blob_count,
+            avg_blob_bytes,
+            largest_blob_bytes,
+        })
+    }
+
     /// Get database size in bytes
     pub fn get_db_size(&self) -> Result<i64> {
         let page_count: i64 = self.conn.query_row(
@@ -579,20 +1932,495 @@ impl Database {
 
         Ok(page_count * page_size)
     }
+
+    /// Unix timestamp of the last successful `vacuum()`, if any
+    pub fn last_vacuum_at(&self) -> Result<Option<i64>> {
+        Ok(self.get_config("last_vacuum_at")?.and_then(|v| v.parse().ok()))
+    }
+
+    /// Whether scheduled maintenance should vacuum: never vacuumed, last vacuum
+    /// more than 7 days ago, or database larger than 50 MB
+    pub fn needs_vacuum(&self) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp();
+        let overdue = match self.last_vacuum_at()? {
+            Some(last) => now - last > VACUUM_INTERVAL_SECS,
+            None => true,
+        };
+        Ok(overdue || self.get_db_size()? > VACUUM_SIZE_THRESHOLD)
+    }
+
+    /// Checkpoint the WAL and rebuild the database file to reclaim free pages.
+    /// Records the time in `config.last_vacuum_at`.
+    pub fn vacuum(&self) -> Result<()> {
+        let size_before = self.get_db_size()?;
+        self.vacuum_blobs()?;
+        self.checkpoint_wal()?;
+        self.conn.execute("VACUUM", [])?;
+        self.set_config("last_vacuum_at", &chrono::Utc::now().timestamp().to_string())?;
+        info!("🧹 Vacuumed database ({} KB -> {} KB)", size_before / 1024, self.get_db_size()? / 1024);
+        Ok(())
+    }
+
+    /// Delete `clipboard_data` rows no item points to (left behind if a crash
+    /// interrupted a delete). Soft-deleted blobs live in `deleted_data`, so
+    /// they are unaffected. Returns the number of rows removed.
+    pub fn vacuum_blobs(&self) -> Result<usize> {
+        const ORPHANS: &str = "FROM clipboard_data
+             WHERE id NOT IN (SELECT data_blob_id FROM clipboard_items WHERE data_blob_id IS NOT NULL)
+               AND id NOT IN (SELECT thumbnail_blob_id FROM clipboard_items WHERE thumbnail_blob_id IS NOT NULL)";
+
+        let tx = self.conn.unchecked_transaction()?;
+        let bytes: i64 = tx.query_row(
+            &format!("SELECT COALESCE(SUM(length(data)), 0) {}", ORPHANS),
+            [],
+            |row| row.get(0),
+        )?;
+        let removed = tx.execute(&format!("DELETE {}", ORPHANS), [])?;
+        tx.commit()?;
+
+        if removed > 0 {
+            info!("🧹 Removed {} orphaned blobs ({} KB freed)", removed, bytes / 1024);
+        }
+        Ok(removed)
+    }
+
+    /// Flush write-ahead-log pages into the main database file and truncate the WAL
+    pub fn checkpoint_wal(&self) -> Result<()> {
+        // Returns (busy, log_frames, checkpointed_frames)
+        let (busy, log_frames, checkpointed): (i64, i64, i64) = self.conn.query_row(
+            "PRAGMA wal_checkpoint(TRUNCATE)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        if busy != 0 {
+            log::warn!("WAL checkpoint incomplete (database busy)");
+        } else {
+            info!("💾 WAL checkpoint complete ({} of {} frames)", checkpointed, log_frames);
+        }
+        Ok(())
+    }
 }
 
+/// Everything needed to store a new item and its blob in one go
 #[derive(Debug, Clone)]
+pub struct PendingItem {
+    pub blob: Vec<u8>,
+    pub timestamp: i64,
+    pub data_type: String,
+    pub is_sensitive: bool,
+    pub is_encrypted: bool,
+    pub preview_text: Option<String>,
+    /// Unencrypted size of the data
+    pub data_size: i64,
+    /// Metadata as JSON
+    pub metadata: Option<String>,
+    pub copy_count: i64,
+    pub content_hash: Option<String>,
+    /// Small PNG shown in the popup list (images only, never for encrypted items)
+    pub thumbnail: Option<Vec<u8>>,
+    /// Normalized tags (see `normalize_tag`)
+    pub tags: Vec<String>,
+}
+
+impl PendingItem {
+    /// Pending item for processed clipboard data stored as `blob`, which may
+    /// differ from `processed.blob` (e.g. a placeholder for data encrypted later)
+    pub fn from_processed(processed: &ProcessedData, blob: Vec<u8>, timestamp: i64, is_encrypted: bool) -> Self {
+        PendingItem {
+            blob,
+            timestamp,
+            data_type: processed.data_type.as_str().to_string(),
+            is_sensitive: processed.is_sensitive,
+            is_encrypted,
+            preview_text: processed.preview_text.clone(),
+            data_size: processed.blob.len() as i64,
+            metadata: processed.metadata.as_ref().map(|m| m.to_json()),
+            copy_count: 1,
+            // The plain hash of a secret is a guessing oracle; see `Database::content_hash_for`
+            content_hash: if is_encrypted { None } else { Some(processed.content_hash.clone()) },
+            thumbnail: if is_encrypted { None } else { processed.thumbnail.clone() },
+            tags: processed.suggested_tags(),
+        }
+    }
+
+    /// Replace the content hash, e.g. with the keyed hash of an encrypted item
+    pub fn with_content_hash(mut self, content_hash: Option<String>) -> Self {
+        self.content_hash = content_hash;
+        self
+    }
+
+    /// Record the app the data was copied from in the item's metadata
+    pub fn with_source_app(mut self, source: &SourceApp) -> Self {
+        self.metadata = source.merge_into_metadata_json(self.metadata.as_deref());
+        self
+    }
+}
+
+/// Body of `recount_all_copy_counts`, run on `conn` (normally inside a
+/// transaction; also the last schema migration)
+pub(crate) fn merge_duplicate_items(conn: &Connection) -> Result<usize> {
+    let groups = Database::duplicate_groups(conn)?;
+
+    let mut removed = 0;
+    for group in &groups {
+        let Some((newest, duplicates)) = group.items.split_first() else { continue };
+        conn.execute(
+            "UPDATE clipboard_items SET copy_count = ?1, is_pinned = ?2 WHERE id = ?3",
+            params![group.total_count(), group.any_pinned(), newest.0],
+        )?;
+        for (item_id, _, _) in duplicates {
+            delete_item_row(conn, *item_id)?;
+            removed += 1;
+        }
+    }
+    conn.execute(
+        "UPDATE clipboard_items SET copy_count = 1 WHERE copy_count IS NULL OR copy_count < 1",
+        [],
+    )?;
+
+    if removed > 0 {
+        info!("♻️  Merged {} duplicate item(s) into {} group(s)", removed, groups.len());
+    }
+    Ok(removed)
+}
+
+/// Delete an item row and its blob, unless another item still references the blob
+fn delete_item_row(conn: &Connection, item_id: ItemId) -> Result<()> {
+    let blob_id: BlobId = conn.query_row(
+        "SELECT data_blob_id FROM clipboard_items WHERE id = ?1",
+        params![item_id],
+        |row| row.get(0),
+    )?;
+    conn.execute("DELETE FROM clipboard_items WHERE id = ?1", params![item_id])?;
+    conn.execute(&format!("DELETE FROM clipboard_data WHERE id = ?1 AND {}", BLOB_UNREFERENCED), params![blob_id])?;
+    Ok(())
+}
+
+/// Duplicate items found by `Database::duplicate_groups`
+struct DuplicateGroup {
+    preview: String,
+    /// (id, stored copy_count, is_pinned), newest first
+    items: Vec<(ItemId, i64, bool)>,
+}
+
+impl DuplicateGroup {
+    /// Sum of the group's counts, treating missing or non-positive counts as 1
+    fn total_count(&self) -> i64 {
+        self.items.iter().map(|(_, count, _)| (*count).max(1)).sum()
+    }
+
+    fn any_pinned(&self) -> bool {
+        self.items.iter().any(|(_, _, pinned)| *pinned)
+    }
+}
+
+/// A write transaction from `Database::begin_write_transaction`. Changes are
+/// applied by `commit()`; dropping it without committing (including while
+/// unwinding from a panic) rolls everything back.
+pub struct DatabaseTransaction<'db> {
+    tx: Transaction<'db>,
+    compress: bool,
+}
+
+impl DatabaseTransaction<'_> {
+    /// Store a clipboard data blob, reusing an existing blob with identical bytes
+    pub fn store_blob_tx(&self, data: &[u8]) -> Result<BlobId> {
+        store_blob_dedup(&self.tx, data, self.compress)
+    }
+
+    /// Store the item row for `item`, whose blob is already stored as `blob_id`.
+    /// Its thumbnail, if any, is stored as a separate blob first.
+    pub fn store_item_tx(&self, item: &PendingItem, blob_id: BlobId) -> Result<ItemId> {
+        // Thumbnails are deleted with their item by trigger, so they're never shared
+        let thumbnail_blob_id = item.thumbnail.as_deref()
+            .map(|thumbnail| -> Result<BlobId> {
+                self.tx.execute("INSERT INTO clipboard_data (data) VALUES (?1)", params![thumbnail])?;
+                Ok(BlobId(self.tx.last_insert_rowid()))
+            })
+            .transpose()?;
+        self.tx.execute(
+            "INSERT INTO clipboard_items
+             (timestamp, data_type, is_sensitive, is_encrypted, preview_text, data_size, data_blob_id, metadata, copy_count, content_hash, thumbnail_blob_id, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                item.timestamp,
+                item.data_type,
+                item.is_sensitive,
+                item.is_encrypted,
+                item.preview_text,
+                item.data_size,
+                blob_id,
+                item.metadata,
+                item.copy_count,
+                item.content_hash,
+                thumbnail_blob_id,
+                join_tags(&item.tags),
+            ],
+        )?;
+        Ok(ItemId(self.tx.last_insert_rowid()))
+    }
+
+    /// Delete a single item and its blob
+    pub fn delete_item_tx(&self, item_id: ItemId) -> Result<()> {
+        delete_item_row(&self.tx, item_id)
+    }
+
+    /// Apply every change made in this transaction
+    pub fn commit(self) -> Result<()> {
+        self.tx.commit()
+    }
+
+    /// Raw access for multi-statement operations inside `Database`
+    fn conn(&self) -> &Connection {
+        &self.tx
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseStatistics {
+    pub total_items: i64,
+    /// Sensitive after any manual override
+    pub sensitive_items: i64,
+    pub sensitive_fraction: f64,
+    pub encrypted_items: i64,
+    /// Bytes stored in `clipboard_data` (compressed, encrypted, deduplicated),
+    /// thumbnails included
+    pub total_blob_bytes: i64,
+    pub items_by_type: HashMap<String, i64>,
+    /// Original (uncompressed, unencrypted) size of each type's items
+    pub bytes_by_type: HashMap<String, i64>,
+    pub oldest_timestamp: Option<i64>,
+    pub newest_timestamp: Option<i64>,
+}
+
+/// Blob storage split returned by `Database::get_storage_breakdown`.
+/// `avg_blob_bytes` and `largest_blob_bytes` cover live blobs only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageBreakdown {
+    pub live_items_bytes: i64,
+    pub deleted_items_bytes: i64,
+    pub blob_count: i64,
+    pub deleted_blob_count: i64,
+    pub avg_blob_bytes: f64,
+    pub largest_blob_bytes: i64,
+}
+
+/// Scale an AppKit image down to an `APP_ICON_PIXELS` square PNG
+fn icon_to_png(icon: &NSImage) -> std::result::Result<Vec<u8>, String> {
+    let tiff = unsafe { icon.TIFFRepresentation() }.ok_or("Icon has no bitmap representation")?;
+    let image = image::load_from_memory_with_format(tiff.bytes(), image::ImageFormat::Tiff)
+        .map_err(|e| format!("Failed to decode icon: {}", e))?;
+    let scaled = image.resize_exact(APP_ICON_PIXELS, APP_ICON_PIXELS, image::imageops::FilterType::Lanczos3);
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    scaled.write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode icon: {}", e))?;
+    Ok(png.into_inner())
+}
+
+/// An item in the soft-delete trash, as returned by `Database::get_deleted_items`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedItem {
+    /// Row ID in `deleted_items`
+    pub id: i64,
+    /// ID the item had in `clipboard_items`
+    pub original_id: ItemId,
+    pub timestamp: i64,
+    pub deleted_at: i64,
+    pub data_type: String,
+    pub is_sensitive: bool,
+    pub preview_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct ClipboardItem {
-    pub id: i64,
+    pub id: ItemId,
     pub timestamp: i64,
     pub data_type: String,
     pub is_sensitive: bool,
     pub is_encrypted: bool,
     pub preview_text: Option<String>,
     pub data_size: i64,
-    pub data_blob_id: i64,
+    pub data_blob_id: BlobId,
     pub metadata: Option<String>,
     pub copy_count: i64,
     pub is_pinned: bool,
+    /// Small PNG preview for image items, read with `Database::get_thumbnail`
+    pub thumbnail_blob_id: Option<BlobId>,
+    /// App the item was copied from, read from `metadata` (see `SourceApp`)
+    pub source_app_name: Option<String>,
+    pub source_bundle_id: Option<String>,
+    /// Sensitivity set by hand (`None` = use the detected `is_sensitive`)
+    pub sensitivity_override: Option<bool>,
+    /// Normalized tags (see `normalize_tag`), in the order they were added
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// One entry of `Database::export_json_file`: the item's fields, plus its
+/// decrypted data as base64 when blobs were included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedItem {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+impl ClipboardItem {
+    /// Seconds since the item was captured
+    pub fn age_seconds(&self, now: i64) -> i64 {
+        now - self.timestamp
+    }
+
+    /// Whether the item carries `tag` (compared after normalizing, so "#Work" matches "work")
+    pub fn has_tag(&self, tag: &str) -> bool {
+        normalize_tag(tag).is_some_and(|tag| self.tags.contains(&tag))
+    }
+
+    /// Whether the item counts as sensitive: the manual override if set, else detection
+    pub fn effective_sensitivity(&self) -> bool {
+        self.sensitivity_override.unwrap_or(self.is_sensitive)
+    }
+
+    /// Days this item is kept, taking the sensitive-item override into account
+    pub fn effective_retention_days(&self, config: &AppConfig) -> u32 {
+        if self.effective_sensitivity() {
+            config.sensitive_retention_days.unwrap_or(config.retention_days)
+        } else {
+            config.retention_days
+        }
+    }
+
+    /// Whether `cleanup_old_items` would remove this item at `now`
+    pub fn is_expired(&self, config: &AppConfig, now: i64) -> bool {
+        !self.is_pinned
+            && self.age_seconds(now) > i64::from(self.effective_retention_days(config)) * 86400
+    }
+
+    /// `SELECT <ITEM_COLUMNS> FROM clipboard_items`, ready for WHERE/ORDER BY
+    pub fn select_sql() -> &'static str {
+        concat!("SELECT ", item_columns!(), " FROM clipboard_items")
+    }
+
+    /// Build an item from a row whose columns follow `ITEM_COLUMNS`
+    pub fn from_row(row: &Row<'_>) -> Result<ClipboardItem> {
+        let metadata: Option<String> = row.get(8)?;
+        let source = metadata.as_deref().map(SourceApp::from_metadata_json).unwrap_or_default();
+        Ok(ClipboardItem {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            data_type: row.get(2)?,
+            is_sensitive: row.get(3)?,
+            is_encrypted: row.get(4)?,
+            preview_text: row.get(5)?,
+            data_size: row.get(6)?,
+            data_blob_id: row.get(7)?,
+            metadata,
+            copy_count: row.get(9)?,
+            is_pinned: row.get(10)?,
+            thumbnail_blob_id: row.get(11)?,
+            source_app_name: source.name,
+            source_bundle_id: source.bundle_id,
+            sensitivity_override: row.get(12)?,
+            tags: row.get::<_, Option<String>>(13)?
+                .map(|tags| tags.split(',').filter(|t| !t.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Canonical form of a tag: no leading `#`, lowercase, and only letters, digits,
+/// `-` and `_`. `None` if nothing valid is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+    let valid = !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(tag)
+}
+
+/// Tags as stored in the `tags` column (`None` when there are none)
+fn join_tags(tags: &[String]) -> Option<String> {
+    (!tags.is_empty()).then(|| tags.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn blob_count(db: &Database) -> i64 {
+        db.conn.query_row("SELECT COUNT(*) FROM clipboard_data", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_encode_blob_roundtrip() {
+        let text = "the same line again\n".repeat(200).into_bytes();
+        let (stored, compression) = encode_blob(&text, true);
+        assert_eq!(compression, COMPRESSION_DEFLATE);
+        assert!(stored.len() < text.len() / 10);
+        assert_eq!(decompress_blob(stored.into_owned(), compression).unwrap(), text);
+
+        // Stored as-is when off, or when deflate wouldn't help
+        assert_eq!(encode_blob(&text, false).1, COMPRESSION_NONE);
+        let (stored, compression) = encode_blob(b"x", true);
+        assert_eq!((&*stored, compression), (&b"x"[..], COMPRESSION_NONE));
+        assert_eq!(decompress_blob(b"x".to_vec(), COMPRESSION_NONE).unwrap(), b"x");
+
+        assert!(decompress_blob(b"x".to_vec(), "zstd").is_err());
+    }
+
+    #[test]
+    fn test_base64_roundtrip() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+        assert_eq!(base64_decode("Zm8"), None);
+        assert_eq!(base64_decode("Z==="), None);
+        assert_eq!(base64_decode("Zm9*"), None);
+    }
+
+    #[test]
+    fn test_crash_between_blob_and_item_leaves_no_orphan() {
+        let mut db = Database::new_in_memory().unwrap();
+        let processed = crate::storage::processor::DataProcessor::process_text("half-written", &[]);
+        let pending = PendingItem::from_processed(&processed, processed.blob.clone(), 1_700_000_000, false);
+
+        let crashed = catch_unwind(AssertUnwindSafe(|| {
+            db.store_pending_item(&pending, || panic!("simulated crash after blob insert"))
+        }));
+        assert!(crashed.is_err());
+
+        assert_eq!(blob_count(&db), 0);
+        assert_eq!(db.count_items().unwrap(), 0);
+
+        // The connection is still usable afterwards
+        let (blob_id, item_id) = db.store_item_transactional(&pending).unwrap();
+        assert_eq!(blob_count(&db), 1);
+        assert_eq!(db.get_item(item_id).unwrap().data_blob_id, blob_id);
+    }
+
+    #[test]
+    fn test_transaction_dropped_without_commit_rolls_back() {
+        let mut db = Database::new_in_memory().unwrap();
+        let processed = crate::storage::processor::DataProcessor::process_text("kept", &[]);
+        let pending = PendingItem::from_processed(&processed, processed.blob.clone(), 1_700_000_000, false);
+        let (_, kept_id) = db.store_item_transactional(&pending).unwrap();
+
+        {
+            let tx = db.begin_write_transaction().unwrap();
+            let blob_id = tx.store_blob_tx(b"discarded").unwrap();
+            tx.store_item_tx(&pending, blob_id).unwrap();
+            tx.delete_item_tx(kept_id).unwrap();
+        }
+
+        assert_eq!(blob_count(&db), 1);
+        assert_eq!(db.count_items().unwrap(), 1);
+        assert!(db.get_item(kept_id).is_ok());
+    }
 }
@@ -1,16 +1,44 @@
 // Encryption module for sensitive clipboard data
 use chacha20poly1305::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     ChaCha20Poly1305, Nonce,
 };
+use hmac::{Hmac, Mac};
 use rand::RngCore;
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use super::database::ItemId;
+use super::keychain::{KeyStore, KeyStoreError};
 
 const NONCE_SIZE: usize = 12; // 96 bits for ChaCha20Poly1305
 
+/// Longest nonce generation may wait on the entropy source before encryption gives up
+const NONCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Run `f` on a helper thread and wait at most `dur` for its result.
+/// On timeout the thread is left to finish (or hang) on its own and `None` is returned.
+pub fn with_timeout<T, F>(dur: Duration, f: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(dur).ok()
+}
+
 pub struct Encryptor {
-    cipher: ChaCha20Poly1305,
+    /// `None` when no key could be loaded; every encrypt/decrypt then fails
+    cipher: Option<ChaCha20Poly1305>,
+    /// HMAC key for `keyed_content_hash`, derived from the encryption key
+    hash_key: Option<[u8; 32]>,
+    /// Where a rotated key is saved; `None` for a key file
+    key_store: Option<Arc<dyn KeyStore>>,
 }
 
 impl Encryptor {
@@ -18,8 +46,153 @@ impl Encryptor {
     /// The key is stored securely in the user's data directory
     pub fn new(key_path: PathBuf) -> Result<Self, String> {
         let key = Self::load_or_create_key(key_path)?;
-        let cipher = ChaCha20Poly1305::new(&key);
-        Ok(Encryptor { cipher })
+        let encryptor = Self::from_key(&key, None);
+        encryptor.verify_integrity()?;
+        Ok(encryptor)
+    }
+
+    /// Create an encryptor whose key lives in the macOS Keychain, generating
+    /// and saving a key on first use
+    pub fn new_keychain(service: &str, account: &str) -> Result<Self, KeyStoreError> {
+        Self::with_key_store(Arc::new(super::keychain::Keychain::new(service, account)))
+    }
+
+    /// Create an encryptor whose key lives in `store`, generating and saving a key on first use
+    pub fn with_key_store(store: Arc<dyn KeyStore>) -> Result<Self, KeyStoreError> {
+        let key = match store.load_key()? {
+            Some(key_bytes) => {
+                if key_bytes.len() != 32 {
+                    return Err(KeyStoreError::Failed("Invalid key length".to_string()));
+                }
+                let mut key = chacha20poly1305::Key::default();
+                key.copy_from_slice(&key_bytes);
+                key
+            }
+            None => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                store.save_key(&key)?;
+                log::info!("🔑 Generated new encryption key in the Keychain");
+                key
+            }
+        };
+
+        let encryptor = Self::from_key(&key, Some(store));
+        encryptor.verify_integrity().map_err(KeyStoreError::Failed)?;
+        Ok(encryptor)
+    }
+
+    /// Prefer the key in `store`, first moving a key file at `key_path` into it.
+    /// If the store refuses access, fall back to the key file with a warning,
+    /// but only while that file exists: once the key has moved into the store,
+    /// a fresh key file would silently orphan every encrypted item.
+    pub fn open(key_path: &Path, store: Arc<dyn KeyStore>) -> Result<Self, KeyStoreError> {
+        let result = Self::migrate_file_key(key_path, store.as_ref())
+            .and_then(|_| Self::with_key_store(store));
+        match result {
+            Ok(encryptor) => Ok(encryptor),
+            Err(KeyStoreError::Denied(e)) if key_path.exists() => {
+                log::warn!("⚠️  {}; using key file {}", e, key_path.display());
+                Self::new(key_path.to_path_buf()).map_err(KeyStoreError::Failed)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// An encryptor without a key, for running when the key is unavailable:
+    /// sensitive items can't be sealed and existing ones can't be opened
+    pub fn disabled() -> Self {
+        Encryptor { cipher: None, hash_key: None, key_store: None }
+    }
+
+    fn from_key(key: &chacha20poly1305::Key, key_store: Option<Arc<dyn KeyStore>>) -> Self {
+        let hash_key = Sha256::new()
+            .chain_update(b"ClipVault content hash key")
+            .chain_update(key)
+            .finalize()
+            .into();
+        Encryptor { cipher: Some(ChaCha20Poly1305::new(key)), hash_key: Some(hash_key), key_store }
+    }
+
+    /// HMAC-SHA256 of `data` under a key derived from the encryption key, in hex.
+    /// Used instead of the plain SHA-256 for encrypted items, so the stored hash
+    /// can't be checked against guessed passwords. `None` when no key is loaded.
+    pub fn keyed_content_hash(&self, data: &[u8]) -> Option<String> {
+        let key = self.hash_key.as_ref()?;
+        // HMAC takes keys of any length, so this never fails
+        let mac = <Hmac<Sha256> as Mac>::new_from_slice(key).ok()?
+            .chain_update(data)
+            .finalize()
+            .into_bytes();
+        Some(mac.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Whether a key is loaded (false for `disabled`)
+    pub fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    fn cipher(&self) -> Result<&ChaCha20Poly1305, String> {
+        self.cipher.as_ref().ok_or_else(|| "Encryption is unavailable: no key loaded".to_string())
+    }
+
+    /// Import the key file at `key_path` into `store`, then delete the file and its
+    /// checksum. Returns whether a key was imported. A store that already holds a
+    /// different key is left alone and the file is kept.
+    pub fn migrate_file_key(key_path: &Path, store: &dyn KeyStore) -> Result<bool, KeyStoreError> {
+        if !key_path.exists() {
+            return Ok(false);
+        }
+        let key = Self::read_key_file(key_path).map_err(KeyStoreError::Failed)?;
+
+        match store.load_key()? {
+            Some(existing) if existing[..] == key[..] => {}
+            Some(_) => {
+                return Err(KeyStoreError::Failed(format!(
+                    "The Keychain already holds a different encryption key than {}",
+                    key_path.display()
+                )));
+            }
+            None => {
+                store.save_key(&key)?;
+                // Only delete the file once the stored copy reads back intact
+                if store.load_key()?.as_deref() != Some(&key[..]) {
+                    return Err(KeyStoreError::Failed("Keychain copy of the encryption key does not match".to_string()));
+                }
+            }
+        }
+
+        fs::remove_file(key_path)
+            .map_err(|e| KeyStoreError::Failed(format!("Failed to remove encryption key file: {}", e)))?;
+        let _ = fs::remove_file(Self::checksum_path(key_path));
+        log::info!("🔑 Moved encryption key from {} into the Keychain", key_path.display());
+        Ok(true)
+    }
+
+    /// Plaintext round-tripped by `verify_integrity`
+    pub fn self_test_plaintext() -> &'static [u8] {
+        b"ClipVault key verification"
+    }
+
+    /// Confirm the loaded key works: a test plaintext must round-trip, and the
+    /// key must not be all zeros (which round-trips fine but means a wiped key file)
+    pub fn verify_integrity(&self) -> Result<(), String> {
+        const FAILED: &str = "Encryption key self-test failed";
+        let plaintext = Self::self_test_plaintext();
+
+        let encrypted = self.encrypt(plaintext).map_err(|_| FAILED)?;
+        let decrypted = self.decrypt(&encrypted).map_err(|_| FAILED)?;
+        if decrypted != plaintext {
+            return Err(FAILED.to_string());
+        }
+
+        // Same nonce and plaintext give the same ciphertext only under the same key
+        let nonce = Nonce::default();
+        let zero_key = ChaCha20Poly1305::new(&chacha20poly1305::Key::default());
+        if self.cipher()?.encrypt(&nonce, plaintext).ok() == zero_key.encrypt(&nonce, plaintext).ok() {
+            log::error!("Encryption key is all zeros");
+            return Err(FAILED.to_string());
+        }
+        Ok(())
     }
 
     /// Load existing key or create a new one
@@ -31,52 +204,191 @@ impl Encryptor {
         }
 
         if key_path.exists() {
-            // Load existing key
-            let key_bytes = fs::read(&key_path)
-                .map_err(|e| format!("Failed to read encryption key: {}", e))?;
-
-            if key_bytes.len() != 32 {
-                return Err("Invalid key length".to_string());
-            }
-
-            let mut key = chacha20poly1305::Key::default();
-            key.copy_from_slice(&key_bytes);
-            Ok(key)
+            Self::read_key_file(&key_path)
         } else {
             // Generate new key
             let key = ChaCha20Poly1305::generate_key(&mut OsRng);
 
-            // Save key with restricted permissions (macOS will set 0600 by default for new files)
-            fs::write(&key_path, key)
-                .map_err(|e| format!("Failed to save encryption key: {}", e))?;
+            // Write key and checksum to temp files first, then rename both into place,
+            // so a crash can't leave a key without its checksum
+            let key_tmp = Self::with_suffix(&key_path, ".tmp");
+            let checksum_path = Self::checksum_path(&key_path);
+            let checksum_tmp = Self::with_suffix(&checksum_path, ".tmp");
 
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms = fs::metadata(&key_path)
-                    .map_err(|e| format!("Failed to get key file metadata: {}", e))?
-                    .permissions();
-                perms.set_mode(0o600); // Owner read/write only
-                fs::set_permissions(&key_path, perms)
-                    .map_err(|e| format!("Failed to set key file permissions: {}", e))?;
-            }
+            Self::write_private(&key_tmp, &key)?;
+            Self::write_private(&checksum_tmp, Self::sha256_hex(&key).as_bytes())?;
+
+            fs::rename(&key_tmp, &key_path)
+                .map_err(|e| format!("Failed to save encryption key: {}", e))?;
+            fs::rename(&checksum_tmp, &checksum_path)
+                .map_err(|e| format!("Failed to save encryption key checksum: {}", e))?;
 
             log::info!("🔑 Generated new encryption key at: {}", key_path.display());
             Ok(key)
         }
     }
 
+    /// Load an existing key file, checking its length and checksum
+    fn read_key_file(key_path: &Path) -> Result<chacha20poly1305::Key, String> {
+        let key_bytes = fs::read(key_path)
+            .map_err(|e| format!("Failed to read encryption key: {}", e))?;
+
+        if key_bytes.len() != 32 {
+            return Err("Invalid key length".to_string());
+        }
+
+        Self::verify_checksum(key_path, &key_bytes)?;
+
+        let mut key = chacha20poly1305::Key::default();
+        key.copy_from_slice(&key_bytes);
+        Ok(key)
+    }
+
+    /// Compare the key against `<key>.sha256`. Keys created before checksums
+    /// existed get one written now (trust on first use).
+    fn verify_checksum(key_path: &Path, key_bytes: &[u8]) -> Result<(), String> {
+        let checksum_path = Self::checksum_path(key_path);
+        let actual = Self::sha256_hex(key_bytes);
+
+        match fs::read_to_string(&checksum_path) {
+            Ok(stored) => {
+                if stored.trim() != actual {
+                    log::error!("Checksum mismatch for {}", key_path.display());
+                    return Err("Encryption key file has been tampered with \u{2014} refusing to start".to_string());
+                }
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::write_private(&checksum_path, actual.as_bytes())?;
+                log::info!("🔑 Recorded checksum for existing encryption key");
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to read encryption key checksum: {}", e)),
+        }
+    }
+
+    /// Generate a fresh key at `new_key_path` (with its checksum) for key rotation.
+    /// Refuses to reuse an existing file, so the returned encryptor never holds the old key.
+    /// The new encryptor saves to the same key store as this one.
+    pub fn rotate_key(&self, new_key_path: &Path) -> Result<Encryptor, String> {
+        // Without the current key nothing could be re-encrypted
+        self.cipher()?;
+        if new_key_path.exists() {
+            return Err(format!("Key file already exists: {}", new_key_path.display()));
+        }
+        let mut encryptor = Self::new(new_key_path.to_path_buf())?;
+        encryptor.key_store = self.key_store.clone();
+        Ok(encryptor)
+    }
+
+    /// Make the key created by `rotate_key` the live one: save it to the key store,
+    /// or move it (and its checksum) over the key file at `key_path`.
+    /// Call this only after every blob has been re-encrypted with the new key.
+    pub fn install_key(&self, new_key_path: &Path, key_path: &Path) -> Result<(), String> {
+        if let Some(store) = &self.key_store {
+            let key = Self::read_key_file(new_key_path)?;
+            store.save_key(&key).map_err(|e| e.to_string())?;
+            Self::discard_key(new_key_path);
+            return Ok(());
+        }
+
+        fs::rename(Self::checksum_path(new_key_path), Self::checksum_path(key_path))
+            .map_err(|e| format!("Failed to install encryption key checksum: {}", e))?;
+        fs::rename(new_key_path, key_path)
+            .map_err(|e| format!("Failed to install encryption key: {}", e))
+    }
+
+    /// Delete a key created by `rotate_key` that was never installed
+    pub fn discard_key(new_key_path: &Path) {
+        let _ = fs::remove_file(Self::checksum_path(new_key_path));
+        let _ = fs::remove_file(new_key_path);
+    }
+
+    /// Rewrite `<key>.sha256` from the key currently on disk (after key rotation)
+    pub fn rotate_checksum(&self, key_path: &Path) -> Result<(), String> {
+        let key_bytes = fs::read(key_path)
+            .map_err(|e| format!("Failed to read encryption key: {}", e))?;
+        let checksum_path = Self::checksum_path(key_path);
+        let checksum_tmp = Self::with_suffix(&checksum_path, ".tmp");
+
+        Self::write_private(&checksum_tmp, Self::sha256_hex(&key_bytes).as_bytes())?;
+        fs::rename(&checksum_tmp, &checksum_path)
+            .map_err(|e| format!("Failed to save encryption key checksum: {}", e))
+    }
+
+    fn checksum_path(key_path: &Path) -> PathBuf {
+        Self::with_suffix(key_path, ".sha256")
+    }
+
+    fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Write a file readable only by the owner
+    fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+        fs::write(path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(path)
+                .map_err(|e| format!("Failed to get key file metadata: {}", e))?
+                .permissions();
+            perms.set_mode(0o600); // Owner read/write only
+            fs::set_permissions(path, perms)
+                .map_err(|e| format!("Failed to set key file permissions: {}", e))?;
+        }
+        Ok(())
+    }
+
     /// Encrypt data and return [nonce || ciphertext]
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
-        // Generate random nonce
-        let mut nonce_bytes = [0u8; NONCE_SIZE];
-        OsRng.fill_bytes(&mut nonce_bytes);
+        self.encrypt_with_aad(plaintext, b"")
+    }
+
+    /// Decrypt data from [nonce || ciphertext]
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, String> {
+        self.decrypt_with_aad(encrypted, b"")
+    }
+
+    /// Encrypt a blob bound to its item, so it can't be moved to another item
+    pub fn encrypt_for_item(&self, plaintext: &[u8], item_id: ItemId) -> Result<Vec<u8>, String> {
+        self.encrypt_with_aad(plaintext, &item_id.0.to_le_bytes())
+    }
+
+    /// Decrypt a blob stored for `item_id`. Blobs written before items were
+    /// bound by AAD are still accepted with an empty AAD.
+    pub fn decrypt_for_item(&self, encrypted: &[u8], item_id: ItemId) -> Result<Vec<u8>, String> {
+        self.decrypt_with_aad(encrypted, &item_id.0.to_le_bytes())
+            .or_else(|e| self.decrypt(encrypted).map_err(|_| e))
+    }
+
+    /// Encrypt data and return [nonce || ciphertext]; `aad` is authenticated but not stored
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = self.cipher()?;
+
+        // Generate random nonce (can block if the entropy source stalls)
+        let nonce_bytes = with_timeout(NONCE_TIMEOUT, || {
+            let mut nonce_bytes = [0u8; NONCE_SIZE];
+            OsRng.fill_bytes(&mut nonce_bytes);
+            nonce_bytes
+        })
+        .ok_or_else(|| {
+            log::error!("Nonce generation timed out after {:?}", NONCE_TIMEOUT);
+            "Encryption timed out".to_string()
+        })?;
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         // Encrypt
-        let ciphertext = self
-            .cipher
-            .encrypt(nonce, plaintext)
+        let ciphertext = cipher
+            .encrypt(nonce, Payload { msg: plaintext, aad })
             .map_err(|e| format!("Encryption failed: {}", e))?;
 
         // Prepend nonce to ciphertext
@@ -87,8 +399,8 @@ impl Encryptor {
         Ok(result)
     }
 
-    /// Decrypt data from [nonce || ciphertext]
-    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, String> {
+    /// Decrypt data from [nonce || ciphertext]; fails unless `aad` matches the one used to encrypt
+    pub fn decrypt_with_aad(&self, encrypted: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
         if encrypted.len() < NONCE_SIZE {
             return Err("Invalid encrypted data: too short".to_string());
         }
@@ -98,12 +410,9 @@ impl Encryptor {
         let nonce = Nonce::from_slice(nonce_bytes);
 
         // Decrypt
-        let plaintext = self
-            .cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| format!("Decryption failed: {}", e))?;
-
-        Ok(plaintext)
+        self.cipher()?
+            .decrypt(nonce, Payload { msg: ciphertext, aad })
+            .map_err(|e| format!("Decryption failed: {}", e))
     }
 }
 
@@ -179,4 +488,286 @@ mod tests {
         OsRng.fill_bytes(&mut bad_data);
         assert!(encryptor.decrypt(&bad_data).is_err());
     }
+
+    #[test]
+    fn test_wrong_aad_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        let encryptor = Encryptor::new(key_path).unwrap();
+
+        let plaintext = b"password=hunter2";
+        let encrypted = encryptor.encrypt_with_aad(plaintext, &1i64.to_le_bytes()).unwrap();
+
+        assert_eq!(encryptor.decrypt_with_aad(&encrypted, &1i64.to_le_bytes()).unwrap(), plaintext);
+        assert!(encryptor.decrypt_with_aad(&encrypted, &2i64.to_le_bytes()).is_err());
+        assert!(encryptor.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_item_bound_blob_cannot_be_swapped() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        let encryptor = Encryptor::new(key_path).unwrap();
+
+        let plaintext = b"sk-1234567890abcdef";
+        let encrypted = encryptor.encrypt_for_item(plaintext, ItemId(7)).unwrap();
+        assert_eq!(encryptor.decrypt_for_item(&encrypted, ItemId(7)).unwrap(), plaintext);
+        assert!(encryptor.decrypt_for_item(&encrypted, ItemId(8)).is_err());
+
+        // Blobs encrypted before AAD binding still decrypt
+        let legacy = encryptor.encrypt(plaintext).unwrap();
+        assert_eq!(encryptor.decrypt_for_item(&legacy, ItemId(8)).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_with_timeout_returns_fast_result() {
+        assert_eq!(with_timeout(Duration::from_millis(500), || 40 + 2), Some(42));
+    }
+
+    #[test]
+    fn test_with_timeout_gives_up_on_slow_closure() {
+        let result = with_timeout(Duration::from_millis(100), || {
+            std::thread::sleep(Duration::from_secs(1));
+            42
+        });
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let temp_dir = TempDir::new().unwrap();
+        let encryptor = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        assert!(encryptor.verify_integrity().is_ok());
+
+        let zeroed = Encryptor::from_key(&chacha20poly1305::Key::default(), None);
+        assert_eq!(zeroed.verify_integrity().unwrap_err(), "Encryption key self-test failed");
+    }
+
+    #[test]
+    fn test_zeroed_key_file_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        // Legacy key without a checksum, so only the self-test can catch it
+        fs::write(&key_path, [0u8; 32]).unwrap();
+        let err = Encryptor::new(key_path).err().unwrap();
+        assert!(err.contains("self-test"));
+    }
+
+    #[test]
+    fn test_key_creation_writes_checksum() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        Encryptor::new(key_path.clone()).unwrap();
+
+        let checksum_path = temp_dir.path().join("test.key.sha256");
+        let key_bytes = fs::read(&key_path).unwrap();
+        let stored = fs::read_to_string(&checksum_path).unwrap();
+        assert_eq!(stored, Encryptor::sha256_hex(&key_bytes));
+        assert_eq!(stored.len(), 64);
+        assert!(!temp_dir.path().join("test.key.tmp").exists());
+        assert!(!temp_dir.path().join("test.key.sha256.tmp").exists());
+    }
+
+    #[test]
+    fn test_tampered_key_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        Encryptor::new(key_path.clone()).unwrap();
+
+        // Swap in a key the attacker knows
+        fs::write(&key_path, [7u8; 32]).unwrap();
+        let err = Encryptor::new(key_path).err().unwrap();
+        assert!(err.contains("tampered"));
+    }
+
+    #[test]
+    fn test_legacy_key_gets_checksum_and_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        fs::write(&key_path, [1u8; 32]).unwrap();
+
+        // No checksum yet: accepted and recorded
+        let encryptor = Encryptor::new(key_path.clone()).unwrap();
+        assert!(temp_dir.path().join("test.key.sha256").exists());
+
+        // After a legitimate key change, rotate_checksum makes it loadable again
+        fs::write(&key_path, [2u8; 32]).unwrap();
+        assert!(Encryptor::new(key_path.clone()).is_err());
+        encryptor.rotate_checksum(&key_path).unwrap();
+        assert!(Encryptor::new(key_path).is_ok());
+    }
+
+    #[test]
+    fn test_rotate_key_installs_fresh_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        let new_key_path = temp_dir.path().join("test.key.new");
+        let old = Encryptor::new(key_path.clone()).unwrap();
+        let sealed = old.encrypt(b"secret").unwrap();
+
+        let new = old.rotate_key(&new_key_path).unwrap();
+        assert!(new.decrypt(&sealed).is_err());
+        // An existing file is never reused as the "new" key
+        assert!(old.rotate_key(&key_path).is_err());
+
+        new.install_key(&new_key_path, &key_path).unwrap();
+        assert!(!new_key_path.exists());
+        assert!(!temp_dir.path().join("test.key.new.sha256").exists());
+
+        // The installed key (and its checksum) load as the live key
+        let reloaded = Encryptor::new(key_path).unwrap();
+        assert_eq!(reloaded.decrypt(&new.encrypt(b"after").unwrap()).unwrap(), b"after");
+        assert!(reloaded.decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_discard_key_removes_new_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let new_key_path = temp_dir.path().join("test.key.new");
+        let old = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        old.rotate_key(&new_key_path).unwrap();
+
+        Encryptor::discard_key(&new_key_path);
+        assert!(!new_key_path.exists());
+        assert!(!temp_dir.path().join("test.key.new.sha256").exists());
+    }
+
+    /// Stands in for the Keychain in tests: the key lives in a temp file
+    struct FileKeyStore(PathBuf);
+
+    impl KeyStore for FileKeyStore {
+        fn load_key(&self) -> Result<Option<Vec<u8>>, KeyStoreError> {
+            match fs::read(&self.0) {
+                Ok(key) => Ok(Some(key)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(KeyStoreError::Failed(e.to_string())),
+            }
+        }
+
+        fn save_key(&self, key: &[u8]) -> Result<(), KeyStoreError> {
+            fs::write(&self.0, key).map_err(|e| KeyStoreError::Failed(e.to_string()))
+        }
+    }
+
+    /// A keychain that always refuses access
+    struct DeniedKeyStore;
+
+    impl KeyStore for DeniedKeyStore {
+        fn load_key(&self) -> Result<Option<Vec<u8>>, KeyStoreError> {
+            Err(KeyStoreError::Denied("locked".to_string()))
+        }
+
+        fn save_key(&self, _key: &[u8]) -> Result<(), KeyStoreError> {
+            Err(KeyStoreError::Denied("locked".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_key_store_creates_then_reuses_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("keychain");
+
+        let first = Encryptor::with_key_store(Arc::new(FileKeyStore(store_path.clone()))).unwrap();
+        assert_eq!(fs::read(&store_path).unwrap().len(), 32);
+        let sealed = first.encrypt(b"secret").unwrap();
+
+        let second = Encryptor::with_key_store(Arc::new(FileKeyStore(store_path))).unwrap();
+        assert_eq!(second.decrypt(&sealed).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_open_migrates_file_key_into_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        let sealed = Encryptor::new(key_path.clone()).unwrap().encrypt(b"secret").unwrap();
+
+        let store = Arc::new(FileKeyStore(temp_dir.path().join("keychain")));
+        let encryptor = Encryptor::open(&key_path, store.clone()).unwrap();
+        assert_eq!(encryptor.decrypt(&sealed).unwrap(), b"secret");
+        assert!(!key_path.exists());
+        assert!(!temp_dir.path().join("test.key.sha256").exists());
+
+        // Nothing left to migrate on the next launch
+        assert!(!Encryptor::migrate_file_key(&key_path, store.as_ref()).unwrap());
+        assert_eq!(Encryptor::open(&key_path, store).unwrap().decrypt(&sealed).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_migration_keeps_file_when_store_has_other_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        Encryptor::new(key_path.clone()).unwrap();
+        let store = FileKeyStore(temp_dir.path().join("keychain"));
+        store.save_key(&[9u8; 32]).unwrap();
+
+        let err = Encryptor::migrate_file_key(&key_path, &store).unwrap_err();
+        assert!(matches!(err, KeyStoreError::Failed(_)));
+        assert!(key_path.exists());
+    }
+
+    #[test]
+    fn test_open_falls_back_to_file_when_denied() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        let sealed = Encryptor::new(key_path.clone()).unwrap().encrypt(b"secret").unwrap();
+
+        let encryptor = Encryptor::open(&key_path, Arc::new(DeniedKeyStore)).unwrap();
+        assert_eq!(encryptor.decrypt(&sealed).unwrap(), b"secret");
+        assert!(key_path.exists());
+    }
+
+    #[test]
+    fn test_denied_store_without_key_file_never_creates_a_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("test.key");
+        Encryptor::new(key_path.clone()).unwrap();
+        let store = Arc::new(FileKeyStore(temp_dir.path().join("keychain")));
+        Encryptor::open(&key_path, store).unwrap();
+        assert!(!key_path.exists());
+
+        // Keychain refuses on a later launch: no fallback key may appear
+        let err = Encryptor::open(&key_path, Arc::new(DeniedKeyStore)).err().unwrap();
+        assert!(matches!(err, KeyStoreError::Denied(_)));
+        assert!(!key_path.exists());
+        assert!(!temp_dir.path().join("test.key.sha256").exists());
+
+        let disabled = Encryptor::disabled();
+        assert!(!disabled.is_enabled());
+        assert!(disabled.encrypt(b"secret").is_err());
+        assert!(disabled.rotate_key(&temp_dir.path().join("test.key.new")).is_err());
+    }
+
+    #[test]
+    fn test_keyed_content_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let encryptor = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let other = Encryptor::new(temp_dir.path().join("other.key")).unwrap();
+
+        let hash = encryptor.keyed_content_hash(b"hunter2").unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(encryptor.keyed_content_hash(b"hunter2").unwrap(), hash);
+        assert_ne!(encryptor.keyed_content_hash(b"hunter3").unwrap(), hash);
+        // Neither the plain SHA-256 nor another key's hash
+        assert_ne!(hash, Encryptor::sha256_hex(b"hunter2"));
+        assert_ne!(other.keyed_content_hash(b"hunter2").unwrap(), hash);
+        assert_eq!(Encryptor::disabled().keyed_content_hash(b"hunter2"), None);
+    }
+
+    #[test]
+    fn test_rotated_key_is_saved_to_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("keychain");
+        let old = Encryptor::with_key_store(Arc::new(FileKeyStore(store_path.clone()))).unwrap();
+        let old_key = fs::read(&store_path).unwrap();
+
+        let new_key_path = temp_dir.path().join("test.key.new");
+        let new = old.rotate_key(&new_key_path).unwrap();
+        new.install_key(&new_key_path, &temp_dir.path().join("test.key")).unwrap();
+
+        assert_ne!(fs::read(&store_path).unwrap(), old_key);
+        assert!(!new_key_path.exists());
+        assert!(!temp_dir.path().join("test.key").exists());
+        let reloaded = Encryptor::with_key_store(Arc::new(FileKeyStore(store_path))).unwrap();
+        assert_eq!(reloaded.decrypt(&new.encrypt(b"after").unwrap()).unwrap(), b"after");
+    }
 }
@@ -0,0 +1,235 @@
+// Encryption key storage in the macOS Keychain
+use std::fmt;
+
+/// Keychain service the encryption key is stored under
+pub const KEYCHAIN_SERVICE: &str = "com.smolkapps.clipboard-manager";
+
+/// Keychain account the encryption key is stored under
+pub const KEYCHAIN_ACCOUNT: &str = "encryption-key";
+
+/// Why a key store couldn't be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyStoreError {
+    /// Access was refused (locked keychain, no entitlement, user cancelled);
+    /// callers may fall back to the key file
+    Denied(String),
+    /// Any other failure
+    Failed(String),
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Denied(e) => write!(f, "Keychain access denied: {}", e),
+            KeyStoreError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Somewhere other than a plain file to keep the encryption key
+pub trait KeyStore: Send + Sync {
+    /// The stored key, or `None` if none has been saved yet
+    fn load_key(&self) -> Result<Option<Vec<u8>>, KeyStoreError>;
+
+    /// Save `key`, replacing any key already stored
+    fn save_key(&self, key: &[u8]) -> Result<(), KeyStoreError>;
+}
+
+/// Generic password item in the user's login keychain
+#[derive(Debug, Clone)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub struct Keychain {
+    service: String,
+    account: String,
+}
+
+impl Keychain {
+    pub fn new(service: &str, account: &str) -> Self {
+        Keychain { service: service.to_string(), account: account.to_string() }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl KeyStore for Keychain {
+    fn load_key(&self) -> Result<Option<Vec<u8>>, KeyStoreError> {
+        use sys::*;
+        unsafe {
+            let service = CfOwned::string(&self.service);
+            let account = CfOwned::string(&self.account);
+            let query = CfOwned::dictionary(&[
+                (kSecClass, kSecClassGenericPassword),
+                (kSecAttrService, service.0),
+                (kSecAttrAccount, account.0),
+                (kSecReturnData, kCFBooleanTrue),
+                (kSecMatchLimit, kSecMatchLimitOne),
+            ]);
+
+            let mut result: CFTypeRef = std::ptr::null();
+            match SecItemCopyMatching(query.0, &mut result) {
+                ERR_SEC_SUCCESS => {
+                    let data = CfOwned(result);
+                    let bytes = CFDataGetBytePtr(data.0);
+                    let len = CFDataGetLength(data.0) as usize;
+                    Ok(Some(std::slice::from_raw_parts(bytes, len).to_vec()))
+                }
+                ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+                status => Err(status_error(status, "read")),
+            }
+        }
+    }
+
+    fn save_key(&self, key: &[u8]) -> Result<(), KeyStoreError> {
+        use sys::*;
+        unsafe {
+            let service = CfOwned::string(&self.service);
+            let account = CfOwned::string(&self.account);
+            let data = CfOwned(CFDataCreate(std::ptr::null(), key.as_ptr(), key.len() as isize));
+            let query = CfOwned::dictionary(&[
+                (kSecClass, kSecClassGenericPassword),
+                (kSecAttrService, service.0),
+                (kSecAttrAccount, account.0),
+            ]);
+            let update = CfOwned::dictionary(&[(kSecValueData, data.0)]);
+
+            match SecItemUpdate(query.0, update.0) {
+                ERR_SEC_SUCCESS => Ok(()),
+                ERR_SEC_ITEM_NOT_FOUND => {
+                    let item = CfOwned::dictionary(&[
+                        (kSecClass, kSecClassGenericPassword),
+                        (kSecAttrService, service.0),
+                        (kSecAttrAccount, account.0),
+                        (kSecAttrAccessible, kSecAttrAccessibleAfterFirstUnlockThisDeviceOnly),
+                        (kSecValueData, data.0),
+                    ]);
+                    match SecItemAdd(item.0, std::ptr::null_mut()) {
+                        ERR_SEC_SUCCESS => Ok(()),
+                        status => Err(status_error(status, "add")),
+                    }
+                }
+                status => Err(status_error(status, "update")),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+impl KeyStore for Keychain {
+    fn load_key(&self) -> Result<Option<Vec<u8>>, KeyStoreError> {
+        Err(KeyStoreError::Denied("the Keychain is only available on macOS".to_string()))
+    }
+
+    fn save_key(&self, _key: &[u8]) -> Result<(), KeyStoreError> {
+        Err(KeyStoreError::Denied("the Keychain is only available on macOS".to_string()))
+    }
+}
+
+/// Map a Security framework status to an error; refusals become `Denied`
+#[cfg(target_os = "macos")]
+fn status_error(status: i32, action: &str) -> KeyStoreError {
+    // errSecUserCanceled, errSecNotAvailable, errSecAuthFailed, errSecNoSuchKeychain,
+    // errSecInteractionNotAllowed, errSecMissingEntitlement
+    const DENIED: [i32; 6] = [-128, -25291, -25293, -25294, -25308, -34018];
+    let message = format!("Failed to {} keychain item (OSStatus {})", action, status);
+    if DENIED.contains(&status) {
+        KeyStoreError::Denied(message)
+    } else {
+        KeyStoreError::Failed(message)
+    }
+}
+
+/// Minimal CoreFoundation and Security framework bindings
+#[cfg(target_os = "macos")]
+#[allow(non_upper_case_globals)]
+mod sys {
+    use std::ffi::c_void;
+
+    pub type CFTypeRef = *const c_void;
+    pub type OSStatus = i32;
+
+    pub const ERR_SEC_SUCCESS: OSStatus = 0;
+    pub const ERR_SEC_ITEM_NOT_FOUND: OSStatus = -25300;
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    /// Dictionary callbacks; only ever passed by address
+    #[repr(C)]
+    pub struct CFDictionaryCallBacks {
+        _private: [u8; 0],
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        pub static kCFBooleanTrue: CFTypeRef;
+        static kCFTypeDictionaryKeyCallBacks: CFDictionaryCallBacks;
+        static kCFTypeDictionaryValueCallBacks: CFDictionaryCallBacks;
+
+        fn CFDictionaryCreate(
+            allocator: CFTypeRef,
+            keys: *const CFTypeRef,
+            values: *const CFTypeRef,
+            count: isize,
+            key_callbacks: *const CFDictionaryCallBacks,
+            value_callbacks: *const CFDictionaryCallBacks,
+        ) -> CFTypeRef;
+        fn CFStringCreateWithBytes(
+            allocator: CFTypeRef,
+            bytes: *const u8,
+            count: isize,
+            encoding: u32,
+            is_external_representation: u8,
+        ) -> CFTypeRef;
+        pub fn CFDataCreate(allocator: CFTypeRef, bytes: *const u8, length: isize) -> CFTypeRef;
+        pub fn CFDataGetLength(data: CFTypeRef) -> isize;
+        pub fn CFDataGetBytePtr(data: CFTypeRef) -> *const u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        pub static kSecClass: CFTypeRef;
+        pub static kSecClassGenericPassword: CFTypeRef;
+        pub static kSecAttrService: CFTypeRef;
+        pub static kSecAttrAccount: CFTypeRef;
+        pub static kSecAttrAccessible: CFTypeRef;
+        pub static kSecAttrAccessibleAfterFirstUnlockThisDeviceOnly: CFTypeRef;
+        pub static kSecValueData: CFTypeRef;
+        pub static kSecReturnData: CFTypeRef;
+        pub static kSecMatchLimit: CFTypeRef;
+        pub static kSecMatchLimitOne: CFTypeRef;
+
+        pub fn SecItemAdd(attributes: CFTypeRef, result: *mut CFTypeRef) -> OSStatus;
+        pub fn SecItemCopyMatching(query: CFTypeRef, result: *mut CFTypeRef) -> OSStatus;
+        pub fn SecItemUpdate(query: CFTypeRef, attributes_to_update: CFTypeRef) -> OSStatus;
+    }
+
+    /// Owned CoreFoundation object, released on drop
+    pub struct CfOwned(pub CFTypeRef);
+
+    impl CfOwned {
+        pub unsafe fn string(s: &str) -> Self {
+            CfOwned(CFStringCreateWithBytes(
+                std::ptr::null(), s.as_ptr(), s.len() as isize, K_CF_STRING_ENCODING_UTF8, 0,
+            ))
+        }
+
+        pub unsafe fn dictionary(pairs: &[(CFTypeRef, CFTypeRef)]) -> Self {
+            let keys: Vec<CFTypeRef> = pairs.iter().map(|(k, _)| *k).collect();
+            let values: Vec<CFTypeRef> = pairs.iter().map(|(_, v)| *v).collect();
+            CfOwned(CFDictionaryCreate(
+                std::ptr::null(),
+                keys.as_ptr(),
+                values.as_ptr(),
+                pairs.len() as isize,
+                &kCFTypeDictionaryKeyCallBacks,
+                &kCFTypeDictionaryValueCallBacks,
+            ))
+        }
+    }
+
+    impl Drop for CfOwned {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                unsafe { CFRelease(self.0) }
+            }
+        }
+    }
+}
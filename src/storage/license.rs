@@ -16,6 +16,9 @@ const GRACE_PERIOD_SECS: i64 = 30 * 24 * 3600;
 /// Maximum clipboard history items for free tier
 pub const FREE_HISTORY_LIMIT: usize = 25;
 
+/// Maximum days items are kept on the free tier
+pub const FREE_RETENTION_DAYS: u32 = 7;
+
 /// Checkout URL for ClipVault Pro
 pub const CHECKOUT_URL: &str = "https://smolkin.org/clipvault-license";
 
@@ -29,6 +32,9 @@ pub struct LicenseInfo {
     pub product_name: Option<String>,
 }
 
+/// Sends a form POST to the license API (`curl_post` outside tests)
+type HttpPost = fn(&str, &[(&str, &str)]) -> Result<ApiResponse, String>;
+
 #[derive(Debug, Deserialize)]
 struct ApiResponse {
     valid: Option<bool>,
@@ -69,6 +75,7 @@ fn validate_key_format(key: &str) -> Result<(), String> {
 pub struct LicenseManager {
     data_dir: PathBuf,
     pro_flag: Arc<AtomicBool>,
+    http_post: HttpPost,
 }
 
 impl LicenseManager {
@@ -76,9 +83,15 @@ impl LicenseManager {
         LicenseManager {
             data_dir: data_dir.to_path_buf(),
             pro_flag,
+            http_post: curl_post,
         }
     }
 
+    /// True once `now` is past the offline grace period since the last validation
+    pub fn is_grace_period_expired(info: &LicenseInfo, now: i64) -> bool {
+        now - info.validated_at >= GRACE_PERIOD_SECS
+    }
+
     #[allow(dead_code)]
     pub fn is_pro(&self) -> bool {
         self.pro_flag.load(Ordering::Relaxed)
@@ -119,12 +132,16 @@ impl LicenseManager {
 
     /// Check license status on startup. Returns true if Pro is active.
     pub fn check_on_startup(&self) -> bool {
+        self.check_on_startup_at(chrono::Utc::now().timestamp())
+    }
+
+    /// `check_on_startup` with an explicit current time (Unix seconds)
+    pub fn check_on_startup_at(&self, now: i64) -> bool {
         let Some(info) = self.load() else {
             self.pro_flag.store(false, Ordering::Relaxed);
             return false;
         };
 
-        let now = chrono::Utc::now().timestamp();
         let age = now - info.validated_at;
 
         if age <= REVALIDATE_SECS {
@@ -153,22 +170,23 @@ impl LicenseManager {
                     e,
                     (GRACE_PERIOD_SECS - age).max(0) / 86400
                 );
-                if age < GRACE_PERIOD_SECS {
-                    self.pro_flag.store(true, Ordering::Relaxed);
-                    true
-                } else {
-                    self.pro_flag.store(false, Ordering::Relaxed);
-                    false
-                }
+                let in_grace = !Self::is_grace_period_expired(&info, now);
+                self.pro_flag.store(in_grace, Ordering::Relaxed);
+                in_grace
             }
         }
     }
 
     /// Activate a license key. Calls Lemon Squeezy API.
     pub fn activate(&self, key: &str) -> Result<LicenseInfo, String> {
+        self.activate_at(key, chrono::Utc::now().timestamp())
+    }
+
+    /// `activate` with an explicit current time, recorded as `validated_at`
+    pub fn activate_at(&self, key: &str, now: i64) -> Result<LicenseInfo, String> {
         validate_key_format(key)?;
         let hostname = get_hostname();
-        let resp = curl_post(ACTIVATE_URL, &[
+        let resp = (self.http_post)(ACTIVATE_URL, &[
             ("license_key", key),
             ("instance_name", &hostname),
         ])?;
@@ -201,7 +219,7 @@ impl LicenseManager {
                 .license_key
                 .and_then(|lk| lk.status)
                 .unwrap_or_else(|| "active".to_string()),
-            validated_at: chrono::Utc::now().timestamp(),
+            validated_at: now,
             customer_email: resp.meta.as_ref().and_then(|m| m.customer_email.clone()),
             product_name: resp.meta.as_ref().and_then(|m| m.product_name.clone()),
         };
@@ -215,7 +233,7 @@ impl LicenseManager {
     /// Deactivate the current license on this machine.
     pub fn deactivate(&self) -> Result<(), String> {
         if let Some(info) = self.load() {
-            let resp = curl_post(DEACTIVATE_URL, &[
+            let resp = (self.http_post)(DEACTIVATE_URL, &[
                 ("license_key", &info.license_key),
                 ("instance_id", &info.instance_id),
             ])?;
@@ -237,7 +255,7 @@ impl LicenseManager {
             fields.push(("instance_id", iid));
         }
 
-        let resp = curl_post(VALIDATE_URL, &fields)?;
+        let resp = (self.http_post)(VALIDATE_URL, &fields)?;
 
         if let Some(meta) = &resp.meta {
             if let Some(name) = &meta.product_name {
@@ -303,3 +321,91 @@ fn format_api_error(err: &serde_json::Value) -> String {
         other => other.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const VALIDATED_AT: i64 = 1_700_000_000;
+
+    fn offline(_url: &str, _fields: &[(&str, &str)]) -> Result<ApiResponse, String> {
+        Err("network unreachable".to_string())
+    }
+
+    fn activation_ok(_url: &str, _fields: &[(&str, &str)]) -> Result<ApiResponse, String> {
+        serde_json::from_str(r#"{
+            "activated": true,
+            "license_key": {"status": "active"},
+            "instance": {"id": "inst-1"},
+            "meta": {"product_name": "ClipVault Pro", "customer_email": "me@example.com"}
+        }"#).map_err(|e| e.to_string())
+    }
+
+    fn manager(temp_dir: &TempDir, http_post: HttpPost) -> LicenseManager {
+        let mut mgr = LicenseManager::new(temp_dir.path(), Arc::new(AtomicBool::new(false)));
+        mgr.http_post = http_post;
+        mgr
+    }
+
+    fn saved_license(mgr: &LicenseManager) -> LicenseInfo {
+        let info = LicenseInfo {
+            license_key: "KEY-123".to_string(),
+            instance_id: "inst-1".to_string(),
+            status: "active".to_string(),
+            validated_at: VALIDATED_AT,
+            customer_email: None,
+            product_name: Some(EXPECTED_PRODUCT.to_string()),
+        };
+        mgr.save(&info).unwrap();
+        info
+    }
+
+    #[test]
+    fn test_recently_validated_needs_no_network() {
+        let temp_dir = TempDir::new().unwrap();
+        let mgr = manager(&temp_dir, |_, _| panic!("should not revalidate"));
+        saved_license(&mgr);
+
+        assert!(mgr.check_on_startup_at(VALIDATED_AT + REVALIDATE_SECS - 1));
+        assert!(mgr.is_pro());
+    }
+
+    #[test]
+    fn test_offline_within_grace_period() {
+        let temp_dir = TempDir::new().unwrap();
+        let mgr = manager(&temp_dir, offline);
+        let info = saved_license(&mgr);
+
+        let now = VALIDATED_AT + REVALIDATE_SECS + 1;
+        assert!(!LicenseManager::is_grace_period_expired(&info, now));
+        assert!(mgr.check_on_startup_at(now));
+        assert!(mgr.is_pro());
+        // Still on disk for the next attempt
+        assert!(mgr.load().is_some());
+    }
+
+    #[test]
+    fn test_offline_after_grace_period() {
+        let temp_dir = TempDir::new().unwrap();
+        let mgr = manager(&temp_dir, offline);
+        let info = saved_license(&mgr);
+
+        let now = VALIDATED_AT + GRACE_PERIOD_SECS + 1;
+        assert!(LicenseManager::is_grace_period_expired(&info, now));
+        assert!(!mgr.check_on_startup_at(now));
+        assert!(!mgr.is_pro());
+    }
+
+    #[test]
+    fn test_activate_at_records_timestamp() {
+        let temp_dir = TempDir::new().unwrap();
+        let mgr = manager(&temp_dir, activation_ok);
+
+        let info = mgr.activate_at("KEY-123", VALIDATED_AT).unwrap();
+        assert_eq!(info.validated_at, VALIDATED_AT);
+        assert_eq!(info.instance_id, "inst-1");
+        assert_eq!(mgr.load().unwrap().validated_at, VALIDATED_AT);
+        assert!(mgr.is_pro());
+    }
+}
@@ -0,0 +1,217 @@
+// Typed schema for the clipboard_items.metadata JSON column
+use serde::{Deserialize, Serialize};
+
+/// Metadata attached to a clipboard item.
+///
+/// Serialized untagged so the JSON shapes match what was stored before the
+/// schema existed (`{"uti_types":[…]}`, `{"width":…,"height":…,"format":…}`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ItemMetadata {
+    Text {
+        uti_types: Vec<String>,
+        /// URLs found inside non-URL, non-sensitive text
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        embedded_urls: Vec<String>,
+    },
+    Image {
+        width: u32,
+        height: u32,
+        format: String,
+        #[serde(default)]
+        thumbnail_width: u32,
+        #[serde(default)]
+        thumbnail_height: u32,
+        #[serde(default)]
+        thumbnail_size: usize,
+        /// Encoding of the stored blob ("PNG" or "JPEG"); older items are PNG
+        #[serde(default = "default_stored_format")]
+        stored_format: String,
+    },
+    File {
+        path: String,
+    },
+    Unknown(serde_json::Value),
+}
+
+/// App that was frontmost when an item was copied. Stored as top-level
+/// `source_app_name`/`source_bundle_id` keys beside the typed metadata;
+/// `ItemMetadata` ignores keys it doesn't know, so every shape still parses.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceApp {
+    #[serde(rename = "source_app_name", default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "source_bundle_id", default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+}
+
+impl SourceApp {
+    /// Source app keys of a stored metadata JSON string (empty if absent or invalid)
+    pub fn from_metadata_json(s: &str) -> SourceApp {
+        serde_json::from_str(s).unwrap_or_default()
+    }
+
+    /// `metadata` with the source app keys added (`None` starts a new object).
+    /// Metadata that isn't a JSON object is returned unchanged.
+    pub fn merge_into_metadata_json(&self, metadata: Option<&str>) -> Option<String> {
+        if self.name.is_none() && self.bundle_id.is_none() {
+            return metadata.map(str::to_string);
+        }
+        let mut object = match metadata.map(serde_json::from_str::<serde_json::Value>) {
+            None => serde_json::Map::new(),
+            Some(Ok(serde_json::Value::Object(object))) => object,
+            Some(_) => return metadata.map(str::to_string),
+        };
+        if let Ok(serde_json::Value::Object(source)) = serde_json::to_value(self) {
+            object.extend(source);
+        }
+        Some(serde_json::Value::Object(object).to_string())
+    }
+}
+
+fn default_stored_format() -> String {
+    "PNG".to_string()
+}
+
+impl ItemMetadata {
+    /// Parse a metadata JSON string. Never fails: anything that doesn't match
+    /// a known shape (including invalid JSON) becomes `Unknown`.
+    pub fn from_json(s: &str) -> ItemMetadata {
+        serde_json::from_str(s).unwrap_or_else(|_| {
+            ItemMetadata::Unknown(serde_json::Value::String(s.to_string()))
+        })
+    }
+
+    /// Pasteboard UTI for an image blob described by this metadata
+    pub fn image_pasteboard_type(&self) -> &'static str {
+        match self {
+            ItemMetadata::Image { stored_format, .. } if stored_format == "JPEG" => "public.jpeg",
+            _ => "public.png",
+        }
+    }
+
+    /// Serialize to the JSON string stored in the database
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "null".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_roundtrip() {
+        let meta = ItemMetadata::Text {
+            uti_types: vec!["public.utf8-plain-text".to_string()],
+            embedded_urls: Vec::new(),
+        };
+        let json = meta.to_json();
+        assert_eq!(json, r#"{"uti_types":["public.utf8-plain-text"]}"#);
+        assert_eq!(ItemMetadata::from_json(&json), meta);
+    }
+
+    #[test]
+    fn test_text_with_embedded_urls_roundtrip() {
+        let meta = ItemMetadata::Text {
+            uti_types: vec![],
+            embedded_urls: vec!["https://example.com".to_string()],
+        };
+        let json = meta.to_json();
+        assert_eq!(json, r#"{"uti_types":[],"embedded_urls":["https://example.com"]}"#);
+        assert_eq!(ItemMetadata::from_json(&json), meta);
+    }
+
+    #[test]
+    fn test_image_roundtrip() {
+        let meta = ItemMetadata::Image {
+            width: 400,
+            height: 300,
+            format: "PNG".to_string(),
+            thumbnail_width: 200,
+            thumbnail_height: 150,
+            thumbnail_size: 1234,
+            stored_format: "JPEG".to_string(),
+        };
+        let json = meta.to_json();
+        assert!(json.contains(r#""thumbnail_width":200"#));
+        assert_eq!(ItemMetadata::from_json(&json), meta);
+    }
+
+    #[test]
+    fn test_image_without_thumbnail_fields() {
+        let meta = ItemMetadata::from_json(r#"{"width":10,"height":20,"format":"TIFF"}"#);
+        assert_eq!(meta.image_pasteboard_type(), "public.png");
+        match meta {
+            ItemMetadata::Image { width, height, format, thumbnail_width, stored_format, .. } => {
+                assert_eq!((width, height), (10, 20));
+                assert_eq!(format, "TIFF");
+                assert_eq!(thumbnail_width, 0);
+                assert_eq!(stored_format, "PNG");
+            }
+            other => panic!("expected Image, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_image_pasteboard_type() {
+        let meta = ItemMetadata::from_json(r#"{"width":10,"height":20,"format":"TIFF","stored_format":"JPEG"}"#);
+        assert_eq!(meta.image_pasteboard_type(), "public.jpeg");
+        let text = ItemMetadata::Text { uti_types: vec![], embedded_urls: vec![] };
+        assert_eq!(text.image_pasteboard_type(), "public.png");
+    }
+
+    #[test]
+    fn test_file_roundtrip() {
+        let meta = ItemMetadata::File { path: "/tmp/report.pdf".to_string() };
+        assert_eq!(ItemMetadata::from_json(&meta.to_json()), meta);
+    }
+
+    #[test]
+    fn test_source_app_roundtrip() {
+        let source = SourceApp {
+            name: Some("Safari".to_string()),
+            bundle_id: Some("com.apple.Safari".to_string()),
+        };
+        let meta = ItemMetadata::Text {
+            uti_types: vec!["public.utf8-plain-text".to_string()],
+            embedded_urls: Vec::new(),
+        };
+        let json = source.merge_into_metadata_json(Some(&meta.to_json())).unwrap();
+        assert!(json.contains(r#""source_app_name":"Safari""#));
+        assert!(json.contains(r#""source_bundle_id":"com.apple.Safari""#));
+        assert_eq!(SourceApp::from_metadata_json(&json), source);
+        assert_eq!(ItemMetadata::from_json(&json), meta);
+
+        // Images keep their shape too
+        let image = r#"{"width":10,"height":20,"format":"PNG"}"#;
+        let json = source.merge_into_metadata_json(Some(image)).unwrap();
+        assert!(matches!(ItemMetadata::from_json(&json), ItemMetadata::Image { width: 10, .. }));
+    }
+
+    #[test]
+    fn test_source_app_without_metadata() {
+        let source = SourceApp { name: Some("Notes".to_string()), bundle_id: None };
+        assert_eq!(source.merge_into_metadata_json(None).as_deref(), Some(r#"{"source_app_name":"Notes"}"#));
+        assert_eq!(SourceApp::default().merge_into_metadata_json(None), None);
+        assert_eq!(source.merge_into_metadata_json(Some("null")).as_deref(), Some("null"));
+        assert_eq!(SourceApp::from_metadata_json("not json"), SourceApp::default());
+        assert_eq!(SourceApp::from_metadata_json(r#"{"uti_types":[]}"#), SourceApp::default());
+    }
+
+    #[test]
+    fn test_unknown_shape() {
+        let meta = ItemMetadata::from_json(r#"{"something":"else"}"#);
+        assert!(matches!(meta, ItemMetadata::Unknown(_)));
+        assert_eq!(meta.to_json(), r#"{"something":"else"}"#);
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let meta = ItemMetadata::from_json("{not json");
+        assert_eq!(
+            meta,
+            ItemMetadata::Unknown(serde_json::Value::String("{not json".to_string()))
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// Versioned schema migrations for the SQLite database
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use log::info;
+use crate::storage::database::merge_duplicate_items;
+
+/// Config key holding the last migration applied
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// A schema change, applied once when the stored version is below its number
+pub type Migration = fn(&Connection) -> Result<()>;
+
+/// Brings a database up to the latest schema, one migration at a time.
+///
+/// Databases created before the migrator record version 1–3 but may already
+/// have any of the later changes, so every migration must also succeed (as a
+/// no-op) on a database that already has its change.
+pub struct Migrator {
+    migrations: Vec<(i32, Migration)>,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Migrator {
+    /// Every migration the app knows, oldest first
+    pub fn new() -> Self {
+        Migrator {
+            migrations: vec![
+                (1, create_tables),
+                (2, add_copy_count),
+                (3, add_thumbnail_blob_id),
+                (4, add_is_pinned),
+                (5, add_slot),
+                (6, add_content_hash),
+                (7, add_sensitivity_override),
+                (8, add_tags),
+                (9, add_blob_hash),
+                (10, add_blob_compression),
+                (11, create_fts_index),
+                (12, merge_duplicates),
+                (13, index_sensitivity),
+                (14, clear_encrypted_content_hashes),
+            ],
+        }
+    }
+
+    /// Version a fully migrated database is at
+    pub fn latest_version(&self) -> i32 {
+        self.migrations.last().map_or(0, |(version, _)| *version)
+    }
+
+    /// Version recorded in `conn`'s config table (0 for a new database)
+    pub fn current_version(conn: &Connection) -> Result<i32> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS config (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        )?;
+        let stored: Option<String> = conn.query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            params![SCHEMA_VERSION_KEY],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(stored.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    /// Apply each migration newer than the stored version, each in its own
+    /// transaction together with the version bump. Returns the version reached.
+    pub fn run(&self, conn: &Connection) -> Result<i32> {
+        let mut version = Self::current_version(conn)?;
+        for (target, migrate) in &self.migrations {
+            if *target <= version {
+                continue;
+            }
+            let tx = conn.unchecked_transaction()?;
+            migrate(&tx)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+                params![SCHEMA_VERSION_KEY, target.to_string()],
+            )?;
+            tx.commit()?;
+            info!("  Migrated database schema to version {}", target);
+            version = *target;
+        }
+        Ok(version)
+    }
+}
+
+/// Whether `table` already has `column`
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// `ALTER TABLE … ADD COLUMN`, skipped when the column is already there
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// Item, blob, trash and config tables with their indexes
+fn create_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS clipboard_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            data_type TEXT NOT NULL,
+            is_sensitive BOOLEAN DEFAULT 0,
+            is_encrypted BOOLEAN DEFAULT 0,
+            preview_text TEXT,
+            data_size INTEGER,
+            data_blob_id INTEGER,
+            metadata TEXT,
+            FOREIGN KEY(data_blob_id) REFERENCES clipboard_data(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_timestamp ON clipboard_items(timestamp DESC);
+        CREATE INDEX IF NOT EXISTS idx_data_type ON clipboard_items(data_type);
+        CREATE INDEX IF NOT EXISTS idx_preview_search ON clipboard_items(preview_text);
+
+        -- Blob storage
+        CREATE TABLE IF NOT EXISTS clipboard_data (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            data BLOB NOT NULL
+        );
+
+        -- Soft-delete trash, mirrors clipboard_items
+        CREATE TABLE IF NOT EXISTS deleted_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            original_id INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            data_type TEXT NOT NULL,
+            is_sensitive BOOLEAN DEFAULT 0,
+            is_encrypted BOOLEAN DEFAULT 0,
+            preview_text TEXT,
+            data_size INTEGER,
+            deleted_blob_id INTEGER,
+            metadata TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_deleted_at ON deleted_items(deleted_at);
+
+        -- Blob storage for soft-deleted items
+        CREATE TABLE IF NOT EXISTS deleted_data (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            data BLOB NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS config (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+fn add_copy_count(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "copy_count", "INTEGER DEFAULT 1")
+}
+
+/// Small PNG shown for image items in the list
+fn add_thumbnail_blob_id(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "thumbnail_blob_id", "INTEGER")?;
+    // Thumbnails belong to their item: every delete path (single delete, cleanup,
+    // history limit, dedup, clear) drops the thumbnail blob with the row
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS clipboard_thumbnail_delete AFTER DELETE ON clipboard_items
+         WHEN old.thumbnail_blob_id IS NOT NULL BEGIN
+             DELETE FROM clipboard_data WHERE id = old.thumbnail_blob_id;
+         END",
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_is_pinned(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "is_pinned", "BOOLEAN DEFAULT 0")?;
+    // Rows added before is_pinned had a default may hold NULL; cleanup compares
+    // the flag directly, so normalize them to unpinned
+    conn.execute("UPDATE clipboard_items SET is_pinned = 0 WHERE is_pinned IS NULL", [])?;
+    Ok(())
+}
+
+/// Named clipboard slots
+fn add_slot(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "slot", "TEXT DEFAULT 'default'")
+}
+
+/// Dedup by content
+fn add_content_hash(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "content_hash", "TEXT")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_content_hash ON clipboard_items(content_hash)", [])?;
+    Ok(())
+}
+
+/// Manual sensitivity (NULL = auto-detected)
+fn add_sensitivity_override(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "sensitivity_override", "BOOLEAN")
+}
+
+/// Comma-separated, normalized by `normalize_tag`
+fn add_tags(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_items", "tags", "TEXT")
+}
+
+/// SHA-256 of the data so identical blobs are stored once. Rows from before
+/// this column and placeholders have none.
+fn add_blob_hash(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_data", "hash", "TEXT")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_blob_hash ON clipboard_data(hash)", [])?;
+    Ok(())
+}
+
+/// How each blob's (plaintext) bytes are encoded, so blobs written before
+/// compression was enabled stay readable
+fn add_blob_compression(conn: &Connection) -> Result<()> {
+    add_column(conn, "clipboard_data", "compression", "TEXT NOT NULL DEFAULT 'none'")?;
+    add_column(conn, "deleted_data", "compression", "TEXT NOT NULL DEFAULT 'none'")
+}
+
+/// Full-text index over previews, kept in sync with clipboard_items by triggers.
+/// '_' is a token character so identifiers like parse_config stay whole.
+fn create_fts_index(conn: &Connection) -> Result<()> {
+    let fts_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'clipboard_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+            preview_text, data_type,
+            content = 'clipboard_items', content_rowid = 'id',
+            tokenize = \"unicode61 tokenchars '_'\"
+        );
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_insert AFTER INSERT ON clipboard_items BEGIN
+            INSERT INTO clipboard_fts (rowid, preview_text, data_type)
+            VALUES (new.id, new.preview_text, new.data_type);
+        END;
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_delete AFTER DELETE ON clipboard_items BEGIN
+            INSERT INTO clipboard_fts (clipboard_fts, rowid, preview_text, data_type)
+            VALUES ('delete', old.id, old.preview_text, old.data_type);
+        END;
+        CREATE TRIGGER IF NOT EXISTS clipboard_fts_update AFTER UPDATE OF preview_text, data_type ON clipboard_items BEGIN
+            INSERT INTO clipboard_fts (clipboard_fts, rowid, preview_text, data_type)
+            VALUES ('delete', old.id, old.preview_text, old.data_type);
+            INSERT INTO clipboard_fts (rowid, preview_text, data_type)
+            VALUES (new.id, new.preview_text, new.data_type);
+        END;",
+    )?;
+    if !fts_exists {
+        // Index items stored before the index existed
+        conn.execute("INSERT INTO clipboard_fts (clipboard_fts) VALUES ('rebuild')", [])?;
+    }
+    Ok(())
+}
+
+/// Merge duplicates left behind by older dedup logic and fix their counts.
+/// Rows from before content hashes have none yet and are left alone; the
+/// backfill merges them once it has hashed them.
+fn merge_duplicates(conn: &Connection) -> Result<()> {
+    merge_duplicate_items(conn).map(|_| ())
+}
+
+/// Index for the Sensitive tab and `get_items_by_sensitivity`, which filter on
+/// the flag after any manual override
+fn index_sensitivity(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_effective_sensitivity
+         ON clipboard_items(COALESCE(sensitivity_override, is_sensitive))",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Encrypted items used to store the plain SHA-256 of their plaintext; new
+/// ones store a keyed hash (see `Database::content_hash_for`)
+fn clear_encrypted_content_hashes(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE clipboard_items SET content_hash = NULL WHERE is_encrypted = 1", [])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let names = stmt.query_map([], |row| row.get(1)).unwrap();
+        names.collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn test_new_database_reaches_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrator = Migrator::new();
+
+        assert_eq!(Migrator::current_version(&conn).unwrap(), 0);
+        assert_eq!(migrator.run(&conn).unwrap(), migrator.latest_version());
+        assert_eq!(Migrator::current_version(&conn).unwrap(), migrator.latest_version());
+
+        let item_columns = columns(&conn, "clipboard_items");
+        for column in ["copy_count", "thumbnail_blob_id", "is_pinned", "content_hash", "tags"] {
+            assert!(item_columns.iter().any(|c| c == column), "missing {}", column);
+        }
+    }
+
+    #[test]
+    fn test_running_again_is_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrator = Migrator::new();
+        migrator.run(&conn).unwrap();
+        let before = columns(&conn, "clipboard_items");
+
+        assert_eq!(migrator.run(&conn).unwrap(), migrator.latest_version());
+        assert_eq!(columns(&conn, "clipboard_items"), before);
+    }
+
+    #[test]
+    fn test_pre_migrator_database_catches_up() {
+        // Databases from before the migrator have every column but record version 3
+        let conn = Connection::open_in_memory().unwrap();
+        let migrator = Migrator::new();
+        migrator.run(&conn).unwrap();
+        conn.execute("UPDATE config SET value = '3' WHERE key = ?1", params![SCHEMA_VERSION_KEY]).unwrap();
+
+        assert_eq!(migrator.run(&conn).unwrap(), migrator.latest_version());
+    }
+
+    #[test]
+    fn test_upgrade_keeps_different_items_sharing_a_preview() {
+        // A database from before content hashes, with two different same-size screenshots
+        let conn = Connection::open_in_memory().unwrap();
+        let migrator = Migrator::new();
+        let before_hashes = Migrator {
+            migrations: migrator.migrations.iter().copied().take_while(|(version, _)| *version < 6).collect(),
+        };
+        before_hashes.run(&conn).unwrap();
+        for (timestamp, data) in [(100, &b"first png"[..]), (200, &b"second png"[..])] {
+            conn.execute("INSERT INTO clipboard_data (data) VALUES (?1)", params![data]).unwrap();
+            conn.execute(
+                "INSERT INTO clipboard_items (timestamp, data_type, preview_text, data_size, data_blob_id, copy_count)
+                 VALUES (?1, 'image', '1920x1080 PNG', ?2, ?3, 1)",
+                params![timestamp, data.len() as i64, conn.last_insert_rowid()],
+            ).unwrap();
+        }
+
+        assert_eq!(migrator.run(&conn).unwrap(), migrator.latest_version());
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count("clipboard_items"), 2);
+        assert_eq!(count("clipboard_data"), 2);
+    }
+
+    #[test]
+    fn test_failed_migration_keeps_previous_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let failing: Migration = |conn| conn.execute("ALTER TABLE no_such_table ADD COLUMN x TEXT", []).map(|_| ());
+        let migrator = Migrator { migrations: vec![(1, create_tables), (2, failing)] };
+
+        assert!(migrator.run(&conn).is_err());
+        assert_eq!(Migrator::current_version(&conn).unwrap(), 1);
+    }
+}
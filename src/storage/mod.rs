@@ -2,12 +2,19 @@
 pub mod database;
 pub mod processor;
 pub mod encryption;
+pub mod keychain;
 pub mod search;
 pub mod config;
 pub mod license;
+pub mod metadata;
+pub mod migrations;
+pub mod sensitivity;
 
-pub use database::{Database, ClipboardItem};
+pub use database::{Database, ClipboardItem, DatabaseStatistics, DeletedItem, ItemId, BlobId, PendingItem, StorageBreakdown};
 pub use processor::DataProcessor;
 pub use encryption::Encryptor;
-pub use config::AppConfig;
+pub use keychain::{Keychain, KeyStore, KeyStoreError, KEYCHAIN_ACCOUNT, KEYCHAIN_SERVICE};
+pub use config::{AppConfig, PopupPlacement, RetentionPolicy};
 pub use license::LicenseManager;
+pub use metadata::{ItemMetadata, SourceApp};
+pub use sensitivity::SensitivityAnalyzer;
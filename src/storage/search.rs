@@ -1,52 +1,212 @@
 // Fuzzy search for clipboard history
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
-use crate::storage::database::ClipboardItem;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::storage::database::{normalize_tag, ClipboardItem, Database};
+
+/// Recent items fuzzy-matched when the full-text index finds nothing
+pub const FUZZY_FALLBACK_WINDOW: i32 = 500;
 
 pub struct SearchEngine {
     matcher: SkimMatcherV2,
+    metadata_cache: RefCell<MetadataCache>,
+}
+
+/// Flattened metadata strings by item ID and hash of the metadata JSON, so an
+/// item whose metadata changed is flattened again
+#[derive(Default)]
+struct MetadataCache {
+    flattened: HashMap<(i64, u64), String>,
 }
 
 impl SearchEngine {
     pub fn new() -> Self {
         SearchEngine {
             matcher: SkimMatcherV2::default().ignore_case(),
+            metadata_cache: RefCell::new(MetadataCache::default()),
         }
     }
 
     /// Search clipboard items by query string
     /// Returns items sorted by relevance score (highest first)
+    ///
+    /// `#tag` words restrict the results to items carrying every such tag; the
+    /// rest of the query is fuzzy-matched as usual.
     pub fn search<'a>(&self, items: &'a [ClipboardItem], query: &str) -> Vec<(i64, &'a ClipboardItem)> {
-        if query.is_empty() {
-            // No query - return all items with neutral score
-            return items.iter().map(|item| (0, item)).collect();
+        let (tags, text) = Self::split_tags(query);
+        let mut results = self.search_text(items, &text);
+        if !tags.is_empty() {
+            results.retain(|(_, item)| tags.iter().all(|tag| item.has_tag(tag)));
+        }
+        results
+    }
+
+    /// Split `#tag` words out of a query: (tags, remaining text)
+    pub fn split_tags(query: &str) -> (Vec<String>, String) {
+        let mut tags = Vec::new();
+        let mut text = Vec::new();
+        for word in query.split_whitespace() {
+            match word.strip_prefix('#').and_then(normalize_tag) {
+                Some(tag) => tags.push(tag),
+                None => text.push(word),
+            }
+        }
+        (tags, text.join(" "))
+    }
+
+    fn search_text<'a>(&self, items: &'a [ClipboardItem], query: &str) -> Vec<(i64, &'a ClipboardItem)> {
+        if query.trim().is_empty() {
+            return Self::ranked_by_recency(items);
         }
 
+        let mut cache = self.metadata_cache_for(items);
         let mut results: Vec<(i64, &ClipboardItem)> = items
             .iter()
             .filter_map(|item| {
-                // Search in preview text
-                if let Some(preview) = &item.preview_text {
-                    if let Some(score) = self.matcher.fuzzy_match(preview, query) {
-                        return Some((score, item));
-                    }
-                }
+                // Search in preview text, falling back to the data type
+                let text_score = item.preview_text.as_deref()
+                    .and_then(|preview| self.matcher.fuzzy_match(preview, query))
+                    .or_else(|| self.matcher.fuzzy_match(&item.data_type, query));
 
-                // Search in data type
-                if let Some(score) = self.matcher.fuzzy_match(&item.data_type, query) {
-                    return Some((score, item));
-                }
-
-                None
+                // Union with metadata matches, keeping the better score
+                let metadata_score = self.metadata_score(&mut cache, item, query);
+                text_score.max(metadata_score).map(|score| (score, item))
             })
             .collect();
 
-        // Sort by score (highest first), then by timestamp (newest first)
+        Self::sort_by_score(&mut results);
+        results
+    }
+
+    /// Search the whole history through the database's full-text index, best
+    /// match first. Falls back to fuzzy matching over the most recent
+    /// `FUZZY_FALLBACK_WINDOW` items (which also catches typos and metadata
+    /// matches) when the index finds nothing or can't be queried.
+    pub fn search_database(&self, db: &Database, query: &str, limit: i32) -> rusqlite::Result<Vec<ClipboardItem>> {
+        // The index knows nothing about tags: narrow to the first tag in SQL instead
+        if let Some(tag) = Self::split_tags(query).0.first() {
+            let items = db.get_items_by_tag(tag, FUZZY_FALLBACK_WINDOW)?;
+            return Ok(self.search(&items, query)
+                .into_iter()
+                .take(limit.max(0) as usize)
+                .map(|(_, item)| item.clone())
+                .collect());
+        }
+
+        match db.fts_search(query, limit) {
+            Ok(items) if !items.is_empty() => return Ok(items),
+            Ok(_) => {}
+            Err(e) => log::warn!("Full-text search failed, using fuzzy search: {}", e),
+        }
+
+        let items = db.get_recent_items(FUZZY_FALLBACK_WINDOW)?;
+        Ok(self.search(&items, query)
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, item)| item.clone())
+            .collect())
+    }
+
+    /// Search only the items' metadata (dimensions, UTIs, embedded URLs, …),
+    /// flattened to `[key: value, …]`. Sorted like `search`.
+    pub fn search_metadata<'a>(&self, items: &'a [ClipboardItem], query: &str) -> Vec<(i64, &'a ClipboardItem)> {
+        let mut cache = self.metadata_cache_for(items);
+        let mut results: Vec<(i64, &ClipboardItem)> = items
+            .iter()
+            .filter_map(|item| self.metadata_score(&mut cache, item, query).map(|score| (score, item)))
+            .collect();
+
+        Self::sort_by_score(&mut results);
+        results
+    }
+
+    /// Items whose metadata field `key` equals `value` (ASCII case-insensitive),
+    /// or contains it when the field is an array. This scans the given items;
+    /// with a database at hand, `Database::search_metadata_field` and
+    /// `Database::search_metadata_contains` do the same in SQL.
+    pub fn search_by_metadata_field<'a>(&self, items: &'a [ClipboardItem], key: &str, value: &str) -> Vec<&'a ClipboardItem> {
+        let matches = |field: &serde_json::Value| match field {
+            serde_json::Value::String(s) => s.eq_ignore_ascii_case(value),
+            serde_json::Value::Null | serde_json::Value::Object(_) | serde_json::Value::Array(_) => false,
+            other => serde_json::from_str::<serde_json::Value>(value).is_ok_and(|v| &v == other),
+        };
+
+        items
+            .iter()
+            .filter(|item| {
+                let Some(metadata) = item.metadata.as_deref() else { return false };
+                let Ok(json) = serde_json::from_str::<serde_json::Value>(metadata) else { return false };
+                match json.get(key) {
+                    Some(serde_json::Value::Array(values)) => values.iter().any(matches),
+                    Some(field) => matches(field),
+                    None => false,
+                }
+            })
+            .collect()
+    }
+
+    /// Sort by score (highest first), then by timestamp (newest first)
+    fn sort_by_score(results: &mut [(i64, &ClipboardItem)]) {
         results.sort_by(|a, b| {
             b.0.cmp(&a.0).then_with(|| b.1.timestamp.cmp(&a.1.timestamp))
         });
+    }
 
-        results
+    /// The metadata cache, cleared first if it holds more entries than `items`
+    /// can use (stale ones from other items or edited metadata)
+    fn metadata_cache_for(&self, items: &[ClipboardItem]) -> std::cell::RefMut<'_, MetadataCache> {
+        let mut cache = self.metadata_cache.borrow_mut();
+        if cache.flattened.len() > items.len() {
+            cache.flattened.clear();
+        }
+        cache
+    }
+
+    fn metadata_score(&self, cache: &mut MetadataCache, item: &ClipboardItem, query: &str) -> Option<i64> {
+        let metadata = item.metadata.as_deref()?;
+        let mut hasher = DefaultHasher::new();
+        metadata.hash(&mut hasher);
+        let flattened = cache.flattened
+            .entry((item.id.0, hasher.finish()))
+            .or_insert_with(|| Self::flatten_metadata(metadata));
+        self.matcher.fuzzy_match(flattened, query)
+    }
+
+    /// Render metadata JSON as `[key1: value1, key2: value2, …]`
+    fn flatten_metadata(metadata: &str) -> String {
+        fn collect(key: &str, value: &serde_json::Value, out: &mut Vec<String>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (k, v) in map {
+                        collect(k, v, out);
+                    }
+                }
+                serde_json::Value::Array(values) => {
+                    for v in values {
+                        collect(key, v, out);
+                    }
+                }
+                serde_json::Value::Null => {}
+                serde_json::Value::String(s) => out.push(format!("{}: {}", key, s)),
+                other => out.push(format!("{}: {}", key, other)),
+            }
+        }
+
+        let mut parts = Vec::new();
+        match serde_json::from_str::<serde_json::Value>(metadata) {
+            Ok(value) => collect("metadata", &value, &mut parts),
+            Err(_) => parts.push(metadata.to_string()),
+        }
+        format!("[{}]", parts.join(", "))
+    }
+
+    /// All items with a neutral score, in their given order. Items from
+    /// `get_recent_items` are already newest-first, so no sort is needed.
+    pub fn ranked_by_recency(items: &[ClipboardItem]) -> Vec<(i64, &ClipboardItem)> {
+        items.iter().map(|item| (0, item)).collect()
     }
 }
 
@@ -59,23 +219,51 @@ impl Default for SearchEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::database::{BlobId, ItemId};
+
+    #[test]
+    fn test_flatten_metadata() {
+        let flat = SearchEngine::flatten_metadata(r#"{"width":1920,"height":1080,"format":"PNG"}"#);
+        assert_eq!(flat, "[format: PNG, height: 1080, width: 1920]");
+
+        let flat = SearchEngine::flatten_metadata(r#"{"uti_types":["public.rtf","public.utf8-plain-text"]}"#);
+        assert_eq!(flat, "[uti_types: public.rtf, uti_types: public.utf8-plain-text]");
+    }
 
     fn create_test_item(id: i64, preview: &str, timestamp: i64) -> ClipboardItem {
         ClipboardItem {
-            id,
+            id: ItemId(id),
             timestamp,
             data_type: "text".to_string(),
             is_sensitive: false,
             is_encrypted: false,
             preview_text: Some(preview.to_string()),
             data_size: preview.len() as i64,
-            data_blob_id: id,
+            data_blob_id: BlobId(id),
             metadata: None,
             copy_count: 1,
             is_pinned: false,
+            thumbnail_blob_id: None,
+            source_app_name: None,
+            source_bundle_id: None,
+            sensitivity_override: None,
+            tags: Vec::new(),
         }
     }
 
+    #[test]
+    fn test_metadata_search_sees_edited_metadata() {
+        let engine = SearchEngine::new();
+        let mut items = vec![create_test_item(1, "screenshot", 100)];
+        items[0].metadata = Some(r#"{"format":"PNG"}"#.to_string());
+        assert_eq!(engine.search_metadata(&items, "png").len(), 1);
+
+        // Same slice, same length: only the metadata changed
+        items[0].metadata = Some(r#"{"format":"TIFF"}"#.to_string());
+        assert!(engine.search_metadata(&items, "png").is_empty());
+        assert_eq!(engine.search_metadata(&items, "tiff").len(), 1);
+    }
+
     #[test]
     fn test_fuzzy_search() {
         let engine = SearchEngine::new();
@@ -102,6 +290,10 @@ mod tests {
 
         let results = engine.search(&items, "");
         assert_eq!(results.len(), 2); // Should return all items
+
+        // Whitespace-only behaves like empty
+        let results = engine.search(&items, "   ");
+        assert_eq!(results.len(), 2);
     }
 
     #[test]
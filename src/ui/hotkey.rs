@@ -1,34 +1,298 @@
 // Global hotkey registration for clipboard popup
 use global_hotkey::{GlobalHotKeyManager, hotkey::{HotKey, Modifiers, Code}};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+use clipboard_manager::LockExt;
+use crate::ui::popup::PopupWindow;
+
+/// How often the background thread checks that the hotkey is still registered
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// IDs of the hotkeys whose last `register` call succeeded
+type Registrations = Rc<RefCell<HashSet<u32>>>;
+
+/// What a registered global hotkey does when pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    TogglePopup,
+    PasteMostRecent,
+    TogglePrivacyMode,
+    OpenPreferences,
+}
+
+thread_local! {
+    /// Main-thread handle for the health check (GlobalHotKeyManager is not Send)
+    static HEALTH_CHECK_TARGET: RefCell<Option<(Weak<GlobalHotKeyManager>, Vec<HotKey>, Registrations)>> = const { RefCell::new(None) };
+    /// Hotkey ID → action, and the popup the actions act on, for `HotkeyManager::dispatch`
+    static DISPATCH_TARGET: RefCell<Option<(HashMap<u32, HotkeyAction>, Arc<Mutex<PopupWindow>>)>> = const { RefCell::new(None) };
+}
 
 pub struct HotkeyManager {
-    manager: GlobalHotKeyManager,
-    hotkey: HotKey,
+    manager: Rc<GlobalHotKeyManager>,
+    hotkeys: Vec<HotKey>,
+    registrations: Registrations,
+    /// Times the health check found a hotkey unregistered
+    registration_failures: Arc<AtomicU32>,
+    health_check_running: Arc<AtomicBool>,
 }
 
 impl HotkeyManager {
-    /// Register Cmd+Shift+C as the global hotkey.
-    /// Events are polled separately in main.rs via GlobalHotKeyEvent::receiver().
-    pub fn new() -> Result<Self, String> {
+    /// Hotkeys registered by `new`: Cmd+Shift+C toggles the popup
+    pub fn default_hotkeys() -> Vec<(HotKey, HotkeyAction)> {
+        vec![(
+            HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyC),
+            HotkeyAction::TogglePopup,
+        )]
+    }
+
+    /// Register the default hotkeys. See `new_with_multiple_hotkeys`.
+    pub fn new(popup: Arc<Mutex<PopupWindow>>) -> Result<Self, String> {
+        Self::new_with_multiple_hotkeys(popup, &Self::default_hotkeys())
+    }
+
+    /// Register every hotkey in `hotkeys`, each bound to its action.
+    /// Events are polled separately in main.rs via GlobalHotKeyEvent::receiver()
+    /// and handed to `dispatch`. Must be called on the main thread.
+    pub fn new_with_multiple_hotkeys(
+        popup: Arc<Mutex<PopupWindow>>,
+        hotkeys: &[(HotKey, HotkeyAction)],
+    ) -> Result<Self, String> {
+        let actions = Self::action_map(hotkeys)?;
+
         let manager = GlobalHotKeyManager::new()
             .map_err(|e| format!("Failed to create hotkey manager: {}", e))?;
 
-        let hotkey = HotKey::new(
-            Some(Modifiers::SUPER | Modifiers::SHIFT),
-            Code::KeyC,
-        );
+        let mut registered = Vec::with_capacity(hotkeys.len());
+        for (hotkey, action) in hotkeys {
+            if let Err(e) = manager.register(*hotkey) {
+                let _ = manager.unregister_all(&registered);
+                return Err(format!("Failed to register hotkey for {:?}: {}", action, e));
+            }
+            registered.push(*hotkey);
+        }
+
+        let manager = Rc::new(manager);
+        let registrations: Registrations = Rc::new(RefCell::new(registered.iter().map(HotKey::id).collect()));
+        HEALTH_CHECK_TARGET.with(|target| {
+            *target.borrow_mut() = Some((Rc::downgrade(&manager), registered.clone(), Rc::clone(&registrations)));
+        });
+        DISPATCH_TARGET.with(|target| *target.borrow_mut() = Some((actions, popup)));
+
+        let registration_failures = Arc::new(AtomicU32::new(0));
+        let health_check_running = Arc::new(AtomicBool::new(true));
+        Self::spawn_health_check(Arc::clone(&health_check_running), Arc::clone(&registration_failures));
 
-        manager.register(hotkey)
-            .map_err(|e| format!("Failed to register hotkey: {}", e))?;
+        Ok(HotkeyManager { manager, hotkeys: registered, registrations, registration_failures, health_check_running })
+    }
+
+    /// Index actions by `HotKey::id()`, rejecting two bindings of the same key combination
+    fn action_map(hotkeys: &[(HotKey, HotkeyAction)]) -> Result<HashMap<u32, HotkeyAction>, String> {
+        let mut actions = HashMap::with_capacity(hotkeys.len());
+        for (hotkey, action) in hotkeys {
+            if let Some(existing) = actions.insert(hotkey.id(), *action) {
+                return Err(format!("Hotkey {:?} is bound to both {:?} and {:?}", hotkey, existing, action));
+            }
+        }
+        Ok(actions)
+    }
 
-        Ok(HotkeyManager { manager, hotkey })
+    /// Run the action bound to the hotkey with this ID. Must be called on the main thread.
+    pub fn dispatch(hotkey_id: u32) {
+        let target = DISPATCH_TARGET.with(|target| {
+            target.borrow().as_ref().map(|(actions, popup)| (actions.get(&hotkey_id).copied(), Arc::clone(popup)))
+        });
+        let Some((action, popup)) = target else { return };
+        match action {
+            Some(action) => Self::perform(action, &popup),
+            None => log::warn!("No action bound to hotkey {}", hotkey_id),
+        }
+    }
+
+    fn perform(action: HotkeyAction, popup: &Arc<Mutex<PopupWindow>>) {
+        match action {
+            HotkeyAction::TogglePopup => {
+                if let Some(mut popup) = popup.lock_or_log() {
+                    popup.toggle();
+                }
+            }
+            HotkeyAction::PasteMostRecent
+            | HotkeyAction::TogglePrivacyMode
+            | HotkeyAction::OpenPreferences => {
+                log::warn!("Hotkey action {:?} is not available yet", action);
+            }
+        }
+    }
+
+    /// Whether every hotkey is registered with the manager. global-hotkey can't
+    /// be queried (registering again on macOS never reports `AlreadyRegistered`),
+    /// so this reflects the result of the last `register` call for each.
+    pub fn is_registered(&self) -> bool {
+        self.hotkeys.iter().all(|hotkey| Self::probe(&self.registrations, *hotkey))
+    }
+
+    /// Number of times the hotkey had to be re-registered (or failed to be)
+    pub fn registration_failure_count(&self) -> u32 {
+        self.registration_failures.load(Ordering::Relaxed)
+    }
+
+    /// Whether `hotkey` is registered, without touching the manager
+    fn probe(registrations: &RefCell<HashSet<u32>>, hotkey: HotKey) -> bool {
+        registrations.borrow().contains(&hotkey.id())
+    }
+
+    /// Re-register if the hotkey was dropped, counting each occurrence.
+    /// Returns whether the hotkey is registered afterwards.
+    fn ensure_registered(
+        is_registered: impl FnOnce() -> bool,
+        register: impl FnOnce() -> Result<(), String>,
+        registration_failures: &AtomicU32,
+    ) -> bool {
+        if is_registered() {
+            return true;
+        }
+
+        registration_failures.fetch_add(1, Ordering::Relaxed);
+        match register() {
+            Ok(()) => {
+                log::warn!("Global hotkey was unregistered; re-registered successfully");
+                true
+            }
+            Err(e) => {
+                log::error!("Global hotkey was unregistered and could not be re-registered: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Every `HEALTH_CHECK_INTERVAL`, hop to the main thread and verify the registration
+    fn spawn_health_check(running: Arc<AtomicBool>, registration_failures: Arc<AtomicU32>) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(HEALTH_CHECK_INTERVAL);
+            if !running.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let registration_failures = Arc::clone(&registration_failures);
+            dispatch::Queue::main().exec_async(move || {
+                let target = HEALTH_CHECK_TARGET.with(|target| target.borrow().clone());
+                let Some((manager, hotkeys, registrations)) = target else { return };
+                let Some(manager) = manager.upgrade() else { return };
+
+                for hotkey in hotkeys {
+                    Self::ensure_registered(
+                        || Self::probe(&registrations, hotkey),
+                        || {
+                            manager.register(hotkey).map_err(|e| e.to_string())?;
+                            registrations.borrow_mut().insert(hotkey.id());
+                            Ok(())
+                        },
+                        &registration_failures,
+                    );
+                }
+            });
+        });
     }
 }
 
 impl Drop for HotkeyManager {
     fn drop(&mut self) {
-        if let Err(e) = self.manager.unregister(self.hotkey) {
-            log::error!("Failed to unregister hotkey: {}", e);
+        self.health_check_running.store(false, Ordering::Relaxed);
+        HEALTH_CHECK_TARGET.with(|target| *target.borrow_mut() = None);
+        DISPATCH_TARGET.with(|target| *target.borrow_mut() = None);
+
+        self.registrations.borrow_mut().clear();
+        if let Err(e) = self.manager.unregister_all(&self.hotkeys) {
+            log::error!("Failed to unregister hotkeys: {}", e);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registered_hotkey_is_left_alone() {
+        let failures = AtomicU32::new(0);
+        let ok = HotkeyManager::ensure_registered(
+            || true,
+            || panic!("should not re-register"),
+            &failures,
+        );
+        assert!(ok);
+        assert_eq!(failures.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_probe_leaves_registered_hotkey_working() {
+        let hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyC);
+        let registrations = RefCell::new(HashSet::from([hotkey.id()]));
+        let failures = AtomicU32::new(0);
+
+        // Every health check finds it registered and never re-registers or drops it
+        for _ in 0..3 {
+            assert!(HotkeyManager::ensure_registered(
+                || HotkeyManager::probe(&registrations, hotkey),
+                || panic!("should not re-register"),
+                &failures,
+            ));
+        }
+        assert!(registrations.borrow().contains(&hotkey.id()));
+        assert_eq!(failures.load(Ordering::Relaxed), 0);
+
+        let other = HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyV);
+        assert!(!HotkeyManager::probe(&registrations, other));
+    }
+
+    #[test]
+    fn test_reregistration_increments_failure_count() {
+        let failures = AtomicU32::new(0);
+        let mut registered = false;
+        let ok = HotkeyManager::ensure_registered(
+            || false,
+            || {
+                registered = true;
+                Ok(())
+            },
+            &failures,
+        );
+        assert!(ok);
+        assert!(registered);
+        assert_eq!(failures.load(Ordering::Relaxed), 1);
+
+        let ok = HotkeyManager::ensure_registered(
+            || false,
+            || Err("taken by another app".to_string()),
+            &failures,
+        );
+        assert!(!ok);
+        assert_eq!(failures.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_hotkeys_dispatch_to_their_own_actions() {
+        let toggle = HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyC);
+        let paste = HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyV);
+        assert_ne!(toggle.id(), paste.id());
+
+        let actions = HotkeyManager::action_map(&[
+            (toggle, HotkeyAction::TogglePopup),
+            (paste, HotkeyAction::PasteMostRecent),
+        ]).unwrap();
+        assert_eq!(actions.get(&toggle.id()), Some(&HotkeyAction::TogglePopup));
+        assert_eq!(actions.get(&paste.id()), Some(&HotkeyAction::PasteMostRecent));
+    }
+
+    #[test]
+    fn test_same_hotkey_cannot_bind_two_actions() {
+        let hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::SHIFT), Code::KeyC);
+        assert!(HotkeyManager::action_map(&[
+            (hotkey, HotkeyAction::TogglePopup),
+            (hotkey, HotkeyAction::OpenPreferences),
+        ]).is_err());
+    }
+}
@@ -1,6 +1,18 @@
 // Launch at Login management via Apple ServiceManagement Framework
 use objc2::{class, msg_send, msg_send_id, rc::Id};
 use objc2_foundation::{NSString, NSProcessInfo};
+use std::path::{Path, PathBuf};
+
+const LAUNCH_AGENT_LABEL: &str = "com.smolkapps.clipboard-manager";
+
+/// Result of checking the per-user LaunchAgent plist against the running binary
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchAgentStatus {
+    Ok,
+    Missing,
+    StaleExePath { plist_path: PathBuf, stored_path: PathBuf },
+    UnreadablePlist,
+}
 
 #[link(name = "ServiceManagement", kind = "framework")]
 extern "C" {
@@ -97,3 +109,152 @@ pub fn sync(enabled: bool) -> Result<(), String> {
         disable()
     }
 }
+
+fn launch_agent_plist_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCH_AGENT_LABEL))
+    })
+}
+
+/// Check whether the LaunchAgent plist still points at the running executable.
+/// A plist left behind after the app bundle moves would silently fail at login.
+pub fn verify() -> LaunchAgentStatus {
+    let Some(plist_path) = launch_agent_plist_path() else {
+        return LaunchAgentStatus::Missing;
+    };
+    let Ok(current_exe) = std::env::current_exe() else {
+        return LaunchAgentStatus::UnreadablePlist;
+    };
+    verify_plist(&plist_path, &current_exe)
+}
+
+/// Whether launch at login is actually in effect: the LaunchAgent plist exists
+/// and starts the running executable. Settings can change behind our back
+/// (e.g. from System Settings), so prefer this over `AppConfig::launch_at_login`.
+pub fn is_enabled() -> bool {
+    let Some(plist_path) = launch_agent_plist_path() else {
+        return false;
+    };
+    let Ok(current_exe) = std::env::current_exe() else {
+        return false;
+    };
+    plist_enabled(&plist_path, &current_exe)
+}
+
+fn plist_enabled(plist_path: &Path, current_exe: &Path) -> bool {
+    verify_plist(plist_path, current_exe) == LaunchAgentStatus::Ok
+}
+
+/// Remove a stale LaunchAgent plist and re-register with the current executable
+pub fn repair(status: &LaunchAgentStatus) -> Result<(), String> {
+    if let LaunchAgentStatus::StaleExePath { plist_path, .. } = status {
+        std::fs::remove_file(plist_path)
+            .map_err(|e| format!("Failed to remove stale plist {:?}: {}", plist_path, e))?;
+        enable()?;
+        log::info!("LaunchAgent plist updated to new executable path");
+    }
+    Ok(())
+}
+
+fn verify_plist(plist_path: &Path, current_exe: &Path) -> LaunchAgentStatus {
+    let contents = match std::fs::read_to_string(plist_path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return LaunchAgentStatus::Missing,
+        Err(_) => return LaunchAgentStatus::UnreadablePlist,
+    };
+
+    let Some(stored) = stored_program_path(&contents) else {
+        return LaunchAgentStatus::UnreadablePlist;
+    };
+    let stored_path = PathBuf::from(stored);
+
+    if stored_path == current_exe {
+        LaunchAgentStatus::Ok
+    } else {
+        LaunchAgentStatus::StaleExePath {
+            plist_path: plist_path.to_path_buf(),
+            stored_path,
+        }
+    }
+}
+
+/// The `Program` string, or the first `ProgramArguments` entry, from an XML plist
+fn stored_program_path(plist: &str) -> Option<String> {
+    let after_key = |key: &str| -> Option<String> {
+        let rest = &plist[plist.find(key)? + key.len()..];
+        let start = rest.find("<string>")? + "<string>".len();
+        let end = rest[start..].find("</string>")? + start;
+        Some(rest[start..end].trim().to_string())
+    };
+    after_key("<key>Program</key>").or_else(|| after_key("<key>ProgramArguments</key>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_plist(dir: &TempDir, exe: &str) -> PathBuf {
+        let path = dir.path().join("agent.plist");
+        std::fs::write(&path, format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\">\n<dict>\n\
+             \t<key>Label</key>\n\t<string>{}</string>\n\
+             \t<key>ProgramArguments</key>\n\t<array>\n\t\t<string>{}</string>\n\t</array>\n\
+             </dict>\n</plist>\n",
+            LAUNCH_AGENT_LABEL, exe
+        )).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_verify_detects_stale_path() {
+        let dir = TempDir::new().unwrap();
+        let plist = write_plist(&dir, "/Users/me/Downloads/ClipVault.app/Contents/MacOS/clipboard-manager");
+        let current = Path::new("/Applications/ClipVault.app/Contents/MacOS/clipboard-manager");
+
+        assert_eq!(
+            verify_plist(&plist, current),
+            LaunchAgentStatus::StaleExePath {
+                plist_path: plist.clone(),
+                stored_path: PathBuf::from("/Users/me/Downloads/ClipVault.app/Contents/MacOS/clipboard-manager"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_matching_path() {
+        let dir = TempDir::new().unwrap();
+        let exe = "/Applications/ClipVault.app/Contents/MacOS/clipboard-manager";
+        let plist = write_plist(&dir, exe);
+        assert_eq!(verify_plist(&plist, Path::new(exe)), LaunchAgentStatus::Ok);
+    }
+
+    #[test]
+    fn test_is_enabled_requires_matching_plist() {
+        let dir = TempDir::new().unwrap();
+        let exe = Path::new("/Applications/ClipVault.app/Contents/MacOS/clipboard-manager");
+        let valid = write_plist(&dir, exe.to_str().unwrap());
+        assert!(plist_enabled(&valid, exe));
+
+        let other = TempDir::new().unwrap();
+        let stale = write_plist(&other, "/Volumes/ClipVault/ClipVault.app/Contents/MacOS/clipboard-manager");
+        assert!(!plist_enabled(&stale, exe));
+
+        let invalid = dir.path().join("invalid.plist");
+        std::fs::write(&invalid, "<plist><dict></dict></plist>").unwrap();
+        assert!(!plist_enabled(&invalid, exe));
+        assert!(!plist_enabled(&dir.path().join("missing.plist"), exe));
+    }
+
+    #[test]
+    fn test_verify_missing_and_unreadable() {
+        let dir = TempDir::new().unwrap();
+        let exe = Path::new("/Applications/ClipVault.app/Contents/MacOS/clipboard-manager");
+        assert_eq!(verify_plist(&dir.path().join("none.plist"), exe), LaunchAgentStatus::Missing);
+
+        let garbage = dir.path().join("garbage.plist");
+        std::fs::write(&garbage, "not a plist").unwrap();
+        assert_eq!(verify_plist(&garbage, exe), LaunchAgentStatus::UnreadablePlist);
+    }
+}
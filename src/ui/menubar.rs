@@ -1,9 +1,11 @@
 // Menu bar application using Cacao
 use cacao::appkit::AppDelegate;
-use std::sync::{Arc, Mutex};
-use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::cell::RefCell;
 use std::path::PathBuf;
+use std::time::Duration;
+use clipboard_manager::LockExt;
 use crate::storage::{AppConfig, Database, Encryptor};
 use crate::ui::popup::PopupWindow;
 use crate::ui::statusbar::StatusBarController;
@@ -16,26 +18,45 @@ pub struct MenuBarApp {
     popup: Arc<Mutex<PopupWindow>>,
     data_dir: PathBuf,
     pro_flag: Arc<AtomicBool>,
+    monitoring_paused: Arc<AtomicBool>,
+    poll_interval: Arc<AtomicU64>,
+    shutdown_requested: Arc<AtomicBool>,
+    in_flight: Arc<(Mutex<bool>, Condvar)>,
     status_bar: RefCell<Option<StatusBarController>>,
     hotkey: RefCell<Option<HotkeyManager>>,
 }
 
+/// How long quitting waits for the background thread to store its current item
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
 impl MenuBarApp {
-    pub fn new(db: Database, encryptor: Encryptor, data_dir: PathBuf, pro_flag: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        db: Database,
+        encryptor: Arc<Mutex<Encryptor>>,
+        data_dir: PathBuf,
+        pro_flag: Arc<AtomicBool>,
+        monitoring_paused: Arc<AtomicBool>,
+        poll_interval: Arc<AtomicU64>,
+        shutdown_requested: Arc<AtomicBool>,
+        in_flight: Arc<(Mutex<bool>, Condvar)>,
+    ) -> Self {
         log::info!("Creating menu bar app...");
         let db_arc = Arc::new(Mutex::new(db));
-        let enc_arc = Arc::new(Mutex::new(encryptor));
         let popup = Arc::new(Mutex::new(PopupWindow::new(
             Arc::clone(&db_arc),
-            Arc::clone(&enc_arc)
+            Arc::clone(&encryptor)
         )));
 
         MenuBarApp {
             db: db_arc,
-            encryptor: enc_arc,
+            encryptor,
             popup,
             data_dir,
             pro_flag,
+            monitoring_paused,
+            poll_interval,
+            shutdown_requested,
+            in_flight,
             status_bar: RefCell::new(None),
             hotkey: RefCell::new(None),
         }
@@ -46,6 +67,14 @@ impl MenuBarApp {
         Arc::clone(&self.popup)
     }
 
+    /// Reopen (Finder double-click, `activate` from AppleScript, Option-click):
+    /// show the popup, or hide it if it's already up
+    fn handle_reopen(popup: &Arc<Mutex<PopupWindow>>) {
+        if let Some(mut popup) = popup.lock_or_log() {
+            popup.toggle();
+        }
+    }
+
     /// Run first-launch setup: ask user about launch-at-login preference.
     /// Deferred via dispatch to run after the app's run loop is active.
     fn first_run_setup(&self) {
@@ -122,28 +151,56 @@ impl AppDelegate for MenuBarApp {
             if let Err(e) = launch_at_login::sync(config.launch_at_login) {
                 log::error!("Failed to sync launch at login: {}", e);
             }
+
+            // Re-register if the app was moved since the LaunchAgent was written
+            if config.launch_at_login {
+                match launch_at_login::verify() {
+                    status @ launch_at_login::LaunchAgentStatus::StaleExePath { .. } => {
+                        if let Err(e) = launch_at_login::repair(&status) {
+                            log::error!("Failed to update stale LaunchAgent: {}", e);
+                        }
+                    }
+                    launch_at_login::LaunchAgentStatus::UnreadablePlist => {
+                        log::warn!("LaunchAgent plist exists but could not be read");
+                    }
+                    _ => {}
+                }
+            }
         }
 
-        // Create status bar icon (pass popup, encryptor, data_dir, and pro flag so menu items work)
+        if let Some(popup) = self.popup.lock_or_log() {
+            popup.set_auto_paste_after_select(config.auto_paste_after_select);
+            popup.set_placement(config.popup_placement);
+            popup.set_auto_resize(config.auto_resize_popup);
+            popup.set_saved_frame(config.popup_frame, self.data_dir.clone());
+        }
+
+        // Create status bar icon (pass popup, encryptor, data_dir and the shared monitor settings so menu items work)
         *self.status_bar.borrow_mut() = Some(StatusBarController::new(
             Arc::clone(&self.db),
             Arc::clone(&self.popup),
             Arc::clone(&self.encryptor),
             self.data_dir.clone(),
             Arc::clone(&self.pro_flag),
+            Arc::clone(&self.monitoring_paused),
+            Arc::clone(&self.poll_interval),
         ));
 
         // Register global hotkey (events polled in main.rs)
-        match HotkeyManager::new() {
+        match HotkeyManager::new(Arc::clone(&self.popup)) {
             Ok(hotkey_mgr) => {
+                if hotkey_mgr.is_registered() {
+                    log::info!("Global hotkey registered: Cmd+Shift+C");
+                } else {
+                    log::warn!("Global hotkey not held after registering; health check will retry");
+                }
                 *self.hotkey.borrow_mut() = Some(hotkey_mgr);
-                log::info!("Global hotkey registered: Cmd+Shift+C");
             }
             Err(e) => log::error!("Failed to register hotkey: {}", e),
         }
 
         // Get clipboard history stats
-        if let Ok(db) = self.db.lock() {
+        if let Some(db) = self.db.lock_or_log() {
             match db.count_items() {
                 Ok(count) => log::info!("  {} items in clipboard history", count),
                 Err(e) => log::error!("  Failed to count items: {}", e),
@@ -153,7 +210,74 @@ impl AppDelegate for MenuBarApp {
         log::info!("Menu bar app running! Press Cmd+Shift+C to show clipboard history");
     }
 
+    fn will_terminate(&self) {
+        log::info!("Quitting, flushing in-flight clipboard items...");
+        if let Some(hotkey) = self.hotkey.borrow().as_ref() {
+            let failures = hotkey.registration_failure_count();
+            if failures > 0 {
+                log::warn!("Global hotkey was lost {} time(s) this session", failures);
+            }
+        }
+        self.shutdown_requested.store(true, Ordering::Relaxed);
+
+        // Wait for the background thread to finish storing the current item
+        let (busy, idle) = &*self.in_flight;
+        if let Some(guard) = busy.lock_or_log() {
+            // A panicked storer poisons the condvar's mutex too; the flag is still valid
+            let (_guard, wait) = idle
+                .wait_timeout_while(guard, SHUTDOWN_FLUSH_TIMEOUT, |busy| *busy)
+                .unwrap_or_else(|e| e.into_inner());
+            if wait.timed_out() {
+                log::warn!("Timed out waiting for in-flight clipboard item");
+            }
+        }
+
+        // Fold the WAL back into the main database file
+        if let Some(db) = self.db.lock_or_log() {
+            if let Err(e) = db.checkpoint_wal() {
+                log::error!("Failed to checkpoint WAL: {}", e);
+            }
+        }
+
+        log::info!("Shutdown complete");
+    }
+
     fn should_terminate_after_last_window_closed(&self) -> bool {
         false // Keep running as menu bar app
     }
+
+    fn should_handle_reopen(&self, _has_visible_windows: bool) -> bool {
+        log::info!("Reopen requested");
+        let popup = Arc::clone(&self.popup);
+        dispatch::Queue::main().exec_async(move || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                Self::handle_reopen(&popup);
+            }));
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reopen_toggles_popup() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let popup = Arc::new(Mutex::new(PopupWindow::new(
+            Arc::new(Mutex::new(db)),
+            Arc::new(Mutex::new(enc)),
+        )));
+
+        MenuBarApp::handle_reopen(&popup);
+        assert!(popup.lock().unwrap().is_visible());
+
+        // Already visible: reopen hides it
+        MenuBarApp::handle_reopen(&popup);
+        assert!(!popup.lock().unwrap().is_visible());
+    }
 }
@@ -2,16 +2,22 @@
 use std::sync::{Arc, Mutex, OnceLock};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use objc2::rc::Retained;
-use objc2::{declare_class, msg_send_id};
+use objc2::{declare_class, msg_send_id, sel};
 use objc2::ClassType;
 use objc2::DeclaredClass;
-use objc2_app_kit::{NSWindow, NSWindowStyleMask, NSBackingStoreType, NSTextView, NSScrollView, NSApplication, NSApplicationActivationPolicy, NSEvent, NSScreen, NSFont, NSColor};
-use objc2_foundation::{NSString, NSRect, NSPoint, NSSize, MainThreadMarker, NSMutableAttributedString, NSRange, NSData, NSObject};
+use objc2_app_kit::{NSWindow, NSWindowDelegate, NSWindowStyleMask, NSBackingStoreType, NSTextView, NSScrollView, NSApplication, NSApplicationActivationPolicy, NSEvent, NSScreen, NSFont, NSColor, NSSavePanel, NSAlert, NSAlertStyle, NSAlertFirstButtonReturn, NSImage, NSTextAttachment, NSMenu, NSMenuItem, NSTextField, NSComboBox, NSButton, NSButtonType, NSView, NSStackView, NSLayoutAttribute, NSUserInterfaceLayoutOrientation, NSTextAlignment, NSLineBreakMode, NSControlStateValueOn, NSControlStateValueOff};
+use objc2_foundation::{NSString, NSRect, NSPoint, NSSize, MainThreadMarker, NSAttributedString, NSMutableAttributedString, NSRange, NSData, NSNotification, NSObject, NSObjectProtocol, NSArray};
 use objc2::msg_send;
-use objc2::runtime::AnyObject;
-use crate::storage::{Database, Encryptor, ClipboardItem};
+use objc2::runtime::{AnyClass, AnyObject, ProtocolObject, Sel};
+use clipboard_manager::LockExt;
+use crate::storage::{AppConfig, Database, Encryptor, BlobId, ClipboardItem, ItemId, ItemMetadata, PopupPlacement};
+use crate::storage::database::normalize_tag;
 use crate::storage::search::SearchEngine;
+use crate::storage::SensitivityAnalyzer;
 use objc2_app_kit::NSPasteboard;
 use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation};
 use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
@@ -41,44 +47,52 @@ declare_class!(
                 match key_code {
                     125 => {
                         // Down arrow
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             popup.move_selection_down();
                         }
                     }
                     126 => {
                         // Up arrow
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             popup.move_selection_up();
                         }
                     }
+                    121 => {
+                        // Page Down - load and jump to the next page
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                            popup.next_page();
+                        }
+                    }
+                    116 => {
+                        // Page Up - back one page
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                            popup.previous_page();
+                        }
+                    }
                     123 => {
                         // Left arrow - move search cursor left
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             popup.move_search_cursor_left();
                         }
                     }
                     124 => {
                         // Right arrow - move search cursor right
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             popup.move_search_cursor_right();
                         }
                     }
                     36 => {
                         // Return/Enter - paste and close
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let mut popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(mut popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             popup.paste_and_close();
                         }
                     }
                     53 => {
                         // Escape - clear search if active, otherwise hide
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let mut popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                            if popup.has_active_search() {
+                        if let Some(mut popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                            if popup.is_showing_help() {
+                                popup.hide_help();
+                            } else if popup.has_active_search() {
                                 popup.clear_search();
                             } else {
                                 popup.hide();
@@ -86,16 +100,30 @@ declare_class!(
                         }
                     }
                     51 => {
-                        // Backspace - delete search character at cursor
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                            popup.delete_search_char();
+                        // Delete - Cmd+Delete pins the selected item; otherwise delete the
+                        // search character at cursor, or ask to trash the item when not searching.
+                        // Key repeat only deletes characters, so holding Delete to clear a
+                        // search can't go on to trash items.
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                            let has_cmd = unsafe {
+                                event.modifierFlags().contains(
+                                    objc2_app_kit::NSEventModifierFlags::NSEventModifierFlagCommand
+                                )
+                            };
+                            if has_cmd {
+                                popup.pin_selected();
+                            } else if popup.search_query.borrow().is_empty() {
+                                if !unsafe { event.isARepeat() } {
+                                    popup.prompt_delete_selected();
+                                }
+                            } else {
+                                popup.delete_search_char();
+                            }
                         }
                     }
                     48 => {
                         // Tab - cycle type filter, Shift+Tab - cycle time filter
-                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                             let has_shift = unsafe {
                                 event.modifierFlags().contains(
                                     objc2_app_kit::NSEventModifierFlags::NSEventModifierFlagShift
@@ -104,7 +132,7 @@ declare_class!(
                             if has_shift {
                                 popup.cycle_time_filter();
                             } else {
-                                popup.cycle_type_filter();
+                                popup.cycle_item_filter();
                             }
                         }
                     }
@@ -115,27 +143,53 @@ declare_class!(
                             )
                         };
 
+                        let has_ctrl = unsafe {
+                            event.modifierFlags().contains(
+                                objc2_app_kit::NSEventModifierFlags::NSEventModifierFlagControl
+                            )
+                        };
+
+                        if has_ctrl && key_code == 1 {
+                            // Ctrl+S - move selected item to a named slot
+                            if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                                popup.prompt_named_slot();
+                            }
+                            return;
+                        }
+
                         if has_cmd {
-                            // Handle Cmd+P (pin) and Cmd+D (delete) ourselves
-                            // P = keyCode 35, D = keyCode 2
+                            // Handle Cmd+P (pin), Cmd+D (delete) and Cmd+S (save) ourselves
+                            // P = keyCode 35, D = keyCode 2, S = keyCode 1
                             match key_code {
                                 35 => {
                                     // Cmd+P - toggle pin
-                                    if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                                        let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                                    if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                                         popup.toggle_pin_selected();
                                     }
                                     return;
                                 }
                                 2 => {
                                     // Cmd+D - delete selected item
-                                    if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                                        let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
+                                    if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
                                         popup.delete_selected();
                                     }
                                     return;
                                 }
+                                1 => {
+                                    // Cmd+S - save selected item to a file
+                                    if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                                        popup.prompt_export_selected();
+                                    }
+                                    return;
+                                }
                                 _ => {
+                                    // Cmd+1 … Cmd+5 - switch category tab
+                                    if let Some(filter) = ItemFilter::tab_for_key_code(key_code) {
+                                        if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                                            popup.set_filter(filter);
+                                        }
+                                        return;
+                                    }
                                     // Forward other Cmd+key combos to NSTextView
                                     unsafe {
                                         let _: () = objc2::msg_send![super(self), keyDown: event];
@@ -145,15 +199,30 @@ declare_class!(
                             }
                         }
 
+                        let has_alt = unsafe {
+                            event.modifierFlags().contains(
+                                objc2_app_kit::NSEventModifierFlags::NSEventModifierFlagOption
+                            )
+                        };
+
                         // All printable characters go to search
                         unsafe {
                             if let Some(chars) = event.charactersIgnoringModifiers() {
                                 let s = chars.to_string();
                                 for c in s.chars() {
                                     if !c.is_control() {
-                                        if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                                            let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                                            popup.append_search_char(c);
+                                        if let Some(mut popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                                            let typing = !popup.search_query.borrow().is_empty();
+                                            // `?` opens help unless the user is already typing a query
+                                            if c == '?' && (popup.is_showing_help() || !typing) {
+                                                popup.toggle_help();
+                                            } else if c.is_ascii_digit() && !typing && !has_ctrl && !has_alt {
+                                                // 1–9 paste that row; other digits are ignored
+                                                popup.quick_paste(c);
+                                                return;
+                                            } else {
+                                                popup.append_search_char(c);
+                                            }
                                         }
                                     }
                                 }
@@ -169,52 +238,143 @@ declare_class!(
             true
         }
 
-        #[method(mouseDown:)]
-        fn mouse_down(&self, event: &NSEvent) {
+        // Click on an item row: select it and paste it
+        #[method(pasteRow:)]
+        fn paste_row(&self, sender: &NSButton) {
             let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                unsafe {
-                    let window_point = event.locationInWindow();
-                    let null_view: *const AnyObject = std::ptr::null();
-                    let local_point: NSPoint = msg_send![self, convertPoint: window_point, fromView: null_view];
-
-                    let layout_mgr: *const AnyObject = msg_send![self, layoutManager];
-                    let text_container: *const AnyObject = msg_send![self, textContainer];
+                let slot = unsafe { sender.tag() };
+                if let (Ok(slot), Some(mut popup)) = (usize::try_from(slot), POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log())) {
+                    popup.click_row(slot);
+                }
+            }));
+        }
 
-                    if layout_mgr.is_null() || text_container.is_null() {
-                        let _: () = objc2::msg_send![super(self), mouseDown: event];
-                        return;
-                    }
+        #[method(setSensitivityOverride:)]
+        fn set_sensitivity_override(&self, sender: &NSMenuItem) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let sensitive = sensitivity_for_tag(unsafe { sender.tag() });
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    popup.set_selected_sensitivity(sensitive);
+                }
+            }));
+        }
 
-                    let mut fraction: f64 = 0.0;
-                    let glyph_idx: usize = msg_send![layout_mgr,
-                        glyphIndexForPoint: local_point
-                        inTextContainer: text_container
-                        fractionOfDistanceThroughGlyph: &mut fraction
-                    ];
-                    let char_idx: usize = msg_send![layout_mgr, characterIndexForGlyphAtIndex: glyph_idx];
-
-                    if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                        let mut popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                        if popup.click_item_at_char(char_idx) {
-                            return;
-                        }
-                    }
+        #[method(selectFilterTab:)]
+        fn select_filter_tab(&self, sender: &NSButton) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let tag = unsafe { sender.tag() };
+                let filter = usize::try_from(tag).ok().and_then(|i| ItemFilter::TABS.get(i).copied());
+                if let (Some(filter), Some(popup)) = (filter, POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log())) {
+                    popup.set_filter(filter);
+                }
+            }));
+        }
 
-                    // Not on an item — default text view behavior
-                    let _: () = objc2::msg_send![super(self), mouseDown: event];
+        #[method(editTags:)]
+        fn edit_tags(&self, _sender: &NSMenuItem) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    popup.prompt_edit_tags();
                 }
             }));
         }
     }
 );
 
+// Borderless button drawing one item row of the list
+declare_class!(
+    struct ItemRowButton;
+
+    unsafe impl ClassType for ItemRowButton {
+        type Super = NSButton;
+        type Mutability = objc2::mutability::MainThreadOnly;
+        const NAME: &'static str = "ClipVaultItemRowButton";
+    }
+
+    impl DeclaredClass for ItemRowButton {
+        type Ivars = ();
+    }
+
+    unsafe impl ItemRowButton {
+        // Right-click on a row: select it and offer the sensitivity override and tag editor
+        #[method_id(menuForEvent:)]
+        fn menu_for_event(&self, _event: &NSEvent) -> Option<Retained<NSMenu>> {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let slot = usize::try_from(unsafe { self.tag() }).ok()?;
+                let target = unsafe { self.target() }?;
+                let item = POPUP_FOR_KEYS.get()?.lock_or_log()?.select_row(slot)?;
+                Some(PopupWindow::item_menu(&item, &target, MainThreadMarker::from(self)))
+            }))
+            .ok()
+            .flatten()
+        }
+    }
+);
+
+impl ItemRowButton {
+    /// Hidden row for stack position `slot`; clicks go to `pasteRow:` on `target`
+    fn new(mtm: MainThreadMarker, slot: usize, target: &AnyObject) -> Retained<Self> {
+        unsafe {
+            let row: Retained<Self> = msg_send_id![mtm.alloc::<Self>(), initWithFrame: NSRect::ZERO];
+            row.setButtonType(NSButtonType::MomentaryChange);
+            row.setBordered(false);
+            row.setAlignment(NSTextAlignment::Left);
+            row.setLineBreakMode(NSLineBreakMode::NSLineBreakByTruncatingTail);
+            row.setTag(slot as isize);
+            row.setTarget(Some(target));
+            row.setAction(Some(sel!(pasteRow:)));
+            row.setRefusesFirstResponder(true);
+            row.setHidden(true);
+            row
+        }
+    }
+}
+
+/// Tags typed into the tag editor: comma-separated, invalid and repeated ones dropped
+fn parse_tag_list(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').filter_map(normalize_tag) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Row index pasted by pressing `digit` in the popup: `1` is the first row,
+/// `9` the ninth. `None` for other characters and rows that aren't listed.
+fn quick_paste_index(digit: char, item_count: usize) -> Option<usize> {
+    let index = digit.to_digit(10)?.checked_sub(1)? as usize;
+    (index < item_count).then_some(index)
+}
+
+/// Digit shown in front of row `index`. Only the first nine rows get one,
+/// and none do while a search query is typed, since digits go to the query.
+fn quick_paste_digit(index: usize, typing: bool) -> Option<usize> {
+    (index < 9 && !typing).then_some(index + 1)
+}
+
+/// Menu item tags for the sensitivity override: marked sensitive, marked not
+/// sensitive, back to automatic detection
+const SENSITIVITY_TAG_SENSITIVE: isize = 1;
+const SENSITIVITY_TAG_NOT_SENSITIVE: isize = 0;
+const SENSITIVITY_TAG_AUTOMATIC: isize = -1;
+
+fn sensitivity_for_tag(tag: isize) -> Option<bool> {
+    match tag {
+        SENSITIVITY_TAG_SENSITIVE => Some(true),
+        SENSITIVITY_TAG_NOT_SENSITIVE => Some(false),
+        _ => None,
+    }
+}
+
 // Window delegate to handle red X close button
 declare_class!(
     struct WindowDelegate;
 
     unsafe impl ClassType for WindowDelegate {
         type Super = NSObject;
-        type Mutability = objc2::mutability::InteriorMutable;
+        type Mutability = objc2::mutability::MainThreadOnly;
         const NAME: &'static str = "ClipVaultWindowDelegate";
     }
 
@@ -222,14 +382,15 @@ declare_class!(
         type Ivars = ();
     }
 
-    unsafe impl WindowDelegate {
+    unsafe impl NSObjectProtocol for WindowDelegate {}
+
+    unsafe impl NSWindowDelegate for WindowDelegate {
         #[method(windowWillClose:)]
-        fn window_will_close(&self, _notification: &AnyObject) {
+        fn window_will_close(&self, _notification: &NSNotification) {
             let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                log::info!("Window delegate: red X button clicked, calling hide()");
-                if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                    let mut popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                    popup.hide();
+                log::info!("Window delegate: red X button clicked");
+                if let Some(mut popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    popup.on_window_close();
                 }
             }));
         }
@@ -270,46 +431,87 @@ impl KeyHandlingTextView {
 }
 
 impl WindowDelegate {
-    fn new(_mtm: MainThreadMarker) -> Retained<Self> {
-        unsafe { msg_send_id![Self::alloc(), init] }
+    fn new(mtm: MainThreadMarker) -> Retained<Self> {
+        unsafe { msg_send_id![mtm.alloc::<Self>(), init] }
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum TypeFilter {
+/// Which items the popup lists: a category tab, or (via Tab) emails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemFilter {
     All,
     Text,
     Image,
     Url,
+    Email,
+    Sensitive,
 }
 
-impl TypeFilter {
+impl ItemFilter {
+    /// Filters shown as tabs above the list, in order (Cmd+1 … Cmd+5)
+    pub const TABS: [ItemFilter; 5] = [
+        ItemFilter::All,
+        ItemFilter::Text,
+        ItemFilter::Image,
+        ItemFilter::Url,
+        ItemFilter::Sensitive,
+    ];
+
     fn next(self) -> Self {
         match self {
-            TypeFilter::All => TypeFilter::Text,
-            TypeFilter::Text => TypeFilter::Image,
-            TypeFilter::Image => TypeFilter::Url,
-            TypeFilter::Url => TypeFilter::All,
+            ItemFilter::All => ItemFilter::Text,
+            ItemFilter::Text => ItemFilter::Image,
+            ItemFilter::Image => ItemFilter::Url,
+            ItemFilter::Url => ItemFilter::Email,
+            ItemFilter::Email => ItemFilter::Sensitive,
+            ItemFilter::Sensitive => ItemFilter::All,
         }
     }
 
     fn label(self) -> &'static str {
         match self {
-            TypeFilter::All => "All types",
-            TypeFilter::Text => "Text",
-            TypeFilter::Image => "Images",
-            TypeFilter::Url => "URLs",
+            ItemFilter::All => "All types",
+            ItemFilter::Text => "Text",
+            ItemFilter::Image => "Images",
+            ItemFilter::Url => "URLs",
+            ItemFilter::Email => "Emails",
+            ItemFilter::Sensitive => "Sensitive",
+        }
+    }
+
+    fn tab_label(self) -> &'static str {
+        match self {
+            ItemFilter::All => "All",
+            other => other.label(),
         }
     }
 
     fn db_value(self) -> Option<&'static str> {
         match self {
-            TypeFilter::All => None,
-            TypeFilter::Text => Some("text"),
-            TypeFilter::Image => Some("image"),
-            TypeFilter::Url => Some("url"),
+            ItemFilter::All | ItemFilter::Sensitive => None,
+            ItemFilter::Text => Some("text"),
+            ItemFilter::Image => Some("image"),
+            ItemFilter::Url => Some("url"),
+            ItemFilter::Email => Some("email"),
         }
     }
+
+    fn sensitive_only(self) -> bool {
+        self == ItemFilter::Sensitive
+    }
+
+    /// Tab selected by Cmd+`key_code` (the 1–5 keys)
+    fn tab_for_key_code(key_code: u16) -> Option<ItemFilter> {
+        let index = match key_code {
+            18 => 0,
+            19 => 1,
+            20 => 2,
+            21 => 3,
+            23 => 4,
+            _ => return None,
+        };
+        Some(Self::TABS[index])
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -355,17 +557,179 @@ pub struct PopupWindow {
     encryptor: Arc<Mutex<Encryptor>>,
     window: RefCell<Option<Retained<NSWindow>>>,
     text_view: RefCell<Option<Retained<NSTextView>>>,
+    tab_buttons: RefCell<Vec<Retained<NSButton>>>,
     window_delegate: RefCell<Option<Retained<WindowDelegate>>>,
     items: RefCell<Vec<ClipboardItem>>,
     selected_index: RefCell<usize>,
-    visible: bool,
+    visible: RefCell<bool>,
     auto_refresh_active: Arc<AtomicBool>,
     search_query: RefCell<String>,
     search_cursor: RefCell<usize>,
-    type_filter: RefCell<TypeFilter>,
+    /// Active category tab (or the Emails filter)
+    item_filter: RefCell<ItemFilter>,
     time_filter: RefCell<TimeFilter>,
     search_engine: SearchEngine,
-    item_char_starts: RefCell<Vec<usize>>,
+    /// Title, search line and status message above the rows
+    header_label: RefCell<Option<Retained<NSTextField>>>,
+    header_hash: RefCell<u64>,
+    /// `PAGE_SIZE` item rows between the header and the preview pane
+    row_stack: RefCell<Option<Retained<NSStackView>>>,
+    row_buttons: RefCell<Vec<Retained<NSButton>>>,
+    /// Item and `row_hash` each row button last drew (`None` = hidden), so unchanged rows are left alone
+    row_hashes: RefCell<Vec<Option<(ItemId, u64)>>>,
+    /// Item shown in the first row; the rows follow the selection through longer lists
+    first_visible_row: RefCell<usize>,
+    /// Row followed by the gap that closes the pinned section
+    pinned_gap_row: RefCell<Option<usize>>,
+    /// Decoded list thumbnails by blob ID (`None` if the thumbnail couldn't be loaded)
+    thumbnails: RefCell<HashMap<BlobId, Option<Retained<NSImage>>>>,
+    /// Decoded source app icons by bundle ID (`None` if the icon couldn't be loaded)
+    app_icons: RefCell<HashMap<String, Option<Retained<NSImage>>>>,
+    content_hash: RefCell<u64>,
+    /// Transient header line and when it stops showing
+    status_message: RefCell<Option<(String, Instant)>>,
+    auto_paste_after_select: RefCell<bool>,
+    placement: RefCell<PopupPlacement>,
+    /// Screen frame of the status bar icon, kept current by StatusBarController
+    icon_frame: RefCell<Option<Arc<Mutex<Option<NSRect>>>>>,
+    show_help: RefCell<bool>,
+    auto_resize: RefCell<bool>,
+    /// Item count the window was last sized for, so selection moves don't resize it
+    last_item_count: RefCell<usize>,
+    /// Offset of the last history page appended to `items`
+    page_offset: RefCell<usize>,
+    /// Items in the whole history, for the "Showing 1–40 of 312" footer
+    total_items: RefCell<usize>,
+    /// Frame the user last moved or resized the window to (`AppConfig::popup_frame`)
+    saved_frame: RefCell<Option<[f64; 4]>>,
+    /// Where the saved frame is written back to
+    config_dir: RefCell<Option<PathBuf>>,
+    /// Frame we last put the window at, to tell user moves from our own
+    placed_frame: RefCell<Option<NSRect>>,
+}
+
+/// Shortcut cheat-sheet shown by pressing `?`
+const KEYBOARD_SHORTCUTS: &[(&str, &str)] = &[
+    ("↑ / ↓", "Move selection"),
+    ("PgUp / PgDn", "Previous / next page"),
+    ("Return", "Paste selected item"),
+    ("1 – 9", "Paste that item"),
+    ("Type", "Search history"),
+    ("← / →", "Move search cursor"),
+    ("Delete", "Delete search character, or move item to the trash (asks first)"),
+    ("\u{2318}Delete", "Pin item"),
+    ("\u{2318}1 – \u{2318}5", "Switch category tab"),
+    ("Tab", "Cycle type filter"),
+    ("Shift+Tab", "Cycle time filter"),
+    ("\u{2318}P", "Pin / unpin item"),
+    ("\u{2318}D", "Delete item"),
+    ("\u{2318}S", "Save item to a file"),
+    ("Ctrl+S", "Move item to a named slot"),
+    ("Right-click", "Mark item sensitive / not sensitive, edit tags"),
+    ("?", "Show / hide this help"),
+    ("Esc", "Close help, clear search, or close window"),
+];
+
+/// What the popup's top-left corner is positioned against
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlacementAnchor {
+    Cursor,
+    StatusBarIcon(NSRect),
+}
+
+/// Fall back to the cursor when anchoring to the icon but its frame isn't known yet
+fn placement_anchor(placement: PopupPlacement, icon_frame: Option<NSRect>) -> PlacementAnchor {
+    match (placement, icon_frame) {
+        (PopupPlacement::BelowStatusBarIcon, Some(frame)) => PlacementAnchor::StatusBarIcon(frame),
+        _ => PlacementAnchor::Cursor,
+    }
+}
+
+/// Items loaded per page of history; more are appended when scrolling past the last
+const PAGE_SIZE: usize = 20;
+
+/// `AppConfig::popup_frame` value for a window frame
+fn frame_to_array(frame: NSRect) -> [f64; 4] {
+    [frame.origin.x, frame.origin.y, frame.size.width, frame.size.height]
+}
+
+/// A saved frame moved (and shrunk if needed) to lie within `visible`, the
+/// screen's visible frame. `None` if it is entirely off that screen, e.g. it
+/// was saved on a display that is no longer connected.
+fn clamp_frame_to_screen(frame: [f64; 4], visible: NSRect) -> Option<NSRect> {
+    let [x, y, width, height] = frame;
+    let (min_x, min_y) = (visible.origin.x, visible.origin.y);
+    let (max_x, max_y) = (min_x + visible.size.width, min_y + visible.size.height);
+    if x >= max_x || y >= max_y || x + width <= min_x || y + height <= min_y {
+        return None;
+    }
+
+    let width = width.min(visible.size.width).max(POPUP_WIDTH.min(visible.size.width));
+    let height = height.min(visible.size.height).max(MIN_POPUP_HEIGHT.min(visible.size.height));
+    let x = x.min(max_x - width).max(min_x);
+    let y = y.min(max_y - height).max(min_y);
+    Some(NSRect::new(NSPoint::new(x, y), NSSize::new(width, height)))
+}
+
+/// How long a transient status line stays in the popup header
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(2);
+/// How long "Deleted" shows after the Delete key removes an item
+const DELETED_MESSAGE_DURATION: Duration = Duration::from_secs(1);
+
+/// Popup width, kept fixed while the height follows the item count
+const POPUP_WIDTH: f64 = 600.0;
+const MIN_POPUP_HEIGHT: f64 = 200.0;
+/// Height of one item row in the 13pt monospaced font
+const ROW_HEIGHT: f64 = 22.0;
+/// Height image thumbnails are drawn at in the list (the 48px PNG at 2x)
+const THUMBNAIL_DISPLAY_HEIGHT: f64 = 24.0;
+/// Size source app icons are drawn at in the list (the 32px PNG at 2x)
+const APP_ICON_DISPLAY_SIZE: f64 = 16.0;
+/// Header, search line and preview pane around the item rows
+const POPUP_CHROME_HEIGHT: f64 = 120.0;
+/// Row of category tabs above the list
+const TAB_BAR_HEIGHT: f64 = 28.0;
+/// Space left free on the usable screen height
+const SCREEN_MARGIN: f64 = 100.0;
+/// Space between the pinned rows and the rest of the list
+const PINNED_GAP: f64 = 8.0;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// One FNV-1a step, for the popup's cheap "did this change" hashes
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Hash of everything the row for `item`, listed at `index`, draws
+fn row_hash(item: &ClipboardItem, index: usize, is_selected: bool, shortcut: Option<usize>) -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, &index.to_le_bytes());
+    hash = fnv1a(hash, &shortcut.unwrap_or(0).to_le_bytes());
+    hash = fnv1a(hash, &[is_selected as u8, item.is_pinned as u8, item.is_encrypted as u8]);
+    hash = fnv1a(hash, &item.copy_count.to_le_bytes());
+    hash = fnv1a(hash, item.data_type.as_bytes());
+    hash = fnv1a(hash, item.preview_text.as_deref().unwrap_or("").as_bytes());
+    hash = fnv1a(hash, format_relative(item.timestamp).as_bytes());
+    hash = fnv1a(hash, &item.thumbnail_blob_id.map_or(0, |blob_id| blob_id.0).to_le_bytes());
+    hash = fnv1a(hash, item.source_bundle_id.as_deref().unwrap_or("").as_bytes());
+    hash
+}
+
+/// Index of the item in the first of the `PAGE_SIZE` rows: `start` while the
+/// selection stays within those rows, otherwise just far enough to show it
+fn visible_row_start(start: usize, selected: usize, item_count: usize) -> usize {
+    let start = start.min(item_count.saturating_sub(PAGE_SIZE));
+    if selected < start {
+        selected
+    } else if selected >= start + PAGE_SIZE {
+        selected + 1 - PAGE_SIZE
+    } else {
+        start
+    }
 }
 
 // SAFETY: PopupWindow contains NSWindow which is !Send, but we only access it
@@ -382,24 +746,113 @@ impl PopupWindow {
             encryptor,
             window: RefCell::new(None),
             text_view: RefCell::new(None),
+            tab_buttons: RefCell::new(Vec::new()),
             window_delegate: RefCell::new(None),
             items: RefCell::new(Vec::new()),
             selected_index: RefCell::new(0),
-            visible: false,
+            visible: RefCell::new(false),
             auto_refresh_active: Arc::new(AtomicBool::new(false)),
             search_query: RefCell::new(String::new()),
             search_cursor: RefCell::new(0),
-            type_filter: RefCell::new(TypeFilter::All),
+            item_filter: RefCell::new(ItemFilter::All),
             time_filter: RefCell::new(TimeFilter::AllTime),
             search_engine: SearchEngine::new(),
-            item_char_starts: RefCell::new(Vec::new()),
+            header_label: RefCell::new(None),
+            header_hash: RefCell::new(0),
+            row_stack: RefCell::new(None),
+            row_buttons: RefCell::new(Vec::new()),
+            row_hashes: RefCell::new(Vec::new()),
+            first_visible_row: RefCell::new(0),
+            pinned_gap_row: RefCell::new(None),
+            thumbnails: RefCell::new(HashMap::new()),
+            app_icons: RefCell::new(HashMap::new()),
+            content_hash: RefCell::new(0),
+            status_message: RefCell::new(None),
+            auto_paste_after_select: RefCell::new(false),
+            placement: RefCell::new(PopupPlacement::NearCursor),
+            icon_frame: RefCell::new(None),
+            show_help: RefCell::new(false),
+            auto_resize: RefCell::new(true),
+            last_item_count: RefCell::new(usize::MAX),
+            page_offset: RefCell::new(0),
+            total_items: RefCell::new(0),
+            saved_frame: RefCell::new(None),
+            config_dir: RefCell::new(None),
+            placed_frame: RefCell::new(None),
+        }
+    }
+
+    pub fn set_placement(&self, placement: PopupPlacement) {
+        *self.placement.borrow_mut() = placement;
+    }
+
+    /// Share the status bar icon frame used by `PopupPlacement::BelowStatusBarIcon`
+    pub fn set_icon_frame_source(&self, icon_frame: Arc<Mutex<Option<NSRect>>>) {
+        *self.icon_frame.borrow_mut() = Some(icon_frame);
+    }
+
+    /// Whether choosing an item also sends Cmd+V to the previously active app
+    pub fn set_auto_paste_after_select(&self, enabled: bool) {
+        *self.auto_paste_after_select.borrow_mut() = enabled;
+    }
+
+    /// Reopen at `frame` (from `AppConfig::popup_frame`) and save later moves to
+    /// the config in `config_dir`
+    pub fn set_saved_frame(&self, frame: Option<[f64; 4]>, config_dir: PathBuf) {
+        *self.saved_frame.borrow_mut() = frame;
+        *self.config_dir.borrow_mut() = Some(config_dir);
+    }
+
+    /// Whether the window height follows the number of items shown
+    pub fn set_auto_resize(&self, enabled: bool) {
+        *self.auto_resize.borrow_mut() = enabled;
+    }
+
+    /// Window height that fits `item_count` rows on the main screen
+    pub fn ideal_height(item_count: usize) -> f64 {
+        let max_height = MainThreadMarker::new()
+            .and_then(|mtm| unsafe { NSScreen::mainScreen(mtm) })
+            .map(|screen| unsafe { screen.visibleFrame() }.size.height - SCREEN_MARGIN)
+            .unwrap_or(f64::INFINITY);
+        Self::ideal_height_within(item_count, max_height)
+    }
+
+    fn ideal_height_within(item_count: usize, max_height: f64) -> f64 {
+        // Longer lists scroll through the `PAGE_SIZE` rows
+        (item_count.min(PAGE_SIZE) as f64 * ROW_HEIGHT + POPUP_CHROME_HEIGHT + TAB_BAR_HEIGHT)
+            .max(MIN_POPUP_HEIGHT)
+            .min(max_height.max(MIN_POPUP_HEIGHT))
+    }
+
+    /// Fit the window height to the item list, keeping its top-left corner in place.
+    /// Only acts when the item count changed since the last resize.
+    fn resize_to_content(&self) {
+        // A size the user chose wins over fitting the content
+        if !*self.auto_resize.borrow() || self.saved_frame.borrow().is_some() {
+            return;
+        }
+        let item_count = self.items.borrow().len();
+        if *self.last_item_count.borrow() == item_count {
+            return;
+        }
+        *self.last_item_count.borrow_mut() = item_count;
+
+        let window = self.window.borrow();
+        let Some(window) = window.as_ref() else { return };
+        unsafe {
+            let frame = window.frame();
+            let top_left = NSPoint::new(frame.origin.x, frame.origin.y + frame.size.height);
+            let width = window.contentRectForFrameRect(frame).size.width;
+            window.setContentSize(NSSize::new(width, Self::ideal_height(item_count)));
+            window.setFrameTopLeftPoint(top_left);
+            *self.placed_frame.borrow_mut() = Some(window.frame());
         }
     }
 
     unsafe fn build_window(&self, mtm: MainThreadMarker) -> Retained<NSWindow> {
         let content_rect = NSRect::new(
             NSPoint::new(0.0, 0.0),
-            NSSize::new(600.0, 400.0),
+            NSSize::new(POPUP_WIDTH, 400.0),
         );
 
         let style_mask = NSWindowStyleMask::Titled
@@ -428,10 +881,16 @@ impl PopupWindow {
         // Allow window to receive keyboard events
         window.setAcceptsMouseMovedEvents(true);
 
-        // Create scroll view sized to the window content area
+        // Content view holds the category tab bar, the header and rows, and the preview pane
+        let content_view = NSView::initWithFrame(mtm.alloc(), content_rect);
+
+        // Scroll view for the preview pane, filling the content area below the tab bar until laid out
         let scroll_view = NSScrollView::new(mtm);
         scroll_view.setHasVerticalScroller(true);
-        scroll_view.setFrame(content_rect);
+        scroll_view.setFrame(NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(content_rect.size.width, content_rect.size.height - TAB_BAR_HEIGHT),
+        ));
         scroll_view.setAutoresizingMask(
             objc2_app_kit::NSAutoresizingMaskOptions::NSViewWidthSizable
             | objc2_app_kit::NSAutoresizingMaskOptions::NSViewHeightSizable,
@@ -461,8 +920,72 @@ impl PopupWindow {
         text_view.setFont(Some(&font));
 
         scroll_view.setDocumentView(Some(&text_view));
+        content_view.addSubview(&scroll_view);
+
+        // Category tabs, pinned to the top edge; clicks go to the text view
+        let mut tab_buttons = Vec::with_capacity(ItemFilter::TABS.len());
+        let mut tab_x = 8.0;
+        for (index, filter) in ItemFilter::TABS.iter().enumerate() {
+            let button = NSButton::radioButtonWithTitle_target_action(
+                &NSString::from_str(filter.tab_label()),
+                Some(&text_view),
+                Some(sel!(selectFilterTab:)),
+                mtm,
+            );
+            button.setTag(index as isize);
+            button.sizeToFit();
+            let size = button.frame().size;
+            button.setFrameOrigin(NSPoint::new(
+                tab_x,
+                content_rect.size.height - TAB_BAR_HEIGHT + (TAB_BAR_HEIGHT - size.height) / 2.0,
+            ));
+            button.setAutoresizingMask(objc2_app_kit::NSAutoresizingMaskOptions::NSViewMinYMargin);
+            button.setRefusesFirstResponder(true);
+            content_view.addSubview(&button);
+            tab_x += size.width + 12.0;
+            tab_buttons.push(button);
+        }
+        *self.tab_buttons.borrow_mut() = tab_buttons;
+        self.update_tab_buttons();
 
-        window.setContentView(Some(&scroll_view));
+        // Header and item rows sit between the tabs and the preview pane;
+        // `layout_list` sizes all three once there is content
+        let header = NSTextField::labelWithAttributedString(&NSAttributedString::new(), mtm);
+        header.setAutoresizingMask(
+            objc2_app_kit::NSAutoresizingMaskOptions::NSViewWidthSizable
+            | objc2_app_kit::NSAutoresizingMaskOptions::NSViewMinYMargin,
+        );
+        content_view.addSubview(&header);
+
+        // A fixed set of row buttons, so moving the selection only redraws the rows it leaves and enters
+        let row_stack = NSStackView::new(mtm);
+        row_stack.setOrientation(NSUserInterfaceLayoutOrientation::Vertical);
+        row_stack.setAlignment(NSLayoutAttribute::Leading);
+        row_stack.setSpacing(0.0);
+        row_stack.setAutoresizingMask(
+            objc2_app_kit::NSAutoresizingMaskOptions::NSViewWidthSizable
+            | objc2_app_kit::NSAutoresizingMaskOptions::NSViewMinYMargin,
+        );
+        let row_target: &AnyObject = &text_view;
+        let row_buttons: Vec<Retained<NSButton>> = (0..PAGE_SIZE)
+            .map(|slot| {
+                let row = ItemRowButton::new(mtm, slot, row_target);
+                row_stack.addArrangedSubview(&row);
+                Retained::into_super(row)
+            })
+            .collect();
+        content_view.addSubview(&row_stack);
+
+        *self.header_label.borrow_mut() = Some(header);
+        *self.row_stack.borrow_mut() = Some(row_stack);
+        *self.row_buttons.borrow_mut() = row_buttons;
+        // Nothing is drawn in the new views yet
+        *self.content_hash.borrow_mut() = 0;
+        *self.header_hash.borrow_mut() = 0;
+        self.row_hashes.borrow_mut().clear();
+        *self.pinned_gap_row.borrow_mut() = None;
+
+        window.setContentView(Some(&content_view));
 
         // Store text view for later updates (cast subclass to NSTextView)
         let text_view_as_super: Retained<NSTextView> = Retained::into_super(text_view);
@@ -470,8 +993,7 @@ impl PopupWindow {
 
         // Create and set window delegate to handle red X close button
         let delegate = WindowDelegate::new(mtm);
-        let delegate_ptr: *const WindowDelegate = &*delegate;
-        let _: () = msg_send![&window, setDelegate: delegate_ptr];
+        window.setDelegate(Some(ProtocolObject::from_ref(&*delegate)));
         *self.window_delegate.borrow_mut() = Some(delegate);
 
         log::info!("✓ Window created with keyboard navigation support and close delegate");
@@ -479,39 +1001,57 @@ impl PopupWindow {
         window
     }
 
-    fn load_items(&self, reset_selection: bool) {
+    /// Load the item list. At offset 0 the list is replaced, keeping as many pages
+    /// as were loaded before (or just the first when `reset_selection`). A later
+    /// offset appends that page of the current tab and time filter.
+    fn load_items(&self, offset: usize, reset_selection: bool) {
         let search_query = self.search_query.borrow().clone();
-        let type_f = *self.type_filter.borrow();
+        let item_f = *self.item_filter.borrow();
         let time_f = *self.time_filter.borrow();
 
-        let db_result = self.db.lock();
-        let db_guard = match db_result {
-            Ok(guard) => Some(guard),
-            Err(poisoned) => {
-                log::error!("Database mutex poisoned in load_items, recovering...");
-                Some(poisoned.into_inner())
-            }
-        };
-        if let Some(db) = db_guard {
+        if reset_selection {
+            *self.page_offset.borrow_mut() = 0;
+        }
+
+        if let Some(db) = self.db.lock_or_log() {
             let has_search = !search_query.is_empty();
-            let has_filters = type_f != TypeFilter::All || time_f != TimeFilter::AllTime;
-            let fetch_limit = if has_search { 200 } else { 20 };
+            let page_limit = if offset > 0 { PAGE_SIZE } else { *self.page_offset.borrow() + PAGE_SIZE };
+
+            let data_type = item_f.db_value();
+            let cutoff = time_f.timestamp_cutoff();
+            let sensitive_only = item_f.sensitive_only();
 
-            let db_items = if has_filters {
-                db.search_items(type_f.db_value(), time_f.timestamp_cutoff(), fetch_limit)
+            match db.count_matching_items(data_type, cutoff, sensitive_only) {
+                Ok(total) => *self.total_items.borrow_mut() = total as usize,
+                Err(e) => log::error!("Failed to count items: {}", e),
+            }
+
+            let unfiltered = item_f == ItemFilter::All && time_f == TimeFilter::AllTime;
+            let db_items = if has_search && unfiltered {
+                // Unfiltered searches use the full-text index over the whole history
+                self.search_engine.search_database(&db, &search_query, 20)
+            } else if has_search {
+                db.search_items_page(data_type, cutoff, sensitive_only, 0, 200)
             } else {
-                db.get_recent_items(fetch_limit)
+                db.search_items_page(data_type, cutoff, sensitive_only, offset as i64, page_limit as i32)
             };
 
             match db_items {
                 Ok(items) => {
-                    let final_items = if has_search {
+                    let filtered_search = has_search && !unfiltered;
+                    let final_items = if filtered_search {
                         let results = self.search_engine.search(&items, &search_query);
                         results.into_iter().map(|(_, item)| item.clone()).take(20).collect()
                     } else {
                         items
                     };
 
+                    if offset > 0 {
+                        *self.page_offset.borrow_mut() = offset;
+                        self.items.borrow_mut().extend(final_items);
+                        return;
+                    }
+
                     if reset_selection {
                         *self.selected_index.borrow_mut() = 0;
                     } else {
@@ -527,190 +1067,616 @@ impl PopupWindow {
         }
     }
 
-    fn refresh_display(&self) {
+    /// Reload items and redraw only if what would be rendered has changed.
+    /// Used by the auto-refresh thread so idle ticks don't rebuild the view.
+    pub fn refresh_items_delta(&self) {
+        self.load_items(0, false);
+        self.refresh_display();
+    }
+
+    /// FNV-1a hash over everything `refresh_display` renders (items with their
+    /// relative timestamps, selection and search state).
+    fn compute_content_hash(&self) -> u64 {
         let items = self.items.borrow();
         let selected_idx = *self.selected_index.borrow();
 
-        let text_view = self.text_view.borrow();
-        let Some(text_view) = text_view.as_ref() else { return };
-
-        unsafe {
-            let mut result = NSMutableAttributedString::new();
+        let mut hash = FNV_OFFSET_BASIS;
+        for item in items.iter() {
+            hash = fnv1a(hash, &item.id.0.to_le_bytes());
+            hash = fnv1a(hash, &item.timestamp.to_le_bytes());
+            hash = fnv1a(hash, &item.copy_count.to_le_bytes());
+            // The override itself, not just its effect, picks the "Marked ..." note
+            let sensitivity_override = match item.sensitivity_override {
+                None => 0,
+                Some(false) => 1,
+                Some(true) => 2,
+            };
+            hash = fnv1a(hash, &[item.is_pinned as u8, item.is_encrypted as u8, item.effective_sensitivity() as u8, sensitivity_override]);
+            hash = fnv1a(hash, item.data_type.as_bytes());
+            hash = fnv1a(hash, item.preview_text.as_deref().unwrap_or("").as_bytes());
+            hash = fnv1a(hash, format_relative(item.timestamp).as_bytes());
+            // Tag pills; the terminator keeps ["ab"] and ["a", "b"] apart
+            for tag in &item.tags {
+                hash = fnv1a(hash, tag.as_bytes());
+                hash = fnv1a(hash, &[0]);
+            }
+        }
+        hash = fnv1a(hash, &selected_idx.to_le_bytes());
+        hash = fnv1a(hash, self.search_query.borrow().as_bytes());
+        hash = fnv1a(hash, &self.search_cursor.borrow().to_le_bytes());
+        hash = fnv1a(hash, self.item_filter.borrow().label().as_bytes());
+        hash = fnv1a(hash, self.time_filter.borrow().label().as_bytes());
+        if let Some(status) = self.active_status_message() {
+            hash = fnv1a(hash, status.as_bytes());
+        }
+        hash = fnv1a(hash, &[*self.show_help.borrow() as u8]);
+        hash = fnv1a(hash, &self.total_items.borrow().to_le_bytes());
+        hash
+    }
 
-            let mono_font = NSFont::monospacedSystemFontOfSize_weight(13.0, 0.0);
-            let bold_font = NSFont::monospacedSystemFontOfSize_weight(14.0, 0.3);
-            let small_font = NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0);
+    fn refresh_display(&self) {
+        // Skip all Objective-C work when nothing visible has changed
+        let hash = self.compute_content_hash();
+        if *self.content_hash.borrow() == hash {
+            return;
+        }
+        *self.content_hash.borrow_mut() = hash;
 
-            let fg_key = NSString::from_str("NSColor");
-            let bg_key = NSString::from_str("NSBackgroundColor");
-            let font_key = NSString::from_str("NSFont");
+        self.resize_to_content();
 
-            // Search state
-            let search_q = self.search_query.borrow().clone();
-            let type_f = *self.type_filter.borrow();
-            let time_f = *self.time_filter.borrow();
-            let search_active = !search_q.is_empty()
-                || type_f != TypeFilter::All
-                || time_f != TimeFilter::AllTime;
+        let text_view = self.text_view.borrow().clone();
+        if *self.show_help.borrow() {
+            if let Some(stack) = self.row_stack.borrow().as_ref() {
+                stack.setHidden(true);
+            }
+            self.layout_list();
+            if let Some(text_view) = text_view {
+                self.render_help(&text_view);
+            }
+            return;
+        }
 
-            // Header
-            Self::append_styled_line(
-                &mut result, "  Clipboard History\n",
-                &bold_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
-            );
+        let (changed_rows, rows_moved) = self.changed_rows();
+        let Some(text_view) = text_view else { return };
 
-            if search_active {
-                let filter_line = format!("  [{}]  [{}]\n",
-                    type_f.label(), time_f.label());
-                Self::append_styled_line(
-                    &mut result, &filter_line,
-                    &small_font, &NSColor::systemBlueColor(), None, &font_key, &fg_key, &bg_key,
-                );
-                // Render search query with cursor position
-                let cursor_pos = *self.search_cursor.borrow();
-                let (before, after) = if cursor_pos <= search_q.len() {
-                    let (a, b) = search_q.split_at(cursor_pos);
-                    (a.to_string(), b.to_string())
-                } else {
-                    (search_q.clone(), String::new())
-                };
-                let search_line = format!("  > {}|{}\n\n", before, after);
-                Self::append_styled_line(
-                    &mut result, &search_line,
-                    &mono_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
-                );
-            } else {
-                Self::append_styled_line(
-                    &mut result, "  Type to search | Tab filter | \u{2318}P pin | \u{2318}D delete | Esc close\n\n",
-                    &small_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
-                );
+        unsafe {
+            let header_changed = self.render_header();
+            let gap_moved = self.render_rows(&changed_rows, rows_moved);
+            let rows_shown = self.row_stack.borrow().as_ref().is_some_and(|stack| {
+                let hidden = stack.isHidden();
+                stack.setHidden(false);
+                hidden
+            });
+            if header_changed || rows_moved || gap_moved || rows_shown {
+                self.layout_list();
             }
+            self.render_details(&text_view);
+        }
+    }
 
-            let mut item_char_positions: Vec<usize> = Vec::new();
+    /// Whether a search, category tab or time filter narrows the list
+    fn search_active(&self) -> bool {
+        !self.search_query.borrow().is_empty()
+            || *self.item_filter.borrow() != ItemFilter::All
+            || *self.time_filter.borrow() != TimeFilter::AllTime
+    }
 
-            if items.is_empty() {
-                let empty_msg = if search_active {
-                    "  No results found.\n"
-                } else {
-                    "  No clipboard history yet.\n  Copy something to get started!\n"
-                };
-                Self::append_styled_line(
-                    &mut result, empty_msg,
-                    &mono_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
-                );
-            } else {
-                for (i, item) in items.iter().enumerate() {
-                    item_char_positions.push(result.length());
-                    let is_selected = i == selected_idx;
-                    let pin = if item.is_pinned { "📌" } else { "" };
-                    let icon = match item.data_type.as_str() {
-                        "image" => "🖼️",
-                        "url" => "🔗",
-                        _ => "📝",
-                    };
-                    let lock = if item.is_sensitive { " 🔒" } else { "" };
+    /// Row slots whose content or selection state changed since they were last
+    /// drawn, after moving the visible rows so the selected item is among them.
+    /// The flag is set when a row now shows a different item (or none), which
+    /// can change its height.
+    fn changed_rows(&self) -> (Vec<usize>, bool) {
+        let items = self.items.borrow();
+        let selected_idx = *self.selected_index.borrow();
+        let start = visible_row_start(*self.first_visible_row.borrow(), selected_idx, items.len());
+        *self.first_visible_row.borrow_mut() = start;
+        let typing = !self.search_query.borrow().is_empty();
+
+        let drawn: Vec<Option<(ItemId, u64)>> = (0..PAGE_SIZE)
+            .map(|slot| {
+                let index = start + slot;
+                items.get(index).map(|item| {
+                    (item.id, row_hash(item, index, index == selected_idx, quick_paste_digit(index, typing)))
+                })
+            })
+            .collect();
+
+        let mut row_hashes = self.row_hashes.borrow_mut();
+        let mut rows_moved = false;
+        let changed = (0..PAGE_SIZE)
+            .filter(|&slot| {
+                let before = row_hashes.get(slot).copied().flatten();
+                rows_moved |= before.map(|(id, _)| id) != drawn[slot].map(|(id, _)| id);
+                before != drawn[slot]
+            })
+            .collect();
+        *row_hashes = drawn;
+        (changed, rows_moved)
+    }
 
-                    let preview = item.preview_text.as_deref().unwrap_or("[No preview]");
-                    let max_preview = if item.is_pinned { 62 } else { 65 };
-                    let preview_short = if preview.chars().count() > max_preview {
-                        format!("{}...", preview.chars().take(max_preview).collect::<String>())
-                    } else {
-                        preview.to_string()
-                    };
+    /// Title, search line and status message above the rows.
+    /// Returns whether they changed (and so may need a different height).
+    unsafe fn render_header(&self) -> bool {
+        let header = self.header_label.borrow();
+        let Some(header) = header.as_ref() else { return false };
 
-                    let count_badge = if item.copy_count > 1 {
-                        format!(" (×{})", item.copy_count)
-                    } else {
-                        String::new()
-                    };
+        let mono_font = NSFont::monospacedSystemFontOfSize_weight(13.0, 0.0);
+        let bold_font = NSFont::monospacedSystemFontOfSize_weight(14.0, 0.3);
+        let small_font = NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0);
 
-                    let marker = if is_selected { "▶" } else { " " };
-                    let line = format!(" {} {}{} {}{}{}\n", marker, pin, icon, preview_short, count_badge, lock);
+        let fg_key = NSString::from_str("NSColor");
+        let bg_key = NSString::from_str("NSBackgroundColor");
+        let font_key = NSString::from_str("NSFont");
 
-                    let bg_color = if is_selected {
-                        Some(NSColor::selectedContentBackgroundColor())
-                    } else if i % 2 == 1 {
-                        Some(NSColor::controlBackgroundColor())
-                    } else {
-                        None
-                    };
+        let search_q = self.search_query.borrow().clone();
+        let item_f = *self.item_filter.borrow();
+        let time_f = *self.time_filter.borrow();
+        let pinned_total = self.items.borrow().iter().filter(|item| item.is_pinned).count();
+        let status = self.active_status_message();
+
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, &pinned_total.to_le_bytes());
+        hash = fnv1a(hash, search_q.as_bytes());
+        hash = fnv1a(hash, &self.search_cursor.borrow().to_le_bytes());
+        hash = fnv1a(hash, item_f.label().as_bytes());
+        hash = fnv1a(hash, time_f.label().as_bytes());
+        hash = fnv1a(hash, status.as_deref().unwrap_or("").as_bytes());
+        if *self.header_hash.borrow() == hash {
+            return false;
+        }
+        *self.header_hash.borrow_mut() = hash;
 
-                    let fg_color = if is_selected {
-                        NSColor::selectedMenuItemTextColor()
-                    } else {
-                        NSColor::labelColor()
-                    };
+        let mut result = NSMutableAttributedString::new();
 
-                    Self::append_styled_line(
-                        &mut result, &line,
-                        &mono_font, &fg_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
-                    );
-                }
-                // Sentinel: marks end of last item
-                item_char_positions.push(result.length());
-            }
+        let title = if pinned_total > 0 {
+            format!("  Clipboard History  ({} pinned)\n", pinned_total)
+        } else {
+            "  Clipboard History\n".to_string()
+        };
+        Self::append_styled_line(
+            &mut result, &title,
+            &bold_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+        );
 
-            *self.item_char_starts.borrow_mut() = item_char_positions;
+        if self.search_active() {
+            let filter_line = format!("  [{}]  [{}]\n",
+                item_f.label(), time_f.label());
+            Self::append_styled_line(
+                &mut result, &filter_line,
+                &small_font, &NSColor::systemBlueColor(), None, &font_key, &fg_key, &bg_key,
+            );
+            // Render search query with cursor position
+            let cursor_pos = *self.search_cursor.borrow();
+            let (before, after) = if cursor_pos <= search_q.len() {
+                let (a, b) = search_q.split_at(cursor_pos);
+                (a.to_string(), b.to_string())
+            } else {
+                (search_q.clone(), String::new())
+            };
+            let search_line = format!("  🔍 {}|{}\n", before, after);
+            Self::append_styled_line(
+                &mut result, &search_line,
+                &mono_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        } else {
+            Self::append_styled_line(
+                &mut result, "  🔍 Type to search | Tab filter | \u{2318}P pin | \u{2318}D delete | ? help | Esc close\n",
+                &small_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        }
 
-            // Preview pane: show full text of selected item
-            if let Some(selected_item) = items.get(selected_idx) {
-                Self::append_styled_line(
-                    &mut result, "\n  ─────────────────────────────────────────\n",
-                    &small_font, &NSColor::separatorColor(), None, &font_key, &fg_key, &bg_key,
-                );
+        if let Some(status) = status {
+            Self::append_styled_line(
+                &mut result, &format!("\n  {}\n", status),
+                &small_font, &NSColor::systemGreenColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        }
 
-                let type_label = match selected_item.data_type.as_str() {
-                    "image" => "Image",
-                    "url" => "URL",
-                    _ => "Text",
-                };
-                let count_info = if selected_item.copy_count > 1 {
-                    format!(" • copied ×{}", selected_item.copy_count)
-                } else {
-                    String::new()
-                };
-                let pin_info = if selected_item.is_pinned { " • 📌 pinned" } else { "" };
-                let time_info = Self::format_relative_time(selected_item.timestamp);
-                let header = format!("  {} • {}{}{}\n\n", type_label, time_info, count_info, pin_info);
-                Self::append_styled_line(
-                    &mut result, &header,
-                    &bold_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
-                );
+        header.setAttributedStringValue(&result);
+        true
+    }
 
-                let full_text = selected_item.preview_text.as_deref().unwrap_or("[No preview]");
-                // Wrap long text at ~80 chars for readability
-                let wrapped = Self::word_wrap(full_text, 80);
-                let padded = wrapped.lines()
-                    .map(|line| format!("  {}", line))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                Self::append_styled_line(
-                    &mut result, &format!("{}\n", padded),
-                    &mono_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
-                );
+    /// Redraw the row buttons in `changed` and move the gap after the pinned
+    /// section. Returns whether the gap moved.
+    unsafe fn render_rows(&self, changed: &[usize], rows_moved: bool) -> bool {
+        let items = self.items.borrow();
+        let selected_idx = *self.selected_index.borrow();
+        let start = *self.first_visible_row.borrow();
+        let row_buttons = self.row_buttons.borrow();
+
+        for &slot in changed {
+            let Some(row) = row_buttons.get(slot) else { continue };
+            let index = start + slot;
+            match items.get(index) {
+                Some(item) => {
+                    let is_selected = index == selected_idx;
+                    row.setAttributedTitle(&self.row_title(item, index, is_selected));
+                    row.setState(if is_selected { NSControlStateValueOn } else { NSControlStateValueOff });
+                    row.setHidden(false);
+                }
+                None => row.setHidden(true),
             }
+        }
 
-            // Replace text storage contents
-            if let Some(mut storage) = text_view.textStorage() {
-                let full_range = NSRange::new(0, storage.length());
-                storage.replaceCharactersInRange_withAttributedString(full_range, &result);
+        if rows_moved {
+            // Only keep thumbnails and app icons for rows still listed
+            self.thumbnails.borrow_mut()
+                .retain(|blob_id, _| items.iter().any(|item| item.thumbnail_blob_id == Some(*blob_id)));
+            self.app_icons.borrow_mut()
+                .retain(|bundle_id, _| items.iter().any(|item| item.source_bundle_id.as_deref() == Some(bundle_id.as_str())));
+        }
+
+        // A gap under the last pinned row, unless the list is all pinned
+        let pinned_section = Self::pinned_section_len(&items, self.search_active());
+        let gap_row = (pinned_section > 0 && pinned_section < items.len())
+            .then(|| (pinned_section - 1).checked_sub(start))
+            .flatten()
+            .filter(|&slot| slot < PAGE_SIZE);
+        if *self.pinned_gap_row.borrow() == gap_row {
+            return false;
+        }
+        let stack = self.row_stack.borrow();
+        if let Some(stack) = stack.as_ref() {
+            if let Some(row) = self.pinned_gap_row.borrow().and_then(|slot| row_buttons.get(slot)) {
+                stack.setCustomSpacing_afterView(0.0, row);
+            }
+            if let Some(row) = gap_row.and_then(|slot| row_buttons.get(slot)) {
+                stack.setCustomSpacing_afterView(PINNED_GAP, row);
             }
         }
+        *self.pinned_gap_row.borrow_mut() = gap_row;
+        true
     }
 
-    unsafe fn append_styled_line(
-        result: &mut NSMutableAttributedString,
-        text: &str,
-        font: &NSFont,
-        fg_color: &NSColor,
-        bg_color: Option<&NSColor>,
-        font_key: &NSString,
-        fg_key: &NSString,
-        bg_key: &NSString,
-    ) {
-        let ns_str = NSString::from_str(text);
-        let line_attr = NSMutableAttributedString::initWithString(
-            objc2_foundation::NSMutableAttributedString::alloc(),
+    /// Title of the row button for `item`, listed at `index`
+    unsafe fn row_title(&self, item: &ClipboardItem, index: usize, is_selected: bool) -> Retained<NSMutableAttributedString> {
+        let mut result = NSMutableAttributedString::new();
+
+        let mono_font = NSFont::monospacedSystemFontOfSize_weight(13.0, 0.0);
+        let small_font = NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0);
+
+        let fg_key = NSString::from_str("NSColor");
+        let bg_key = NSString::from_str("NSBackgroundColor");
+        let font_key = NSString::from_str("NSFont");
+
+        let pin = if item.is_pinned { "📌 " } else { "" };
+        let icon = match item.data_type.as_str() {
+            "image" => "🖼️",
+            "url" => "🔗",
+            "credential" => "🔑",
+            "certificate" => "📜",
+            "email" => "✉️",
+            _ => "📝",
+        };
+        // Only encrypted data gets the lock; a manual "sensitive" mark doesn't seal the blob
+        let lock = if item.is_encrypted { " 🔒" } else { "" };
+
+        let preview = item.preview_text.as_deref().unwrap_or("[No preview]");
+        let max_preview = if item.is_pinned { 48 } else { 51 };
+        let preview_short = if preview.chars().count() > max_preview {
+            format!("{}...", preview.chars().take(max_preview).collect::<String>())
+        } else {
+            preview.to_string()
+        };
+
+        let count_badge = if item.copy_count > 1 {
+            format!(" (×{})", item.copy_count)
+        } else {
+            String::new()
+        };
+
+        let marker = if is_selected { "▶" } else { " " };
+        let typing = !self.search_query.borrow().is_empty();
+        let shortcut = match quick_paste_digit(index, typing) {
+            Some(digit) => format!("[{}]", digit),
+            None => "   ".to_string(),
+        };
+
+        let bg_color = if is_selected {
+            Some(NSColor::selectedContentBackgroundColor())
+        } else if item.is_pinned {
+            Some(NSColor::systemYellowColor().colorWithAlphaComponent(0.15))
+        } else if index % 2 == 1 {
+            Some(NSColor::controlBackgroundColor())
+        } else {
+            None
+        };
+
+        let fg_color = if is_selected {
+            NSColor::selectedMenuItemTextColor()
+        } else {
+            NSColor::labelColor()
+        };
+
+        Self::append_styled_line(
+            &mut result, &format!(" {} {} {}", shortcut, marker, pin),
+            &mono_font, &fg_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
+        );
+
+        // Icon of the app the item was copied from
+        let app_icon = item.source_bundle_id.as_deref()
+            .and_then(|bundle_id| self.app_icon_image(bundle_id));
+        if let Some(app_icon) = app_icon {
+            Self::append_inline_image(&mut result, &app_icon, APP_ICON_DISPLAY_SIZE, &mono_font, bg_color.as_deref(), &bg_key);
+            Self::append_styled_line(
+                &mut result, " ",
+                &mono_font, &fg_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
+            );
+        }
+
+        // Image rows show their thumbnail in place of the icon
+        let thumbnail = item.thumbnail_blob_id
+            .filter(|_| item.data_type == "image")
+            .and_then(|blob_id| self.thumbnail_image(blob_id));
+        if let Some(thumbnail) = thumbnail {
+            Self::append_inline_image(&mut result, &thumbnail, THUMBNAIL_DISPLAY_HEIGHT, &mono_font, bg_color.as_deref(), &bg_key);
+            Self::append_styled_line(
+                &mut result, &format!(" {}{}{}", preview_short, count_badge, lock),
+                &mono_font, &fg_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
+            );
+        } else {
+            let line = format!("{} {}{}{}", icon, preview_short, count_badge, lock);
+            Self::append_styled_line(
+                &mut result, &line,
+                &mono_font, &fg_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
+            );
+        }
+        let time_color = if is_selected { fg_color.clone() } else { NSColor::secondaryLabelColor() };
+        Self::append_styled_line(
+            &mut result, &format!("  {}", format_relative(item.timestamp)),
+            &small_font, &time_color, bg_color.as_deref(), &font_key, &fg_key, &bg_key,
+        );
+        result
+    }
+
+    /// Text under the rows: the empty-list message or page footer, then the
+    /// preview pane for the selected item
+    unsafe fn render_details(&self, text_view: &NSTextView) {
+        let items = self.items.borrow();
+        let selected_idx = *self.selected_index.borrow();
+
+        let mut result = NSMutableAttributedString::new();
+
+        let mono_font = NSFont::monospacedSystemFontOfSize_weight(13.0, 0.0);
+        let bold_font = NSFont::monospacedSystemFontOfSize_weight(14.0, 0.3);
+        let small_font = NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0);
+
+        let fg_key = NSString::from_str("NSColor");
+        let bg_key = NSString::from_str("NSBackgroundColor");
+        let font_key = NSString::from_str("NSFont");
+
+        if items.is_empty() {
+            let empty_msg = if self.search_active() {
+                "  No results found.\n"
+            } else {
+                "  No clipboard history yet.\n  Copy something to get started!\n"
+            };
+            Self::append_styled_line(
+                &mut result, empty_msg,
+                &mono_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        } else if self.search_query.borrow().is_empty() {
+            Self::append_styled_line(
+                &mut result, &format!("  {}\n", Self::page_footer(items.len(), *self.total_items.borrow())),
+                &small_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        }
+
+        // Preview pane: show full text of selected item
+        if let Some(selected_item) = items.get(selected_idx) {
+            Self::append_styled_line(
+                &mut result, "  ─────────────────────────────────────────\n",
+                &small_font, &NSColor::separatorColor(), None, &font_key, &fg_key, &bg_key,
+            );
+
+            let type_label = match selected_item.data_type.as_str() {
+                "image" => "Image",
+                "url" => "URL",
+                "credential" => "Credential",
+                "certificate" => "Certificate",
+                "email" => "Email",
+                _ => "Text",
+            };
+            let count_info = if selected_item.copy_count > 1 {
+                format!(" • copied ×{}", selected_item.copy_count)
+            } else {
+                String::new()
+            };
+            let pin_info = if selected_item.is_pinned { " • 📌 pinned" } else { "" };
+            let source_info = selected_item.source_app_name.as_deref()
+                .map(|app| format!(" • from {}", app))
+                .unwrap_or_default();
+            let time_info = format_relative(selected_item.timestamp);
+            let header = format!("  {} • {}{}{}{}\n\n", type_label, time_info, source_info, count_info, pin_info);
+            Self::append_styled_line(
+                &mut result, &header,
+                &bold_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+
+            // Explain why a sensitive item was flagged (or that the user decided)
+            if let Some(sensitive) = selected_item.sensitivity_override {
+                let note = if sensitive { "  Marked sensitive\n\n" } else { "  Marked not sensitive\n\n" };
+                Self::append_styled_line(
+                    &mut result, note,
+                    &small_font, &NSColor::systemOrangeColor(), None, &font_key, &fg_key, &bg_key,
+                );
+            } else if selected_item.is_sensitive {
+                if let Some(preview) = selected_item.preview_text.as_deref() {
+                    let analysis = SensitivityAnalyzer::analyze(preview);
+                    if !analysis.triggered_patterns.is_empty() {
+                        let reasons: Vec<&str> = analysis.triggered_patterns.iter()
+                            .map(|p| SensitivityAnalyzer::describe(p))
+                            .collect();
+                        Self::append_styled_line(
+                            &mut result, &format!("  Flagged: {}\n\n", reasons.join(", ")),
+                            &small_font, &NSColor::systemOrangeColor(), None, &font_key, &fg_key, &bg_key,
+                        );
+                    }
+                }
+            }
+
+            if !selected_item.tags.is_empty() {
+                let pill_color = NSColor::quaternaryLabelColor();
+                Self::append_styled_line(
+                    &mut result, "  ",
+                    &small_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+                );
+                for tag in &selected_item.tags {
+                    Self::append_styled_line(
+                        &mut result, &format!(" #{} ", tag),
+                        &small_font, &NSColor::labelColor(), Some(&pill_color), &font_key, &fg_key, &bg_key,
+                    );
+                    Self::append_styled_line(
+                        &mut result, " ",
+                        &small_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+                    );
+                }
+                Self::append_styled_line(
+                    &mut result, "\n\n",
+                    &small_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+                );
+            }
+
+            let full_text = selected_item.preview_text.as_deref().unwrap_or("[No preview]");
+            // Wrap long text at ~80 chars for readability
+            let wrapped = Self::word_wrap(full_text, 80);
+            let padded = wrapped.lines()
+                .map(|line| format!("  {}", line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Self::append_styled_line(
+                &mut result, &format!("{}\n", padded),
+                &mono_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+        }
+
+        // Replace text storage contents
+        if let Some(mut storage) = text_view.textStorage() {
+            let full_range = NSRange::new(0, storage.length());
+            storage.replaceCharactersInRange_withAttributedString(full_range, &result);
+        }
+    }
+
+    /// Stack the header, the rows and the preview pane under the tab bar: the
+    /// header and rows as tall as their content, the preview pane taking the rest
+    fn layout_list(&self) {
+        let (Some(header), Some(stack), Some(text_view)) = (
+            self.header_label.borrow().clone(),
+            self.row_stack.borrow().clone(),
+            self.text_view.borrow().clone(),
+        ) else {
+            return;
+        };
+        unsafe {
+            let (Some(content_view), Some(scroll_view)) = (header.superview(), text_view.enclosingScrollView()) else {
+                return;
+            };
+            let bounds = content_view.bounds();
+            let width = bounds.size.width;
+
+            let header_height = header.fittingSize().height;
+            let header_y = (bounds.size.height - TAB_BAR_HEIGHT - header_height).max(0.0);
+            header.setFrame(NSRect::new(NSPoint::new(0.0, header_y), NSSize::new(width, header_height)));
+
+            let rows_height = if stack.isHidden() { 0.0 } else { stack.fittingSize().height };
+            let rows_y = (header_y - rows_height).max(0.0);
+            stack.setFrame(NSRect::new(NSPoint::new(0.0, rows_y), NSSize::new(width, rows_height)));
+
+            scroll_view.setFrame(NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(width, rows_y)));
+        }
+    }
+
+    /// Footer under the plain history list, e.g. "Showing 1–40 of 312"
+    fn page_footer(loaded: usize, total: usize) -> String {
+        format!("Showing 1–{} of {}", loaded, total.max(loaded))
+    }
+
+    /// Number of leading pinned rows, set off from the rest by a gap. Search
+    /// results are ordered by score, so the section is only drawn for the plain list.
+    fn pinned_section_len(items: &[ClipboardItem], search_active: bool) -> usize {
+        if search_active {
+            return 0;
+        }
+        items.iter().take_while(|item| item.is_pinned).count()
+    }
+
+    /// Replace the preview pane with the keyboard shortcut table (the rows are hidden meanwhile)
+    fn render_help(&self, text_view: &NSTextView) {
+        unsafe {
+            let mut result = NSMutableAttributedString::new();
+
+            let header_font = NSFont::boldSystemFontOfSize(18.0);
+            let key_font = NSFont::monospacedSystemFontOfSize_weight(13.0, 0.3);
+            let desc_font = NSFont::systemFontOfSize(13.0);
+            let small_font = NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0);
+
+            let fg_key = NSString::from_str("NSColor");
+            let bg_key = NSString::from_str("NSBackgroundColor");
+            let font_key = NSString::from_str("NSFont");
+
+            Self::append_styled_line(
+                &mut result, "  Keyboard Shortcuts\n\n",
+                &header_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+
+            let key_width = KEYBOARD_SHORTCUTS.iter()
+                .map(|(key, _)| key.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (key, description) in KEYBOARD_SHORTCUTS {
+                let padding = key_width - key.chars().count();
+                Self::append_styled_line(
+                    &mut result, &format!("  {}{}    ", key, " ".repeat(padding)),
+                    &key_font, &NSColor::systemBlueColor(), None, &font_key, &fg_key, &bg_key,
+                );
+                Self::append_styled_line(
+                    &mut result, &format!("{}\n", description),
+                    &desc_font, &NSColor::labelColor(), None, &font_key, &fg_key, &bg_key,
+                );
+            }
+
+            Self::append_styled_line(
+                &mut result, "\n  Press ? or Esc to return to history\n",
+                &small_font, &NSColor::secondaryLabelColor(), None, &font_key, &fg_key, &bg_key,
+            );
+
+            if let Some(mut storage) = text_view.textStorage() {
+                let full_range = NSRange::new(0, storage.length());
+                storage.replaceCharactersInRange_withAttributedString(full_range, &result);
+            }
+        }
+    }
+
+    pub fn is_showing_help(&self) -> bool {
+        *self.show_help.borrow()
+    }
+
+    /// Show or hide the shortcut cheat-sheet
+    pub fn toggle_help(&self) {
+        {
+            let mut show = self.show_help.borrow_mut();
+            *show = !*show;
+        }
+        self.refresh_display();
+    }
+
+    pub fn hide_help(&self) {
+        *self.show_help.borrow_mut() = false;
+        self.refresh_display();
+    }
+
+    unsafe fn append_styled_line(
+        result: &mut NSMutableAttributedString,
+        text: &str,
+        font: &NSFont,
+        fg_color: &NSColor,
+        bg_color: Option<&NSColor>,
+        font_key: &NSString,
+        fg_key: &NSString,
+        bg_key: &NSString,
+    ) {
+        let ns_str = NSString::from_str(text);
+        let line_attr = NSMutableAttributedString::initWithString(
+            objc2_foundation::NSMutableAttributedString::alloc(),
             &ns_str,
         );
         let range = NSRange::new(0, ns_str.length());
@@ -726,6 +1692,57 @@ impl PopupWindow {
         result.appendAttributedString(&line_attr);
     }
 
+    /// Append `image` as an inline attachment scaled to `height` points,
+    /// sitting on the line's descender like the emoji icons
+    unsafe fn append_inline_image(
+        result: &mut NSMutableAttributedString,
+        image: &NSImage,
+        height: f64,
+        font: &NSFont,
+        bg_color: Option<&NSColor>,
+        bg_key: &NSString,
+    ) {
+        let attachment: Retained<NSTextAttachment> = msg_send_id![NSTextAttachment::class(), new];
+        attachment.setImage(Some(image));
+        let size = image.size();
+        let width = if size.height > 0.0 { size.width * height / size.height } else { height };
+        attachment.setBounds(NSRect::new(NSPoint::new(0.0, font.descender()), NSSize::new(width, height)));
+
+        let start = result.length();
+        result.appendAttributedString(&NSAttributedString::attributedStringWithAttachment(&attachment));
+        if let Some(bg) = bg_color {
+            let _: () = msg_send![&*result, addAttribute: bg_key, value: bg, range: NSRange::new(start, result.length() - start)];
+        }
+    }
+
+    /// Decoded thumbnail for an image row, loaded from the database once and cached
+    fn thumbnail_image(&self, blob_id: BlobId) -> Option<Retained<NSImage>> {
+        if let Some(cached) = self.thumbnails.borrow().get(&blob_id) {
+            return cached.clone();
+        }
+        let png = self.db.lock_or_log()?.get_thumbnail(blob_id);
+        let image = match png {
+            Ok(png) => unsafe { NSImage::initWithData(NSImage::alloc(), &NSData::with_bytes(&png)) },
+            Err(e) => {
+                log::warn!("Failed to load thumbnail blob #{}: {}", blob_id, e);
+                None
+            }
+        };
+        self.thumbnails.borrow_mut().insert(blob_id, image.clone());
+        image
+    }
+
+    /// Source app icon for a row, loaded through the database once and cached
+    fn app_icon_image(&self, bundle_id: &str) -> Option<Retained<NSImage>> {
+        if let Some(cached) = self.app_icons.borrow().get(bundle_id) {
+            return cached.clone();
+        }
+        let png = self.db.lock_or_log()?.get_app_icon(bundle_id);
+        let image = png.and_then(|png| unsafe { NSImage::initWithData(NSImage::alloc(), &NSData::with_bytes(&png)) });
+        self.app_icons.borrow_mut().insert(bundle_id.to_string(), image.clone());
+        image
+    }
+
     fn word_wrap(text: &str, width: usize) -> String {
         let mut result = String::new();
         for line in text.lines() {
@@ -754,23 +1771,25 @@ impl PopupWindow {
     }
 
     pub fn toggle(&mut self) {
-        // Sync visible state with actual window visibility
-        // (handles case where user closed window via red X button)
-        if let Some(window) = self.window.borrow().as_ref() {
-            self.visible = window.isVisible();
+        if self.is_visible() {
+            self.hide();
+        } else {
+            self.show();
         }
+    }
 
-        self.visible = !self.visible;
-
-        if self.visible {
-            self.show();
-        } else {
-            self.hide();
+    /// Called by the window delegate when the red X button closes the window
+    pub fn on_window_close(&mut self) {
+        if let Some(window) = self.window.borrow().as_ref() {
+            self.save_frame_if_moved(window);
         }
+        *self.visible.borrow_mut() = false;
+        self.auto_refresh_active.store(false, Ordering::Relaxed);
+        log::info!("Popup window closed");
     }
 
     pub fn show(&mut self) {
-        self.visible = true;
+        *self.visible.borrow_mut() = true;
         log::info!("📋 Popup window shown (Cmd+Shift+C pressed)");
 
         unsafe {
@@ -787,11 +1806,12 @@ impl PopupWindow {
                 // Reset search state on open
                 *self.search_query.borrow_mut() = String::new();
                 *self.search_cursor.borrow_mut() = 0;
-                *self.type_filter.borrow_mut() = TypeFilter::All;
+                *self.item_filter.borrow_mut() = ItemFilter::All;
                 *self.time_filter.borrow_mut() = TimeFilter::AllTime;
+                *self.show_help.borrow_mut() = false;
 
                 // Load and display items
-                self.load_items(true);
+                self.load_items(0, true);
                 self.refresh_display();
 
                 // Start auto-refresh thread (refreshes every 1s while popup is open)
@@ -806,10 +1826,8 @@ impl PopupWindow {
                             dispatch::Queue::main().exec_async(move || {
                                 let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                                     if !active_inner.load(Ordering::Relaxed) { return; }
-                                    if let Some(popup_arc) = POPUP_FOR_KEYS.get() {
-                                        let popup = popup_arc.lock().unwrap_or_else(|e| e.into_inner());
-                                        popup.load_items(false);
-                                        popup.refresh_display();
+                                    if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                                        popup.refresh_items_delta();
                                     }
                                 }));
                             });
@@ -833,32 +1851,34 @@ impl PopupWindow {
                         POLICY_SET.store(true, Ordering::Relaxed);
                     }
 
-                    // Position window near the mouse cursor
-                    let mouse_loc = NSEvent::mouseLocation();
-                    let win_size = window.frame().size;
-                    let cursor_offset = 10.0;
-                    let mut top_left_x = mouse_loc.x + cursor_offset;
-                    let mut top_left_y = mouse_loc.y + cursor_offset;
-                    if let Some(screen) = NSScreen::mainScreen(mtm) {
-                        let sf = screen.visibleFrame();
-                        let smin_x = sf.origin.x;
-                        let smin_y = sf.origin.y;
-                        let smax_x = smin_x + sf.size.width;
-                        let smax_y = smin_y + sf.size.height;
-                        if top_left_x + win_size.width > smax_x {
-                            top_left_x = mouse_loc.x - win_size.width - cursor_offset;
-                        }
-                        if top_left_y > smax_y {
-                            top_left_y = smax_y;
-                        }
-                        if top_left_y - win_size.height < smin_y {
-                            top_left_y = smin_y + win_size.height;
-                        }
-                        if top_left_x < smin_x {
-                            top_left_x = smin_x;
-                        }
+                    let visible_frame = NSScreen::mainScreen(mtm).map(|screen| screen.visibleFrame());
+                    let saved_frame = (*self.saved_frame.borrow())
+                        .zip(visible_frame)
+                        .and_then(|(frame, visible)| clamp_frame_to_screen(frame, visible));
+                    if let Some(frame) = saved_frame {
+                        window.setFrame_display(frame, true);
+                    } else {
+                        let icon_frame = self.icon_frame.borrow().as_ref()
+                            .and_then(|f| f.lock_or_log().and_then(|frame| *frame));
+                        let win_size = window.frame().size;
+                        let (top_left_x, top_left_y) = match placement_anchor(*self.placement.borrow(), icon_frame) {
+                            PlacementAnchor::StatusBarIcon(frame) => {
+                                // Top edge flush with the bottom of the icon
+                                let x = frame.origin.x;
+                                let y = frame.origin.y;
+                                if let Some(screen) = NSScreen::mainScreen(mtm) {
+                                    let sf = screen.visibleFrame();
+                                    let smax_x = sf.origin.x + sf.size.width;
+                                    (x.min(smax_x - win_size.width).max(sf.origin.x), y.min(sf.origin.y + sf.size.height))
+                                } else {
+                                    (x, y)
+                                }
+                            }
+                            PlacementAnchor::Cursor => Self::near_cursor_origin(mtm, win_size),
+                        };
+                        window.setFrameTopLeftPoint(NSPoint::new(top_left_x, top_left_y));
                     }
-                    window.setFrameTopLeftPoint(NSPoint::new(top_left_x, top_left_y));
+                    *self.placed_frame.borrow_mut() = Some(window.frame());
 
                     // Make window visible and bring to front
                     window.makeKeyAndOrderFront(None);
@@ -873,8 +1893,9 @@ impl PopupWindow {
                     #[allow(deprecated)]
                     app.activateIgnoringOtherApps(true);
 
-                    log::info!("Window visible: {}, near cursor ({}, {})",
-                        window.isVisible(), top_left_x, top_left_y);
+                    let frame = window.frame();
+                    log::info!("Window visible: {}, at ({}, {})",
+                        window.isVisible(), frame.origin.x, frame.origin.y + frame.size.height);
                 } else {
                     log::error!("Window is None, cannot show!");
                 }
@@ -885,24 +1906,56 @@ impl PopupWindow {
     }
 
     pub fn hide(&mut self) {
-        if !self.visible {
+        if !*self.visible.borrow() {
             return; // Guard against re-entrancy from windowWillClose
         }
-        self.visible = false;
+        *self.visible.borrow_mut() = false;
         self.auto_refresh_active.store(false, Ordering::Relaxed);
         log::info!("Popup window hidden");
 
         if let Some(window) = self.window.borrow().as_ref() {
+            self.save_frame_if_moved(window);
             window.orderOut(None);
         }
     }
 
+    /// Persist the window frame to the config if the user moved or resized it
+    /// since we placed it
+    fn save_frame_if_moved(&self, window: &NSWindow) {
+        let frame = window.frame();
+        if *self.placed_frame.borrow() == Some(frame) {
+            return;
+        }
+        let saved = frame_to_array(frame);
+        *self.saved_frame.borrow_mut() = Some(saved);
+        *self.placed_frame.borrow_mut() = Some(frame);
+
+        let Some(config_dir) = self.config_dir.borrow().clone() else { return };
+        let mut config = AppConfig::load(&config_dir);
+        config.popup_frame = Some(saved);
+        match config.save(&config_dir) {
+            Ok(()) => log::info!("Saved popup frame {:?}", saved),
+            Err(e) => log::error!("Failed to save popup frame: {}", e),
+        }
+    }
+
+    /// Whether the popup is open. A window that was closed behind our back
+    /// (e.g. without the delegate firing) is reconciled here.
     pub fn is_visible(&self) -> bool {
-        self.visible
+        let window_visible = self.window.borrow().as_ref().map(|window| window.isVisible());
+        if *self.visible.borrow() && window_visible == Some(false) {
+            *self.visible.borrow_mut() = false;
+            self.auto_refresh_active.store(false, Ordering::Relaxed);
+        }
+        *self.visible.borrow()
     }
 
     pub fn move_selection_down(&self) {
         let items_len = self.items.borrow().len();
+        if items_len > 0 && *self.selected_index.borrow() + 1 == items_len && self.has_more_pages() {
+            self.next_page();
+            return;
+        }
         if items_len > 0 {
             {
                 let mut idx = self.selected_index.borrow_mut();
@@ -923,6 +1976,59 @@ impl PopupWindow {
         }
     }
 
+    /// Whether the current tab has items past the pages loaded so far
+    fn has_more_pages(&self) -> bool {
+        self.search_query.borrow().is_empty() && self.items.borrow().len() < *self.total_items.borrow()
+    }
+
+    /// Append the next page of history and select its first item.
+    /// On the last page this just selects the last item.
+    pub fn next_page(&self) {
+        let loaded = self.items.borrow().len();
+        if self.has_more_pages() {
+            self.load_items(loaded, false);
+        }
+        let items_len = self.items.borrow().len();
+        if items_len == 0 {
+            return;
+        }
+        *self.selected_index.borrow_mut() = loaded.min(items_len - 1);
+        self.refresh_display();
+    }
+
+    /// Move the selection back one page (already loaded, so nothing is fetched)
+    pub fn previous_page(&self) {
+        {
+            let mut idx = self.selected_index.borrow_mut();
+            *idx = idx.saturating_sub(PAGE_SIZE);
+        }
+        self.refresh_display();
+    }
+
+    /// Open the popup (if needed) with `id` selected and scrolled into view.
+    /// Used by external triggers such as the IPC `focus` command.
+    pub fn jump_to_item(&mut self, id: ItemId) -> Result<(), String> {
+        if !self.is_visible() {
+            self.show();
+        }
+
+        let mut index = self.items.borrow().iter().position(|item| item.id == id);
+        if index.is_none() {
+            // Not in the current (possibly filtered or stale) list: reload unfiltered
+            *self.search_query.borrow_mut() = String::new();
+            *self.search_cursor.borrow_mut() = 0;
+            *self.item_filter.borrow_mut() = ItemFilter::All;
+            *self.time_filter.borrow_mut() = TimeFilter::AllTime;
+            self.load_items(0, false);
+            index = self.items.borrow().iter().position(|item| item.id == id);
+        }
+
+        let index = index.ok_or_else(|| format!("Item #{} is not in the popup's recent items", id))?;
+        *self.selected_index.borrow_mut() = index;
+        self.refresh_display();
+        Ok(())
+    }
+
     pub fn paste_and_close(&mut self) {
         let idx = *self.selected_index.borrow();
 
@@ -936,37 +2042,12 @@ impl PopupWindow {
             log::info!("📋 Pasting item #{}", item.id);
 
             // Handle poisoned mutex gracefully
-            let db_result = self.db.lock();
-            let db_guard = match db_result {
-                Ok(guard) => Some(guard),
-                Err(poisoned) => {
-                    log::error!("Database mutex poisoned in paste_and_close, recovering...");
-                    Some(poisoned.into_inner())
-                }
-            };
-            if let Some(db) = db_guard {
-                if let Ok(blob) = db.get_blob(item.data_blob_id) {
-                    // Decrypt if needed (handle poisoned encryptor mutex gracefully)
-                    let data = if item.is_encrypted {
-                        let enc_result = self.encryptor.lock();
-                        let enc_guard = match enc_result {
-                            Ok(guard) => Some(guard),
-                            Err(poisoned) => {
-                                log::error!("Encryptor mutex poisoned in paste_and_close, recovering...");
-                                Some(poisoned.into_inner())
-                            }
-                        };
-                        if let Some(enc) = enc_guard {
-                            enc.decrypt(&blob).unwrap_or_else(|e| {
-                                log::error!("Decryption failed: {}", e);
-                                blob.clone()
-                            })
-                        } else {
-                            blob
-                        }
-                    } else {
-                        blob
-                    };
+            if let Some(db) = self.db.lock_or_log() {
+                // Decrypt if needed (handle poisoned encryptor mutex gracefully)
+                let enc = self.encryptor.lock_or_log();
+                let data = db.read_item_data(&item, enc.as_deref());
+                drop(enc);
+                if let Ok(data) = data.map_err(|e| log::error!("Failed to read item #{}: {}", item.id, e)) {
 
                     // Put on pasteboard
                     unsafe {
@@ -976,7 +2057,10 @@ impl PopupWindow {
                         match item.data_type.as_str() {
                             "image" => {
                                 let ns_data = NSData::with_bytes(&data);
-                                let type_str = NSString::from_str("public.png");
+                                let uti = item.metadata.as_deref()
+                                    .map(|m| ItemMetadata::from_json(m).image_pasteboard_type())
+                                    .unwrap_or("public.png");
+                                let type_str = NSString::from_str(uti);
                                 pb.setData_forType(Some(&ns_data), &type_str);
                                 log::info!("✓ Set image on clipboard");
                             }
@@ -995,6 +2079,10 @@ impl PopupWindow {
 
         self.hide();
 
+        if !*self.auto_paste_after_select.borrow() {
+            return;
+        }
+
         // Simulate Cmd+V after a short delay to paste into the previously active app
         std::thread::spawn(|| {
             // Wait for the popup to fully hide and the previous app to regain focus
@@ -1003,15 +2091,63 @@ impl PopupWindow {
         });
     }
 
+    /// Paste the row for a number key (see `quick_paste_index`)
+    pub fn quick_paste(&mut self, digit: char) {
+        let Some(index) = quick_paste_index(digit, self.items.borrow().len()) else {
+            return;
+        };
+        *self.selected_index.borrow_mut() = index;
+        self.paste_and_close();
+    }
+
+    /// Top-left point near the mouse cursor, kept on the visible screen
+    unsafe fn near_cursor_origin(mtm: MainThreadMarker, win_size: NSSize) -> (f64, f64) {
+        let mouse_loc = NSEvent::mouseLocation();
+        let cursor_offset = 10.0;
+        let mut top_left_x = mouse_loc.x + cursor_offset;
+        let mut top_left_y = mouse_loc.y + cursor_offset;
+        if let Some(screen) = NSScreen::mainScreen(mtm) {
+            let sf = screen.visibleFrame();
+            let smin_x = sf.origin.x;
+            let smin_y = sf.origin.y;
+            let smax_x = smin_x + sf.size.width;
+            let smax_y = smin_y + sf.size.height;
+            if top_left_x + win_size.width > smax_x {
+                top_left_x = mouse_loc.x - win_size.width - cursor_offset;
+            }
+            if top_left_y > smax_y {
+                top_left_y = smax_y;
+            }
+            if top_left_y - win_size.height < smin_y {
+                top_left_y = smin_y + win_size.height;
+            }
+            if top_left_x < smin_x {
+                top_left_x = smin_x;
+            }
+        }
+        (top_left_x, top_left_y)
+    }
+
     /// Simulate Cmd+V keystroke via CGEvent API to paste into the active app
     fn simulate_paste() {
+        let Some((key_down, key_up)) = Self::build_paste_events() else {
+            return;
+        };
+        key_down.post(CGEventTapLocation::HID);
+        key_up.post(CGEventTapLocation::HID);
+
+        log::info!("✓ Simulated Cmd+V paste into active app");
+    }
+
+    /// Create the Cmd+V key-down/key-up pair without posting it
+    fn build_paste_events() -> Option<(CGEvent, CGEvent)> {
         const KVK_ANSI_V: u16 = 0x09;
 
         let source = match CGEventSource::new(CGEventSourceStateID::HIDSystemState) {
             Ok(s) => s,
             Err(_) => {
                 log::error!("Failed to create CGEventSource for paste simulation");
-                return;
+                return None;
             }
         };
 
@@ -1019,25 +2155,31 @@ impl PopupWindow {
             Ok(e) => e,
             Err(_) => {
                 log::error!("Failed to create key-down event for paste");
-                return;
+                return None;
             }
         };
         key_down.set_flags(CGEventFlags::CGEventFlagCommand);
-        key_down.post(CGEventTapLocation::HID);
 
         let key_up = match CGEvent::new_keyboard_event(source, KVK_ANSI_V, false) {
             Ok(e) => e,
             Err(_) => {
                 log::error!("Failed to create key-up event for paste");
-                return;
+                return None;
             }
         };
         key_up.set_flags(CGEventFlags::CGEventFlagCommand);
-        key_up.post(CGEventTapLocation::HID);
 
-        log::info!("✓ Simulated Cmd+V paste into active app");
+        Some((key_down, key_up))
+    }
+
+    /// Replace the search query and reload the filtered list, without redrawing
+    pub fn load_items_filtered(&self, query: &str) {
+        *self.search_query.borrow_mut() = query.to_string();
+        *self.search_cursor.borrow_mut() = query.len();
+        self.load_items(0, true);
     }
 
+    /// Insert `c` at the search cursor, a byte offset kept on a char boundary
     pub fn append_search_char(&self, c: char) {
         {
             let mut q = self.search_query.borrow_mut();
@@ -1047,9 +2189,9 @@ impl PopupWindow {
             } else {
                 q.insert(*cur, c);
             }
-            *cur += 1;
+            *cur += c.len_utf8();
         }
-        self.load_items(true);
+        self.load_items(0, true);
         self.refresh_display();
     }
 
@@ -1057,89 +2199,201 @@ impl PopupWindow {
         {
             let mut q = self.search_query.borrow_mut();
             let mut cur = self.search_cursor.borrow_mut();
-            if *cur > 0 && !q.is_empty() {
-                q.remove(*cur - 1);
-                *cur -= 1;
+            if let Some((prev, _)) = q[..*cur].char_indices().next_back() {
+                q.remove(prev);
+                *cur = prev;
             }
         }
-        self.load_items(true);
+        self.load_items(0, true);
         self.refresh_display();
     }
 
     pub fn clear_search(&self) {
         *self.search_query.borrow_mut() = String::new();
         *self.search_cursor.borrow_mut() = 0;
-        *self.type_filter.borrow_mut() = TypeFilter::All;
+        *self.item_filter.borrow_mut() = ItemFilter::All;
         *self.time_filter.borrow_mut() = TimeFilter::AllTime;
-        self.load_items(true);
+        self.load_items(0, true);
         self.refresh_display();
     }
 
     pub fn move_search_cursor_left(&self) {
+        let q = self.search_query.borrow();
         let mut cur = self.search_cursor.borrow_mut();
-        if *cur > 0 {
-            *cur -= 1;
+        if let Some((prev, _)) = q[..*cur].char_indices().next_back() {
+            *cur = prev;
         }
-        drop(cur);
+        drop((q, cur));
         self.refresh_display();
     }
 
     pub fn move_search_cursor_right(&self) {
-        let len = self.search_query.borrow().len();
+        let q = self.search_query.borrow();
         let mut cur = self.search_cursor.borrow_mut();
-        if *cur < len {
-            *cur += 1;
+        if let Some(c) = q[*cur..].chars().next() {
+            *cur += c.len_utf8();
         }
-        drop(cur);
+        drop((q, cur));
         self.refresh_display();
     }
 
     pub fn has_active_search(&self) -> bool {
         !self.search_query.borrow().is_empty()
-            || *self.type_filter.borrow() != TypeFilter::All
+            || *self.item_filter.borrow() != ItemFilter::All
             || *self.time_filter.borrow() != TimeFilter::AllTime
     }
 
-    pub fn cycle_type_filter(&self) {
+    pub fn cycle_item_filter(&self) {
         {
-            let mut f = self.type_filter.borrow_mut();
+            let mut f = self.item_filter.borrow_mut();
             *f = f.next();
         }
-        self.load_items(true);
+        self.load_items(0, true);
+        self.update_tab_buttons();
         self.refresh_display();
     }
 
+    /// Show only items of `filter`'s category, from the first page
+    pub fn set_filter(&self, filter: ItemFilter) {
+        *self.item_filter.borrow_mut() = filter;
+        self.load_items(0, true);
+        self.update_tab_buttons();
+        self.refresh_display();
+    }
+
+    /// Turn on the tab for the current filter. Filters without a tab of their
+    /// own (Email, reached with Tab) leave every tab off.
+    fn update_tab_buttons(&self) {
+        let current = *self.item_filter.borrow();
+        for (button, filter) in self.tab_buttons.borrow().iter().zip(ItemFilter::TABS) {
+            let state = if filter == current { NSControlStateValueOn } else { NSControlStateValueOff };
+            unsafe { button.setState(state) };
+        }
+    }
+
     pub fn cycle_time_filter(&self) {
         {
             let mut f = self.time_filter.borrow_mut();
             *f = f.next();
         }
-        self.load_items(true);
+        self.load_items(0, true);
         self.refresh_display();
     }
 
-    /// Given a character position in the attributed string, return the item index.
-    fn item_index_at_char(&self, char_pos: usize) -> Option<usize> {
-        let starts = self.item_char_starts.borrow();
-        if starts.len() < 2 { return None; }
-        for i in 0..starts.len() - 1 {
-            if char_pos >= starts[i] && char_pos < starts[i + 1] {
-                return Some(i);
-            }
-        }
-        None
+    /// Index of the item drawn in row button `slot`
+    fn item_index_at_row(&self, slot: usize) -> Option<usize> {
+        let index = *self.first_visible_row.borrow() + slot;
+        (index < self.items.borrow().len()).then_some(index)
     }
 
     /// Click handler: select the clicked item and paste it.
-    pub fn click_item_at_char(&mut self, char_pos: usize) -> bool {
-        if let Some(idx) = self.item_index_at_char(char_pos) {
+    pub fn click_row(&mut self, slot: usize) {
+        if let Some(idx) = self.item_index_at_row(slot) {
             *self.selected_index.borrow_mut() = idx;
             self.refresh_display();
             self.paste_and_close();
-            true
+        }
+    }
+
+    /// Right-click handler: select the item in row `slot` without pasting it
+    pub fn select_row(&self, slot: usize) -> Option<ClipboardItem> {
+        let idx = self.item_index_at_row(slot)?;
+        *self.selected_index.borrow_mut() = idx;
+        self.refresh_display();
+        self.items.borrow().get(idx).cloned()
+    }
+
+    /// Context menu for `item` offering to flip its sensitivity or reset the override
+    fn item_menu(item: &ClipboardItem, target: &AnyObject, mtm: MainThreadMarker) -> Retained<NSMenu> {
+        let menu = NSMenu::new(mtm);
+        let add_item = |title: &str, action: Sel, tag: isize| unsafe {
+            let menu_item = NSMenuItem::initWithTitle_action_keyEquivalent(
+                mtm.alloc(), &NSString::from_str(title), Some(action), &NSString::from_str(""),
+            );
+            menu_item.setTag(tag);
+            menu_item.setTarget(Some(target));
+            menu.addItem(&menu_item);
+        };
+
+        let sensitivity = sel!(setSensitivityOverride:);
+        if item.effective_sensitivity() {
+            add_item("Mark as Not Sensitive", sensitivity, SENSITIVITY_TAG_NOT_SENSITIVE);
         } else {
-            false
+            add_item("Mark as Sensitive", sensitivity, SENSITIVITY_TAG_SENSITIVE);
+        }
+        if item.sensitivity_override.is_some() {
+            add_item("Use Automatic Detection", sensitivity, SENSITIVITY_TAG_AUTOMATIC);
+        }
+        menu.addItem(&NSMenuItem::separatorItem(mtm));
+        add_item("Edit Tags…", sel!(editTags:), 0);
+        menu
+    }
+
+    /// Override the selected item's sensitivity (`None` = back to automatic detection)
+    pub fn set_selected_sensitivity(&self, sensitive: Option<bool>) {
+        let idx = *self.selected_index.borrow();
+        let item_id = {
+            let items = self.items.borrow();
+            items.get(idx).map(|i| i.id)
+        };
+        if let Some(id) = item_id {
+            let Some(db) = self.db.lock_or_log() else { return };
+            match db.set_sensitivity_override(id, sensitive) {
+                Ok(()) => log::info!("Set sensitivity of item #{} to {:?}", id, sensitive),
+                Err(e) => log::error!("Failed to set sensitivity: {}", e),
+            }
+            drop(db);
+            self.load_items(0, false);
+            self.refresh_display();
+        }
+    }
+
+    /// Ask for the selected item's tags (comma-separated) and save them. Like
+    /// `prompt_export_selected`, the alert runs after the menu action returns.
+    pub fn prompt_edit_tags(&self) {
+        let Some(item) = self.items.borrow().get(*self.selected_index.borrow()).cloned() else {
+            return;
+        };
+
+        dispatch::Queue::main().exec_async(move || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let Some(mtm) = MainThreadMarker::new() else { return };
+                let tags = unsafe {
+                    let alert = NSAlert::new(mtm);
+                    alert.setMessageText(&NSString::from_str("Edit Tags"));
+                    alert.setInformativeText(&NSString::from_str(
+                        "Separate tags with commas. Search with #tag to find tagged items.",
+                    ));
+                    let field = NSTextField::textFieldWithString(&NSString::from_str(&item.tags.join(", ")), mtm);
+                    field.setFrame(NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(300.0, 24.0)));
+                    alert.setAccessoryView(Some(&field));
+                    alert.addButtonWithTitle(&NSString::from_str("Save"));
+                    alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                    alert.window().setInitialFirstResponder(Some(&field));
+                    if alert.runModal() != NSAlertFirstButtonReturn {
+                        return;
+                    }
+                    field.stringValue().to_string()
+                };
+
+                let tags = parse_tag_list(&tags);
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    popup.set_item_tags(item.id, &tags);
+                }
+            }));
+        });
+    }
+
+    /// Replace an item's tags and redraw
+    fn set_item_tags(&self, item_id: ItemId, tags: &[String]) {
+        let Some(db) = self.db.lock_or_log() else { return };
+        match db.set_tags(item_id, tags) {
+            Ok(()) => log::info!("Tagged item #{} with {:?}", item_id, tags),
+            Err(e) => log::error!("Failed to set tags: {}", e),
         }
+        drop(db);
+        self.load_items(0, false);
+        self.refresh_display();
     }
 
     /// Toggle pin on the selected item
@@ -1150,16 +2404,32 @@ impl PopupWindow {
             items.get(idx).map(|i| i.id)
         };
         if let Some(id) = item_id {
-            let db = match self.db.lock() {
-                Ok(g) => g,
-                Err(p) => p.into_inner(),
-            };
+            let Some(db) = self.db.lock_or_log() else { return };
             match db.toggle_pin(id) {
                 Ok(pinned) => log::info!("{} item #{}", if pinned { "📌 Pinned" } else { "Unpinned" }, id),
                 Err(e) => log::error!("Failed to toggle pin: {}", e),
             }
             drop(db);
-            self.load_items(false);
+            self.load_items(0, false);
+            self.refresh_display();
+        }
+    }
+
+    /// Pin the selected item (no-op if it is already pinned)
+    pub fn pin_selected(&self) {
+        let idx = *self.selected_index.borrow();
+        let item_id = {
+            let items = self.items.borrow();
+            items.get(idx).filter(|i| !i.is_pinned).map(|i| i.id)
+        };
+        if let Some(id) = item_id {
+            let Some(db) = self.db.lock_or_log() else { return };
+            match db.pin_item(id) {
+                Ok(()) => log::info!("📌 Pinned item #{}", id),
+                Err(e) => log::error!("Failed to pin item: {}", e),
+            }
+            drop(db);
+            self.load_items(0, false);
             self.refresh_display();
         }
     }
@@ -1172,34 +2442,765 @@ impl PopupWindow {
             items.get(idx).map(|i| i.id)
         };
         if let Some(id) = item_id {
-            let db = match self.db.lock() {
-                Ok(g) => g,
-                Err(p) => p.into_inner(),
-            };
-            match db.delete_item(id) {
-                Ok(()) => log::info!("🗑️ Deleted item #{}", id),
-                Err(e) => log::error!("Failed to delete item: {}", e),
+            self.delete_item(id);
+        }
+    }
+
+    fn delete_item(&self, id: ItemId) {
+        let Some(db) = self.db.lock_or_log() else { return };
+        match db.delete_item(id) {
+            Ok(()) => log::info!("🗑️ Deleted item #{}", id),
+            Err(e) => log::error!("Failed to delete item: {}", e),
+        }
+        drop(db);
+        self.load_items(0, false);
+        self.refresh_display();
+    }
+
+    /// Ask before moving the selected item to the trash. The alert runs after
+    /// the key handler returns so the popup lock isn't held during its modal loop.
+    pub fn prompt_delete_selected(&self) {
+        let Some(item) = self.items.borrow().get(*self.selected_index.borrow()).cloned() else {
+            return;
+        };
+
+        dispatch::Queue::main().exec_async(move || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let Some(mtm) = MainThreadMarker::new() else { return };
+                let preview: String = item.preview_text.as_deref().unwrap_or("[No preview]")
+                    .chars().take(80).collect();
+                let confirmed = unsafe {
+                    let alert = NSAlert::new(mtm);
+                    alert.setAlertStyle(NSAlertStyle::Warning);
+                    alert.setMessageText(&NSString::from_str("Delete this item?"));
+                    alert.setInformativeText(&NSString::from_str(&preview));
+                    alert.addButtonWithTitle(&NSString::from_str("Delete"));
+                    alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                    alert.runModal() == NSAlertFirstButtonReturn
+                };
+                if confirmed {
+                    if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                        popup.soft_delete_item(item.id);
+                    }
+                }
+            }));
+        });
+    }
+
+    /// Move the selected item to the trash without asking
+    pub fn soft_delete_selected(&self) {
+        if let Some(id) = self.items.borrow().get(*self.selected_index.borrow()).map(|item| item.id) {
+            self.soft_delete_item(id);
+        }
+    }
+
+    /// Move an item to the trash (purged after 7 days) and flash "Deleted" in
+    /// the header for a second
+    fn soft_delete_item(&self, id: ItemId) {
+        let Some(db) = self.db.lock_or_log() else { return };
+        let result = db.soft_delete_item(id);
+        drop(db);
+
+        match result {
+            Ok(()) => {
+                self.load_items(0, false);
+                self.set_status_message_for("🗑️ Deleted".to_string(), DELETED_MESSAGE_DURATION);
             }
-            drop(db);
-            self.load_items(false);
-            self.refresh_display();
+            Err(e) => log::error!("Failed to delete item #{}: {}", id, e),
         }
     }
 
-    /// Format a timestamp as relative time (e.g., "2m ago", "1h ago")
-    fn format_relative_time(timestamp: i64) -> String {
-        let now = chrono::Utc::now().timestamp();
-        let diff = now - timestamp;
-        if diff < 60 {
-            "just now".to_string()
-        } else if diff < 3600 {
-            format!("{}m ago", diff / 60)
-        } else if diff < 86400 {
-            format!("{}h ago", diff / 3600)
-        } else if diff < 7 * 86400 {
-            format!("{}d ago", diff / 86400)
+    /// File extension used when exporting `item`
+    fn export_extension(item: &ClipboardItem) -> &'static str {
+        match item.data_type.as_str() {
+            "image" => {
+                let uti = item.metadata.as_deref()
+                    .map(|m| ItemMetadata::from_json(m).image_pasteboard_type())
+                    .unwrap_or("public.png");
+                if uti == "public.jpeg" { "jpg" } else { "png" }
+            }
+            "rtf" => "rtf",
+            _ => "txt",
+        }
+    }
+
+    /// Suggested name in the save panel, e.g. `clipboard-1700000000.png`
+    fn default_export_filename(item: &ClipboardItem) -> String {
+        format!("clipboard-{}.{}", item.timestamp, Self::export_extension(item))
+    }
+
+    /// Fetch an item's blob, decrypting it if needed
+    fn load_item_data(&self, item: &ClipboardItem) -> Result<Vec<u8>, String> {
+        let db = self.db.lock_or_log().ok_or("Database unavailable")?;
+        let enc = self.encryptor.lock_or_log().ok_or("Encryptor unavailable")?;
+        db.read_item_data(item, Some(&enc))
+            .map_err(|e| format!("Failed to read item #{}: {}", item.id, e))
+    }
+
+    /// Write `item` to `path`, adding the extension for its type if `path` has none.
+    /// Text is always written as UTF-8. Returns the path actually written.
+    pub fn export_item(&self, item: &ClipboardItem, path: &Path) -> Result<PathBuf, String> {
+        let data = self.load_item_data(item)?;
+        let data = match item.data_type.as_str() {
+            "image" | "rtf" => data,
+            _ => String::from_utf8_lossy(&data).into_owned().into_bytes(),
+        };
+
+        let path = if path.extension().is_some() {
+            path.to_path_buf()
         } else {
-            format!("{}w ago", diff / (7 * 86400))
+            path.with_extension(Self::export_extension(item))
+        };
+        std::fs::write(&path, data)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        log::info!("💾 Exported item #{} to {}", item.id, path.display());
+        Ok(path)
+    }
+
+    /// Save the selected item's raw contents to `path`
+    pub fn export_selected_item(&self, path: &Path) -> Result<(), String> {
+        let item = self.items.borrow().get(*self.selected_index.borrow()).cloned()
+            .ok_or("No item selected")?;
+        self.export_item(&item, path).map(|_| ())
+    }
+
+    /// Ask where to save the selected item with an NSSavePanel, then export it.
+    /// The panel runs after the key handler returns so the popup lock isn't held
+    /// during its modal loop.
+    pub fn prompt_export_selected(&self) {
+        let Some(item) = self.items.borrow().get(*self.selected_index.borrow()).cloned() else {
+            return;
+        };
+
+        dispatch::Queue::main().exec_async(move || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let Some(mtm) = MainThreadMarker::new() else { return };
+                let ext = Self::export_extension(&item);
+
+                let path = unsafe {
+                    let panel = NSSavePanel::savePanel(mtm);
+                    panel.setNameFieldStringValue(&NSString::from_str(&Self::default_export_filename(&item)));
+
+                    // Restrict to the item's type (UTType comes from UniformTypeIdentifiers, loaded by AppKit)
+                    if let Some(ut_type_class) = AnyClass::get("UTType") {
+                        let ns_ext = NSString::from_str(ext);
+                        let ut_type: Option<Retained<NSObject>> = msg_send_id![ut_type_class, typeWithFilenameExtension: &*ns_ext];
+                        if let Some(ut_type) = ut_type {
+                            let allowed = NSArray::from_vec(vec![ut_type]);
+                            let _: () = msg_send![&*panel, setAllowedContentTypes: &*allowed];
+                        }
+                    }
+
+                    // NSModalResponseOK
+                    if panel.runModal() != 1 {
+                        return;
+                    }
+                    panel.URL().and_then(|url| url.path()).map(|p| PathBuf::from(p.to_string()))
+                };
+                let Some(path) = path else { return };
+
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    match popup.export_item(&item, &path) {
+                        Ok(written) => {
+                            let name = written.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                            popup.set_status_message(format!("✓ Saved {}", name));
+                        }
+                        Err(e) => log::error!("{}", e),
+                    }
+                }
+            }));
+        });
+    }
+
+    /// Show a transient status line in the popup header for a couple of seconds
+    fn set_status_message(&self, message: String) {
+        self.set_status_message_for(message, STATUS_MESSAGE_DURATION);
+    }
+
+    /// Show a transient status line for `duration`, then redraw without it
+    fn set_status_message_for(&self, message: String, duration: Duration) {
+        *self.status_message.borrow_mut() = Some((message, Instant::now() + duration));
+        self.refresh_display();
+
+        dispatch::Queue::main().exec_after(duration, || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    popup.refresh_display();
+                }
+            }));
+        });
+    }
+
+    fn active_status_message(&self) -> Option<String> {
+        match self.status_message.borrow().as_ref() {
+            Some((message, until)) if Instant::now() < *until => Some(message.clone()),
+            _ => None,
+        }
+    }
+
+    /// Move the selected item into a named clipboard slot
+    pub fn move_item_to_named_slot(&self, slot: &str) -> Result<(), String> {
+        let slot = slot.trim();
+        if slot.is_empty() || slot == "default" {
+            return Err(format!("Invalid slot name: '{}'", slot));
+        }
+
+        let idx = *self.selected_index.borrow();
+        let item_id = {
+            let items = self.items.borrow();
+            items.get(idx).map(|i| i.id)
+        }.ok_or("No item selected")?;
+
+        let db = self.db.lock_or_log().ok_or("Database unavailable")?;
+        db.move_item_to_slot(item_id, slot)
+            .map_err(|e| format!("Failed to move item #{} to slot '{}': {}", item_id, slot, e))?;
+        drop(db);
+
+        log::info!("Moved item #{} to slot '{}'", item_id, slot);
+        self.set_status_message(format!("✓ Moved to slot '{}'", slot));
+        Ok(())
+    }
+
+    /// Ask for a slot name, completing from the slots in use, and move the
+    /// selected item there. Like `prompt_edit_tags`, the alert runs after the
+    /// key handler returns.
+    pub fn prompt_named_slot(&self) {
+        if self.items.borrow().get(*self.selected_index.borrow()).is_none() {
+            return;
+        }
+        let existing = self.db.lock_or_log()
+            .and_then(|db| db.list_slots().ok())
+            .unwrap_or_default();
+
+        dispatch::Queue::main().exec_async(move || {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let Some(mtm) = MainThreadMarker::new() else { return };
+                let slot = unsafe {
+                    let alert = NSAlert::new(mtm);
+                    alert.setMessageText(&NSString::from_str("Move to Slot"));
+                    alert.setInformativeText(&NSString::from_str(
+                        "Type a slot name. Slots already in use complete as you type.",
+                    ));
+                    let field = NSComboBox::new(mtm);
+                    field.setFrame(NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(300.0, 26.0)));
+                    field.setCompletes(true);
+                    for name in &existing {
+                        field.addItemWithObjectValue(&NSString::from_str(name));
+                    }
+                    field.setNumberOfVisibleItems(existing.len().clamp(1, 8) as isize);
+                    alert.setAccessoryView(Some(&field));
+                    alert.addButtonWithTitle(&NSString::from_str("Move"));
+                    alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                    alert.window().setInitialFirstResponder(Some(&field));
+                    if alert.runModal() != NSAlertFirstButtonReturn {
+                        return;
+                    }
+                    field.stringValue().to_string()
+                };
+
+                if let Some(popup) = POPUP_FOR_KEYS.get().and_then(|popup| popup.lock_or_log()) {
+                    if let Err(e) = popup.move_item_to_named_slot(&slot) {
+                        log::error!("{}", e);
+                    }
+                }
+            }));
+        });
+    }
+}
+
+/// How long ago `timestamp` was, for the popup list: "just now", "5 min ago",
+/// "3 hr ago", "yesterday", "4 days ago", then the date from a week on
+fn format_relative(timestamp: i64) -> String {
+    format_relative_at(timestamp, chrono::Utc::now().timestamp())
+}
+
+fn format_relative_at(timestamp: i64, now: i64) -> String {
+    let diff = now - timestamp;
+    if diff < 60 {
+        "just now".to_string()
+    } else if diff < 3600 {
+        format!("{} min ago", diff / 60)
+    } else if diff < 86400 {
+        format!("{} hr ago", diff / 3600)
+    } else if diff < 2 * 86400 {
+        "yesterday".to_string()
+    } else if diff < 7 * 86400 {
+        format!("{} days ago", diff / 86400)
+    } else {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%b %-d, %Y").to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds the events without posting them, so no keystroke reaches other apps.
+    // Headless CI may not provide an event source; that must not panic either.
+    #[test]
+    fn test_build_paste_events_does_not_panic() {
+        if let Some((key_down, key_up)) = PopupWindow::build_paste_events() {
+            assert!(key_down.get_flags().contains(CGEventFlags::CGEventFlagCommand));
+            assert!(key_up.get_flags().contains(CGEventFlags::CGEventFlagCommand));
         }
     }
+
+    #[test]
+    fn test_ideal_height_fits_item_count() {
+        let max = 800.0;
+        assert_eq!(PopupWindow::ideal_height_within(0, max), 200.0);
+        assert_eq!(PopupWindow::ideal_height_within(5, max), 258.0);
+        assert_eq!(PopupWindow::ideal_height_within(20, max), 588.0);
+        assert_eq!(PopupWindow::ideal_height_within(100, max), 588.0);
+        assert_eq!(PopupWindow::ideal_height_within(100, 500.0), 500.0);
+
+        // Never shorter than the minimum, even on a tiny screen
+        assert_eq!(PopupWindow::ideal_height_within(100, 50.0), 200.0);
+        assert!(PopupWindow::ideal_height(0) >= 200.0);
+        assert!(PopupWindow::ideal_height(5) <= PopupWindow::ideal_height(20));
+    }
+
+    #[test]
+    fn test_pinned_section_covers_leading_pinned_items() {
+        let db = Database::new_in_memory().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let ids: Vec<ItemId> = (0..4).map(|i| {
+            let text = format!("item {}", i);
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(now + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap()
+        }).collect();
+        db.pin_item(ids[0]).unwrap();
+        db.pin_item(ids[1]).unwrap();
+
+        let items = db.get_recent_items(10).unwrap();
+        assert_eq!(PopupWindow::pinned_section_len(&items, false), 2);
+        assert_eq!(PopupWindow::pinned_section_len(&items, true), 0);
+        assert_eq!(PopupWindow::pinned_section_len(&items[2..], false), 0);
+    }
+
+    #[test]
+    fn test_near_cursor_placement_uses_mouse_location() {
+        let icon = NSRect::new(NSPoint::new(1200.0, 1050.0), NSSize::new(24.0, 22.0));
+        assert_eq!(placement_anchor(PopupPlacement::NearCursor, Some(icon)), PlacementAnchor::Cursor);
+        assert_eq!(placement_anchor(PopupPlacement::NearCursor, None), PlacementAnchor::Cursor);
+    }
+
+    #[test]
+    fn test_status_bar_placement_needs_icon_frame() {
+        let icon = NSRect::new(NSPoint::new(1200.0, 1050.0), NSSize::new(24.0, 22.0));
+        assert_eq!(
+            placement_anchor(PopupPlacement::BelowStatusBarIcon, Some(icon)),
+            PlacementAnchor::StatusBarIcon(icon)
+        );
+        assert_eq!(placement_anchor(PopupPlacement::BelowStatusBarIcon, None), PlacementAnchor::Cursor);
+    }
+
+    #[test]
+    fn test_toggle_help_twice_returns_to_items() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+
+        assert!(!popup.is_showing_help());
+        popup.toggle_help();
+        assert!(popup.is_showing_help());
+        popup.toggle_help();
+        assert!(!popup.is_showing_help());
+    }
+
+    #[test]
+    fn test_window_close_stops_auto_refresh() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let mut popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+
+        popup.show();
+        popup.auto_refresh_active.store(true, Ordering::Relaxed);
+        assert!(popup.is_visible());
+
+        // What the window delegate does when the red X is clicked
+        popup.on_window_close();
+        assert!(!popup.auto_refresh_active.load(Ordering::Relaxed));
+        assert!(!popup.is_visible());
+
+        // The next toggle opens the popup again instead of "hiding" it
+        popup.toggle();
+        assert!(popup.is_visible());
+    }
+
+    #[test]
+    fn test_content_hash_covers_tags() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let blob_id = db.store_blob(b"tagged").unwrap();
+        db.store_item(chrono::Utc::now().timestamp(), "text", false, false, Some("tagged"), 6, blob_id, None, 1).unwrap();
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        popup.load_items(0, true);
+
+        let before = popup.compute_content_hash();
+        popup.items.borrow_mut()[0].tags = vec!["work".to_string()];
+        let tagged = popup.compute_content_hash();
+        assert_ne!(before, tagged);
+        popup.items.borrow_mut()[0].tags = vec!["wo".to_string(), "rk".to_string()];
+        assert_ne!(popup.compute_content_hash(), tagged);
+    }
+
+    #[test]
+    fn test_content_hash_covers_sensitivity_override() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let blob_id = db.store_blob(b"notes").unwrap();
+        db.store_item(chrono::Utc::now().timestamp(), "text", false, false, Some("notes"), 5, blob_id, None, 1).unwrap();
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        popup.load_items(0, true);
+
+        // Not sensitive either way, but the preview note differs
+        let automatic = popup.compute_content_hash();
+        popup.items.borrow_mut()[0].sensitivity_override = Some(false);
+        assert!(!popup.items.borrow()[0].effective_sensitivity());
+        assert_ne!(popup.compute_content_hash(), automatic);
+    }
+
+    #[test]
+    fn test_jump_to_item_selects_row() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let ids: Vec<ItemId> = (0..3).map(|i| {
+            let text = format!("item {}", i);
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(now + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap()
+        }).collect();
+        let mut popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+
+        // Newest first, so the oldest item is the last row
+        popup.jump_to_item(ids[0]).unwrap();
+        assert!(popup.is_visible());
+        assert_eq!(*popup.selected_index.borrow(), 2);
+
+        popup.jump_to_item(ids[2]).unwrap();
+        assert_eq!(*popup.selected_index.borrow(), 0);
+
+        // Unknown ID: error, selection unchanged
+        assert!(popup.jump_to_item(ItemId(9999)).is_err());
+        assert_eq!(*popup.selected_index.borrow(), 0);
+    }
+
+    #[test]
+    fn test_paging_appends_next_page() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        for i in 0..45 {
+            let text = format!("item {}", i);
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(1_700_000_000 + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap();
+        }
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+
+        popup.load_items(0, true);
+        assert_eq!(popup.items.borrow().len(), PAGE_SIZE);
+        assert_eq!(*popup.total_items.borrow(), 45);
+
+        // Moving down past the last row loads the next page instead of wrapping
+        *popup.selected_index.borrow_mut() = PAGE_SIZE - 1;
+        popup.move_selection_down();
+        assert_eq!(popup.items.borrow().len(), 2 * PAGE_SIZE);
+        assert_eq!(*popup.selected_index.borrow(), PAGE_SIZE);
+        assert_eq!(popup.items.borrow()[PAGE_SIZE].preview_text.as_deref(), Some("item 24"));
+
+        popup.next_page();
+        assert_eq!(popup.items.borrow().len(), 45);
+        assert_eq!(PopupWindow::page_footer(45, 45), "Showing 1–45 of 45");
+
+        // A refresh keeps every loaded page; reopening starts over
+        popup.refresh_items_delta();
+        assert_eq!(popup.items.borrow().len(), 45);
+        popup.previous_page();
+        assert_eq!(*popup.selected_index.borrow(), PAGE_SIZE);
+        popup.load_items(0, true);
+        assert_eq!(popup.items.borrow().len(), PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_visible_rows_follow_selection() {
+        assert_eq!(visible_row_start(0, 5, 45), 0);
+        assert_eq!(visible_row_start(0, PAGE_SIZE, 45), 1);
+        assert_eq!(visible_row_start(10, 3, 45), 3);
+        assert_eq!(visible_row_start(10, 25, 45), 10);
+        // The list shrank: show as many rows as there are
+        assert_eq!(visible_row_start(20, 22, 25), 5);
+        assert_eq!(visible_row_start(20, 0, 3), 0);
+    }
+
+    // 100 Down-arrow presses over a full page. Redrawn rows are those whose
+    // `row_hashes` entry a press changed; clearing the hashes first redraws
+    // every row, as rebuilding the single text view used to.
+    #[test]
+    fn test_move_selection_down_benchmark() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        for i in 0..PAGE_SIZE as i64 {
+            let text = format!("item {}", i);
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(1_700_000_000 + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap();
+        }
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        popup.load_items(0, true);
+        popup.refresh_display();
+
+        let press_down_100 = |redraw_all: bool| {
+            let mut redrawn = 0;
+            let start = Instant::now();
+            for _ in 0..100 {
+                if redraw_all {
+                    popup.row_hashes.borrow_mut().clear();
+                }
+                let before = popup.row_hashes.borrow().clone();
+                popup.move_selection_down();
+                let after = popup.row_hashes.borrow();
+                redrawn += (0..PAGE_SIZE).filter(|&slot| before.get(slot) != after.get(slot)).count();
+            }
+            (redrawn, start.elapsed())
+        };
+
+        let (redrawn_before, duration_before) = press_down_100(true);
+        let (redrawn_after, duration_after) = press_down_100(false);
+        assert_eq!(redrawn_before, 100 * PAGE_SIZE);
+        // The row the selection leaves and the one it enters, wrapping included
+        assert_eq!(redrawn_after, 200);
+        assert!(
+            duration_after.as_millis() < 100,
+            "100 move_selection_down calls took {}ms ({}ms redrawing every row), should be < 100ms",
+            duration_after.as_millis(),
+            duration_before.as_millis()
+        );
+    }
+
+    #[test]
+    fn test_set_filter_shows_only_that_category() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let rows = [
+            ("text", false, "shopping list"),
+            ("image", false, "Image 10x10"),
+            ("url", false, "https://example.com"),
+            ("email", false, "someone@example.com"),
+            ("text", true, "sk-secret"),
+        ];
+        for (i, (data_type, sensitive, preview)) in rows.iter().enumerate() {
+            let blob_id = db.store_blob(preview.as_bytes()).unwrap();
+            db.store_item(1_700_000_000 + i as i64, data_type, *sensitive, *sensitive, Some(preview), preview.len() as i64, blob_id, None, 1).unwrap();
+        }
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        let previews = |popup: &PopupWindow| -> Vec<String> {
+            popup.items.borrow().iter().map(|item| item.preview_text.clone().unwrap()).collect()
+        };
+
+        popup.set_filter(ItemFilter::All);
+        assert_eq!(popup.items.borrow().len(), 5);
+        assert_eq!(*popup.total_items.borrow(), 5);
+
+        popup.set_filter(ItemFilter::Text);
+        assert_eq!(previews(&popup), vec!["sk-secret", "shopping list"]);
+        assert_eq!(*popup.total_items.borrow(), 2);
+
+        popup.set_filter(ItemFilter::Image);
+        assert_eq!(previews(&popup), vec!["Image 10x10"]);
+
+        popup.set_filter(ItemFilter::Url);
+        assert_eq!(previews(&popup), vec!["https://example.com"]);
+
+        popup.set_filter(ItemFilter::Sensitive);
+        assert_eq!(previews(&popup), vec!["sk-secret"]);
+        assert_eq!(*popup.total_items.borrow(), 1);
+
+        assert_eq!(ItemFilter::tab_for_key_code(18), Some(ItemFilter::All));
+        assert_eq!(ItemFilter::tab_for_key_code(23), Some(ItemFilter::Sensitive));
+        assert_eq!(ItemFilter::tab_for_key_code(22), None);
+    }
+
+    #[test]
+    fn test_load_items_filtered() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        for (i, text) in ["grocery list", "meeting notes", "grocery receipt", "café menu"].iter().enumerate() {
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(1_700_000_000 + i as i64, "text", false, false, Some(text), text.len() as i64, blob_id, None, 1).unwrap();
+        }
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        let previews = |popup: &PopupWindow| -> Vec<String> {
+            popup.items.borrow().iter().map(|item| item.preview_text.clone().unwrap()).collect()
+        };
+
+        popup.load_items_filtered("grocery");
+        let mut found = previews(&popup);
+        found.sort();
+        assert_eq!(found, vec!["grocery list", "grocery receipt"]);
+
+        // Same query with a type filter goes through the in-memory fuzzy search
+        *popup.item_filter.borrow_mut() = ItemFilter::Text;
+        popup.load_items_filtered("meeting");
+        assert_eq!(previews(&popup), vec!["meeting notes"]);
+
+        popup.load_items_filtered("no such thing");
+        assert!(popup.items.borrow().is_empty());
+
+        // Typing and backspacing keep the cursor on char boundaries
+        popup.clear_search();
+        for c in "caf\u{e9}".chars() {
+            popup.append_search_char(c);
+        }
+        assert_eq!(previews(&popup), vec!["café menu"]);
+        popup.move_search_cursor_left();
+        popup.move_search_cursor_right();
+        popup.delete_search_char();
+        assert_eq!(popup.search_query.borrow().as_str(), "caf");
+
+        popup.clear_search();
+        assert!(!popup.has_active_search());
+        assert_eq!(popup.items.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_soft_delete_selected_moves_item_to_trash() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let ids: Vec<ItemId> = (0..3).map(|i| {
+            let text = format!("item {}", i);
+            let blob_id = db.store_blob(text.as_bytes()).unwrap();
+            db.store_item(now + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap()
+        }).collect();
+        let db = Arc::new(Mutex::new(db));
+        let popup = PopupWindow::new(Arc::clone(&db), Arc::new(Mutex::new(enc)));
+        popup.load_items(0, true);
+
+        // Newest first: row 1 is the middle item
+        *popup.selected_index.borrow_mut() = 1;
+        popup.soft_delete_selected();
+
+        let listed: Vec<ItemId> = popup.items.borrow().iter().map(|item| item.id).collect();
+        assert_eq!(listed, vec![ids[2], ids[0]]);
+        assert_eq!(popup.active_status_message().as_deref(), Some("🗑️ Deleted"));
+
+        let trash = db.lock().unwrap().get_deleted_items(10).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].original_id, ids[1]);
+    }
+
+    #[test]
+    fn test_export_png_item_writes_png_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(temp_dir.path().join("test.db")).unwrap();
+        let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+
+        let png = crate::storage::DataProcessor::process_image(
+            &{
+                let mut buf = std::io::Cursor::new(Vec::new());
+                image::DynamicImage::new_rgba8(4, 4).write_to(&mut buf, image::ImageFormat::Png).unwrap();
+                buf.into_inner()
+            },
+            "public.png",
+        ).unwrap();
+        let blob_id = db.store_blob(&png.blob).unwrap();
+        db.insert_item(&png, blob_id, 1_700_000_000, 1, false).unwrap();
+
+        let popup = PopupWindow::new(Arc::new(Mutex::new(db)), Arc::new(Mutex::new(enc)));
+        popup.load_items(0, true);
+
+        let item = popup.items.borrow()[0].clone();
+        assert_eq!(PopupWindow::default_export_filename(&item), "clipboard-1700000000.png");
+
+        popup.export_selected_item(&temp_dir.path().join("export")).unwrap();
+        let written = std::fs::read(temp_dir.path().join("export.png")).unwrap();
+        assert!(written.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_keyboard_shortcuts_cover_help_key() {
+        assert!(KEYBOARD_SHORTCUTS.iter().any(|(key, _)| *key == "?"));
+        assert!(KEYBOARD_SHORTCUTS.iter().all(|(key, desc)| !key.is_empty() && !desc.is_empty()));
+    }
+
+    #[test]
+    fn test_sensitivity_menu_tags_map_to_overrides() {
+        assert_eq!(sensitivity_for_tag(SENSITIVITY_TAG_SENSITIVE), Some(true));
+        assert_eq!(sensitivity_for_tag(SENSITIVITY_TAG_NOT_SENSITIVE), Some(false));
+        assert_eq!(sensitivity_for_tag(SENSITIVITY_TAG_AUTOMATIC), None);
+    }
+
+    #[test]
+    fn test_quick_paste_index() {
+        assert_eq!(quick_paste_index('1', 5), Some(0));
+        assert_eq!(quick_paste_index('5', 5), Some(4));
+        assert_eq!(quick_paste_index('9', 20), Some(8));
+        // Beyond the list, zero and non-digits are ignored
+        assert_eq!(quick_paste_index('6', 5), None);
+        assert_eq!(quick_paste_index('1', 0), None);
+        assert_eq!(quick_paste_index('0', 20), None);
+        assert_eq!(quick_paste_index('a', 20), None);
+
+        assert_eq!(quick_paste_digit(0, false), Some(1));
+        assert_eq!(quick_paste_digit(8, false), Some(9));
+        assert_eq!(quick_paste_digit(9, false), None);
+        // Digits go to the query while typing, so no row shows one
+        assert_eq!(quick_paste_digit(0, true), None);
+    }
+
+    #[test]
+    fn test_format_relative_boundaries() {
+        let now = 1_700_000_000;
+        assert_eq!(format_relative_at(now, now), "just now");
+        assert_eq!(format_relative_at(now - 59, now), "just now");
+        assert_eq!(format_relative_at(now - 60, now), "1 min ago");
+        assert_eq!(format_relative_at(now - 3599, now), "59 min ago");
+        assert_eq!(format_relative_at(now - 3600, now), "1 hr ago");
+        assert_eq!(format_relative_at(now - 86399, now), "23 hr ago");
+        assert_eq!(format_relative_at(now - 86400, now), "yesterday");
+        assert_eq!(format_relative_at(now - 2 * 86400, now), "2 days ago");
+        assert_eq!(format_relative_at(now - 7 * 86400 + 1, now), "6 days ago");
+
+        // From a week on, the date (local time zone) instead
+        let week = format_relative_at(now - 7 * 86400, now);
+        assert!(!week.contains("ago"));
+        assert!(week.ends_with(", 2023"), "{}", week);
+    }
+
+    #[test]
+    fn test_saved_frame_is_clamped_to_screen() {
+        let visible = NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(1440.0, 875.0));
+
+        let frame = [100.0, 200.0, 640.0, 480.0];
+        assert_eq!(clamp_frame_to_screen(frame, visible).map(frame_to_array), Some(frame));
+
+        // Hanging off the top-right corner: moved back on screen, size kept
+        let moved = clamp_frame_to_screen([1200.0, 700.0, 640.0, 480.0], visible).unwrap();
+        assert_eq!(frame_to_array(moved), [800.0, 395.0, 640.0, 480.0]);
+
+        // Taller than the screen: shrunk to fit
+        let shrunk = clamp_frame_to_screen([100.0, -50.0, 640.0, 2000.0], visible).unwrap();
+        assert_eq!(frame_to_array(shrunk), [100.0, 0.0, 640.0, 875.0]);
+
+        // Saved on a display to the left that is gone
+        assert_eq!(clamp_frame_to_screen([-1800.0, 200.0, 640.0, 480.0], visible), None);
+    }
+
+    #[test]
+    fn test_parse_tag_list() {
+        assert_eq!(parse_tag_list("Work, #todo,, work , two words"), vec!["work".to_string(), "todo".to_string()]);
+        assert!(parse_tag_list("  ").is_empty());
+    }
 }
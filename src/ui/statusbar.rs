@@ -4,18 +4,24 @@ use objc2::{declare_class, msg_send, msg_send_id, sel, ClassType, DeclaredClass}
 use objc2::mutability::InteriorMutable;
 use objc2::runtime::AnyObject;
 use objc2_app_kit::{
-    NSStatusBar, NSStatusItem, NSMenu, NSMenuItem, NSVariableStatusItemLength,
+    NSFont, NSStatusBar, NSStatusItem, NSMenu, NSMenuItem, NSVariableStatusItemLength,
     NSApplication, NSPasteboard, NSPasteboardTypeString,
     NSAlert, NSAlertStyle, NSAlertFirstButtonReturn,
+    NSOpenPanel, NSSavePanel, NSModalResponseOK, NSTextField, NSColor,
+    NSButton, NSImage, NSBitmapImageRep, NSCellImagePosition, NSScrollView, NSTextView,
 };
-use objc2_foundation::{NSString, NSObject, MainThreadMarker};
+use objc2_foundation::{NSBundle, NSString, NSObject, NSPoint, NSRect, NSRange, NSSize, NSData, NSMutableAttributedString, MainThreadMarker};
+use std::cell::RefCell;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex, OnceLock};
-use crate::storage::{AppConfig, Database, Encryptor};
+use clipboard_manager::LockExt;
+use crate::storage::{AppConfig, Database, DatabaseStatistics, Encryptor, ItemId, StorageBreakdown};
+use crate::storage::config::{write_bootstrap_data_dir, DATA_DIR_ARG, MAX_POLLING_INTERVAL_MS, MIN_POLLING_INTERVAL_MS};
+use crate::storage::processor::ProcessedDataType;
 use crate::storage::license::{LicenseManager, CHECKOUT_URL};
 use crate::ui::popup::PopupWindow;
 use crate::ui::launch_at_login;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 // Global references accessible from ObjC action methods
 static SHARED_POPUP: OnceLock<Arc<Mutex<PopupWindow>>> = OnceLock::new();
@@ -23,7 +29,33 @@ static SHARED_DB: OnceLock<Arc<Mutex<Database>>> = OnceLock::new();
 static SHARED_ENCRYPTOR: OnceLock<Arc<Mutex<Encryptor>>> = OnceLock::new();
 static SHARED_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
 static SHARED_PRO_FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+static SHARED_PAUSED_FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+static SHARED_POLL_INTERVAL: OnceLock<Arc<AtomicU64>> = OnceLock::new();
 static ACTIVATION_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+static SHARED_ICON_FRAME: OnceLock<Arc<Mutex<Option<NSRect>>>> = OnceLock::new();
+
+/// Item counts above this show as "999+" so the menu bar title stays short
+const MAX_BADGE_COUNT: usize = 999;
+
+/// Status bar template icons at 1x and @2x (see resources/generate_statusbar_icons.py)
+const STATUS_ICON_PNG: &[u8] = include_bytes!("../assets/statusbar.png");
+const STATUS_ICON_PNG_2X: &[u8] = include_bytes!("../assets/statusbar@2x.png");
+const PAUSED_ICON_PNG: &[u8] = include_bytes!("../assets/statusbar-paused.png");
+const PAUSED_ICON_PNG_2X: &[u8] = include_bytes!("../assets/statusbar-paused@2x.png");
+
+/// Size in points of the status bar icon
+const STATUS_ICON_SIZE: f64 = 16.0;
+
+/// Width in block characters of the longest bar in the storage chart
+const STORAGE_BAR_WIDTH: usize = 30;
+
+/// Data types offered under "Clear History" (see `clearByType:`)
+const CLEARABLE_TYPES: [&str; 3] = ["image", "url", "text"];
+
+thread_local! {
+    // NSStatusItem is main-thread only, so it can't live in a OnceLock
+    static STATUS_ITEM: RefCell<Option<Retained<NSStatusItem>>> = const { RefCell::new(None) };
+}
 
 declare_class!(
     struct MenuTarget;
@@ -44,10 +76,8 @@ declare_class!(
             log::info!("Show/Hide History menu item clicked");
             let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 if let Some(popup) = SHARED_POPUP.get() {
-                    if let Ok(mut popup) = popup.lock() {
+                    if let Some(mut popup) = popup.lock_or_log() {
                         popup.toggle();
-                    } else {
-                        log::error!("Popup mutex poisoned");
                     }
                 }
             }));
@@ -57,32 +87,16 @@ declare_class!(
         fn paste_item(&self, sender: &AnyObject) {
             unsafe {
                 let menu_item: &NSMenuItem = &*(sender as *const AnyObject as *const NSMenuItem);
-                let item_id = menu_item.tag();
+                let item_id = ItemId::from_tag(menu_item.tag());
                 log::info!("Paste item (id={}) clicked", item_id);
                 if let Some(db_arc) = SHARED_DB.get() {
-                    if let Ok(db) = db_arc.lock() {
+                    if let Some(db) = db_arc.lock_or_log() {
                         if let Ok(items) = db.get_recent_items(100) {
-                            if let Some(item) = items.iter().find(|i| i.id == item_id as i64) {
-                                if let Ok(blob) = db.get_blob(item.data_blob_id) {
-                                    let data = if item.is_encrypted {
-                                        if let Some(enc_arc) = SHARED_ENCRYPTOR.get() {
-                                            if let Ok(enc) = enc_arc.lock() {
-                                                match enc.decrypt(&blob) {
-                                                    Ok(decrypted) => decrypted,
-                                                    Err(e) => {
-                                                        log::error!("Failed to decrypt item: {}", e);
-                                                        return;
-                                                    }
-                                                }
-                                            } else {
-                                                return;
-                                            }
-                                        } else {
-                                            return;
-                                        }
-                                    } else {
-                                        blob
-                                    };
+                            if let Some(item) = items.iter().find(|i| i.id == item_id) {
+                                let enc = SHARED_ENCRYPTOR.get().and_then(|enc_arc| enc_arc.lock_or_log());
+                                let data = db.read_item_data(item, enc.as_deref());
+                                drop(enc);
+                                if let Ok(data) = data.map_err(|e| log::error!("Failed to read item #{}: {}", item.id, e)) {
                                     let pb = NSPasteboard::generalPasteboard();
                                     pb.clearContents();
                                     let text = String::from_utf8_lossy(&data);
@@ -97,6 +111,57 @@ declare_class!(
             }
         }
 
+        #[method(changeDataDirectory:)]
+        fn change_data_directory(&self, _sender: &AnyObject) {
+            log::info!("Change Data Directory clicked");
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let panel = NSOpenPanel::openPanel(mtm);
+                        panel.setCanChooseDirectories(true);
+                        panel.setCanChooseFiles(false);
+                        panel.setCanCreateDirectories(true);
+                        panel.setAllowsMultipleSelection(false);
+                        panel.setMessage(Some(&NSString::from_str(
+                            "Choose where ClipVault stores its history. The app will restart."
+                        )));
+
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        if panel.runModal() != NSModalResponseOK {
+                            return;
+                        }
+                        let Some(chosen) = panel.URL().and_then(|url| url.path()) else {
+                            return;
+                        };
+                        let chosen = PathBuf::from(chosen.to_string());
+
+                        if let Err(e) = write_bootstrap_data_dir(&chosen) {
+                            log::error!("Failed to save data directory choice: {}", e);
+                            return;
+                        }
+
+                        let exe = match std::env::current_exe() {
+                            Ok(exe) => exe,
+                            Err(e) => {
+                                log::error!("Failed to locate executable for restart: {}", e);
+                                return;
+                            }
+                        };
+                        match std::process::Command::new(exe).arg(DATA_DIR_ARG).arg(&chosen).spawn() {
+                            Ok(_) => {
+                                log::info!("Restarting with data directory {}", chosen.display());
+                                NSApplication::sharedApplication(mtm).terminate(None);
+                            }
+                            Err(e) => log::error!("Failed to restart ClipVault: {}", e),
+                        }
+                    }
+                }));
+            });
+        }
+
         #[method(clearHistory:)]
         fn clear_history(&self, _sender: &AnyObject) {
             log::info!("Clear History clicked");
@@ -120,7 +185,7 @@ declare_class!(
                         if response == NSAlertFirstButtonReturn {
                             log::info!("User confirmed clear");
                             if let Some(db_arc) = SHARED_DB.get() {
-                                if let Ok(db) = db_arc.lock() {
+                                if let Some(mut db) = db_arc.lock_or_log() {
                                     match db.soft_delete_all_items() {
                                         Ok(count) => log::info!("Soft-deleted {} items", count),
                                         Err(e) => log::error!("Failed to clear: {}", e),
@@ -133,12 +198,364 @@ declare_class!(
             });
         }
 
+        #[method(clearByType:)]
+        fn clear_by_type(&self, sender: &AnyObject) {
+            // The data type was stored on the menu item when the menu was built
+            let data_type = unsafe {
+                let represented: Option<Retained<NSString>> = msg_send_id![sender, representedObject];
+                represented.map(|s| s.to_string())
+            };
+            let Some(data_type) = data_type else {
+                log::error!("Clear by type: menu item has no data type");
+                return;
+            };
+            log::info!("Clear '{}' items clicked", data_type);
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let label = StatusBarController::type_label(&data_type);
+                        let alert = NSAlert::new(mtm);
+                        alert.setAlertStyle(NSAlertStyle::Warning);
+                        alert.setMessageText(&NSString::from_str(&format!("Clear All {}?", label)));
+                        alert.setInformativeText(&NSString::from_str(&format!(
+                            "This will remove all {} from your clipboard history.",
+                            label.to_lowercase()
+                        )));
+                        alert.addButtonWithTitle(&NSString::from_str(&format!("Clear {}", label)));
+                        alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+
+                        let response = alert.runModal();
+                        if response == NSAlertFirstButtonReturn {
+                            if let Some(db_arc) = SHARED_DB.get() {
+                                if let Some(db) = db_arc.lock_or_log() {
+                                    match db.delete_items_by_type(&data_type) {
+                                        Ok(count) => log::info!("Soft-deleted {} '{}' items", count, data_type),
+                                        Err(e) => log::error!("Failed to clear '{}' items: {}", data_type, e),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }));
+            });
+        }
+
+        #[method(clearBySourceApp:)]
+        fn clear_by_source_app(&self, sender: &AnyObject) {
+            // The bundle ID was stored on the menu item when the menu was built
+            let bundle_id = unsafe {
+                let represented: Option<Retained<NSString>> = msg_send_id![sender, representedObject];
+                represented.map(|s| s.to_string())
+            };
+            let Some(bundle_id) = bundle_id else {
+                log::error!("Clear by app: menu item has no bundle ID");
+                return;
+            };
+            log::info!("Clear items from '{}' clicked", bundle_id);
+            let app_name = StatusBarController::source_app_name(&bundle_id);
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let alert = NSAlert::new(mtm);
+                        alert.setAlertStyle(NSAlertStyle::Warning);
+                        alert.setMessageText(&NSString::from_str(&format!("Clear Items from {}?", app_name)));
+                        alert.setInformativeText(&NSString::from_str(&format!(
+                            "This will remove everything copied from {} from your clipboard history.",
+                            app_name
+                        )));
+                        alert.addButtonWithTitle(&NSString::from_str("Clear Items"));
+                        alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+
+                        let response = alert.runModal();
+                        if response == NSAlertFirstButtonReturn {
+                            if let Some(db_arc) = SHARED_DB.get() {
+                                if let Some(db) = db_arc.lock_or_log() {
+                                    match db.soft_delete_items_by_source_app(&bundle_id) {
+                                        Ok(count) => log::info!("Soft-deleted {} items from '{}'", count, bundle_id),
+                                        Err(e) => log::error!("Failed to clear items from '{}': {}", bundle_id, e),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }));
+            });
+        }
+
+        #[method(vacuumDatabase:)]
+        fn vacuum_database(&self, _sender: &AnyObject) {
+            log::info!("Vacuum Database clicked");
+            std::thread::spawn(|| {
+                let Some(db_arc) = SHARED_DB.get() else { return };
+                let result = {
+                    let Some(db) = db_arc.lock_or_log() else { return };
+                    let before = db.get_db_size().unwrap_or(0);
+                    db.vacuum()
+                        .map(|()| (before, db.get_db_size().unwrap_or(0)))
+                        .map_err(|e| e.to_string())
+                };
+
+                dispatch::Queue::main().exec_async(move || {
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        unsafe {
+                            let mtm = MainThreadMarker::new()
+                                .expect("must be on main thread");
+                            let alert = NSAlert::new(mtm);
+                            match &result {
+                                Ok((before, after)) => {
+                                    alert.setAlertStyle(NSAlertStyle::Informational);
+                                    alert.setMessageText(&NSString::from_str("Database Vacuumed"));
+                                    alert.setInformativeText(&NSString::from_str(&format!(
+                                        "Size before: {} KB\nSize after: {} KB",
+                                        before / 1024, after / 1024
+                                    )));
+                                }
+                                Err(e) => {
+                                    alert.setAlertStyle(NSAlertStyle::Warning);
+                                    alert.setMessageText(&NSString::from_str("Vacuum Failed"));
+                                    alert.setInformativeText(&NSString::from_str(e));
+                                }
+                            }
+                            alert.addButtonWithTitle(&NSString::from_str("OK"));
+                            alert.runModal();
+                        }
+                    }));
+                });
+            });
+        }
+
+        #[method(rotateEncryptionKey:)]
+        fn rotate_encryption_key(&self, _sender: &AnyObject) {
+            log::info!("Rotate Encryption Key clicked");
+            let is_pro = SHARED_PRO_FLAG.get()
+                .map(|f| f.load(Ordering::Relaxed))
+                .unwrap_or(false);
+            if !is_pro {
+                log::warn!("Key rotation requires ClipVault Pro");
+                return;
+            }
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let alert = NSAlert::new(mtm);
+                        alert.setAlertStyle(NSAlertStyle::Warning);
+                        alert.setMessageText(&NSString::from_str("Rotate Encryption Key?"));
+                        alert.setInformativeText(&NSString::from_str(
+                            "A new key will be generated and every encrypted item re-encrypted with it. \
+                             The current key file will stop working."
+                        ));
+                        alert.addButtonWithTitle(&NSString::from_str("Rotate Key"));
+                        alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        if alert.runModal() != NSAlertFirstButtonReturn {
+                            return;
+                        }
+                    }
+
+                    std::thread::spawn(|| {
+                        let result = StatusBarController::rotate_key();
+                        dispatch::Queue::main().exec_async(move || {
+                            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                unsafe {
+                                    let mtm = MainThreadMarker::new()
+                                        .expect("must be on main thread");
+                                    let alert = NSAlert::new(mtm);
+                                    match &result {
+                                        Ok(count) => {
+                                            alert.setAlertStyle(NSAlertStyle::Informational);
+                                            alert.setMessageText(&NSString::from_str("Encryption Key Rotated"));
+                                            alert.setInformativeText(&NSString::from_str(&format!(
+                                                "Re-encrypted {} items with the new key.", count
+                                            )));
+                                        }
+                                        Err(e) => {
+                                            alert.setAlertStyle(NSAlertStyle::Warning);
+                                            alert.setMessageText(&NSString::from_str("Key Rotation Failed"));
+                                            alert.setInformativeText(&NSString::from_str(e));
+                                        }
+                                    }
+                                    alert.addButtonWithTitle(&NSString::from_str("OK"));
+                                    alert.runModal();
+                                }
+                            }));
+                        });
+                    });
+                }));
+            });
+        }
+
+        #[method(exportHistory:)]
+        fn export_history(&self, _sender: &AnyObject) {
+            log::info!("Export History clicked");
+            let is_pro = SHARED_PRO_FLAG.get()
+                .map(|f| f.load(Ordering::Relaxed))
+                .unwrap_or(false);
+            if !is_pro {
+                log::warn!("History export requires ClipVault Pro");
+                return;
+            }
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    let (path, include_blobs) = unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let panel = NSSavePanel::savePanel(mtm);
+                        panel.setNameFieldStringValue(&NSString::from_str(&format!(
+                            "clipvault-history-{}.json", chrono::Local::now().format("%Y-%m-%d")
+                        )));
+                        panel.setMessage(Some(&NSString::from_str(
+                            "Export clipboard history as JSON. Item data is only included if checked \
+                             (sensitive items are decrypted)."
+                        )));
+                        let include = NSButton::checkboxWithTitle_target_action(
+                            &NSString::from_str("Include item data"), None, None, mtm,
+                        );
+                        panel.setAccessoryView(Some(&include));
+
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        if panel.runModal() != NSModalResponseOK {
+                            return;
+                        }
+                        let Some(path) = panel.URL().and_then(|url| url.path()) else {
+                            return;
+                        };
+                        (PathBuf::from(path.to_string()), include.state() == 1) // NSControlStateValueOn = 1
+                    };
+
+                    std::thread::spawn(move || {
+                        let result = StatusBarController::export_history(&path, include_blobs);
+                        dispatch::Queue::main().exec_async(move || {
+                            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                unsafe {
+                                    let mtm = MainThreadMarker::new()
+                                        .expect("must be on main thread");
+                                    let alert = NSAlert::new(mtm);
+                                    match &result {
+                                        Ok(count) => {
+                                            alert.setAlertStyle(NSAlertStyle::Informational);
+                                            alert.setMessageText(&NSString::from_str("History Exported"));
+                                            alert.setInformativeText(&NSString::from_str(&format!(
+                                                "Exported {} items to {}", count, path.display()
+                                            )));
+                                        }
+                                        Err(e) => {
+                                            alert.setAlertStyle(NSAlertStyle::Warning);
+                                            alert.setMessageText(&NSString::from_str("Export Failed"));
+                                            alert.setInformativeText(&NSString::from_str(e));
+                                        }
+                                    }
+                                    alert.addButtonWithTitle(&NSString::from_str("OK"));
+                                    alert.runModal();
+                                }
+                            }));
+                        });
+                    });
+                }));
+            });
+        }
+
+        #[method(showStorageUsage:)]
+        fn show_storage_usage(&self, _sender: &AnyObject) {
+            log::info!("Storage Usage clicked");
+            let Some(db_arc) = SHARED_DB.get() else { return };
+            let breakdown = {
+                let Some(db) = db_arc.lock_or_log() else { return };
+                db.get_storage_breakdown()
+            };
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let alert = NSAlert::new(mtm);
+                        match &breakdown {
+                            Ok(breakdown) => {
+                                alert.setAlertStyle(NSAlertStyle::Informational);
+                                alert.setMessageText(&NSString::from_str("Storage Usage"));
+                                alert.setInformativeText(&NSString::from_str(&format!(
+                                    "Average item: {:.1} KB\nLargest item: {} KB",
+                                    breakdown.avg_blob_bytes / 1024.0, breakdown.largest_blob_bytes / 1024
+                                )));
+                                let chart = StatusBarController::storage_chart(breakdown);
+                                let label = NSTextField::labelWithAttributedString(&chart, mtm);
+                                label.sizeToFit();
+                                alert.setAccessoryView(Some(&label));
+                            }
+                            Err(e) => {
+                                alert.setAlertStyle(NSAlertStyle::Warning);
+                                alert.setMessageText(&NSString::from_str("Could Not Read Storage Usage"));
+                                alert.setInformativeText(&NSString::from_str(&e.to_string()));
+                            }
+                        }
+                        alert.addButtonWithTitle(&NSString::from_str("OK"));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        alert.runModal();
+                    }
+                }));
+            });
+        }
+
+        #[method(showStatistics:)]
+        fn show_statistics(&self, _sender: &AnyObject) {
+            log::info!("Statistics clicked");
+            let Some(db_arc) = SHARED_DB.get() else { return };
+            let stats = {
+                let Some(db) = db_arc.lock_or_log() else { return };
+                db.get_statistics()
+            };
+
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe {
+                        let mtm = MainThreadMarker::new()
+                            .expect("must be on main thread");
+                        let alert = NSAlert::new(mtm);
+                        match &stats {
+                            Ok(stats) => {
+                                alert.setAlertStyle(NSAlertStyle::Informational);
+                                alert.setMessageText(&NSString::from_str("Statistics"));
+                                alert.setInformativeText(&NSString::from_str(
+                                    &StatusBarController::statistics_summary(stats)
+                                ));
+                            }
+                            Err(e) => {
+                                alert.setAlertStyle(NSAlertStyle::Warning);
+                                alert.setMessageText(&NSString::from_str("Could Not Read Statistics"));
+                                alert.setInformativeText(&NSString::from_str(&e.to_string()));
+                            }
+                        }
+                        alert.addButtonWithTitle(&NSString::from_str("OK"));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        alert.runModal();
+                    }
+                }));
+            });
+        }
+
         #[method(toggleLaunchAtLogin:)]
         fn toggle_launch_at_login(&self, _sender: &AnyObject) {
             let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 if let Some(data_dir) = SHARED_DATA_DIR.get() {
                     let mut config = AppConfig::load(data_dir);
-                    config.launch_at_login = !config.launch_at_login;
+                    // Flip what the checkmark shows, not what the config last recorded
+                    config.launch_at_login = !launch_at_login::is_enabled();
 
                     if let Err(e) = launch_at_login::sync(config.launch_at_login) {
                         log::error!("Failed to toggle launch at login: {}", e);
@@ -155,6 +572,187 @@ declare_class!(
             }));
         }
 
+        #[method(toggleAutoPaste:)]
+        fn toggle_auto_paste(&self, _sender: &AnyObject) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if let Some(data_dir) = SHARED_DATA_DIR.get() {
+                    let mut config = AppConfig::load(data_dir);
+                    config.auto_paste_after_select = !config.auto_paste_after_select;
+
+                    if let Err(e) = config.save(data_dir) {
+                        log::error!("Failed to save config: {}", e);
+                        return;
+                    }
+
+                    if let Some(popup) = SHARED_POPUP.get().and_then(|popup| popup.lock_or_log()) {
+                        popup.set_auto_paste_after_select(config.auto_paste_after_select);
+                    }
+
+                    log::info!("Auto-paste after select: {}", if config.auto_paste_after_select { "enabled" } else { "disabled" });
+                }
+            }));
+        }
+
+        #[method(togglePauseMonitoring:)]
+        fn toggle_pause_monitoring(&self, _sender: &AnyObject) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if let Some(flag) = SHARED_PAUSED_FLAG.get() {
+                    // fetch_xor flips the flag and returns the old value
+                    let paused = !flag.fetch_xor(true, Ordering::Relaxed);
+                    StatusBarController::set_paused_icon(paused);
+                    log::info!("Clipboard monitoring {}", if paused { "paused" } else { "resumed" });
+                }
+            }));
+        }
+
+        #[method(setPollingInterval:)]
+        fn set_polling_interval(&self, _sender: &AnyObject) {
+            log::info!("Polling Interval clicked");
+            let Some(data_dir) = SHARED_DATA_DIR.get() else { return };
+            let current = AppConfig::load(data_dir).polling_interval_ms;
+
+            // Run the alert after the menu closes
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    let Some(mtm) = MainThreadMarker::new() else { return };
+                    let input = unsafe {
+                        let alert = NSAlert::new(mtm);
+                        alert.setMessageText(&NSString::from_str("Clipboard Polling Interval"));
+                        alert.setInformativeText(&NSString::from_str(&format!(
+                            "How often ClipVault checks the clipboard, in milliseconds ({}–{}). \
+                             Shorter intervals catch quick copies sooner but use more energy.",
+                            MIN_POLLING_INTERVAL_MS, MAX_POLLING_INTERVAL_MS
+                        )));
+                        let field = NSTextField::textFieldWithString(&NSString::from_str(&current.to_string()), mtm);
+                        field.setFrame(NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(120.0, 24.0)));
+                        alert.setAccessoryView(Some(&field));
+                        alert.addButtonWithTitle(&NSString::from_str("Save"));
+                        alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                        alert.window().setInitialFirstResponder(Some(&field));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        if alert.runModal() != NSAlertFirstButtonReturn {
+                            return;
+                        }
+                        field.stringValue().to_string()
+                    };
+
+                    match StatusBarController::parse_polling_interval(&input) {
+                        Ok(interval_ms) => StatusBarController::apply_polling_interval(interval_ms),
+                        Err(message) => unsafe {
+                            let alert = NSAlert::new(mtm);
+                            alert.setAlertStyle(NSAlertStyle::Warning);
+                            alert.setMessageText(&NSString::from_str("Invalid Polling Interval"));
+                            alert.setInformativeText(&NSString::from_str(&message));
+                            alert.addButtonWithTitle(&NSString::from_str("OK"));
+                            alert.runModal();
+                        },
+                    }
+                }));
+            });
+        }
+
+        #[method(showExcludedApps:)]
+        fn show_excluded_apps(&self, _sender: &AnyObject) {
+            log::info!("Excluded Apps clicked");
+            let Some(data_dir) = SHARED_DATA_DIR.get() else { return };
+            let current = AppConfig::load(data_dir).excluded_bundle_ids.join("\n");
+
+            // Run the alert after the menu closes
+            dispatch::Queue::main().exec_async(move || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    let Some(mtm) = MainThreadMarker::new() else { return };
+                    let input = unsafe {
+                        let alert = NSAlert::new(mtm);
+                        alert.setMessageText(&NSString::from_str("Excluded Apps"));
+                        alert.setInformativeText(&NSString::from_str(
+                            "Nothing you copy while one of these apps is in front is saved. \
+                             Enter one bundle ID per line (e.g. com.googlecode.iterm2), \
+                             or drag apps here from Finder."
+                        ));
+                        let frame = NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(320.0, 140.0));
+                        let scroll_view = NSScrollView::initWithFrame(mtm.alloc(), frame);
+                        scroll_view.setHasVerticalScroller(true);
+                        // Plain text, so an app dropped from Finder comes in as its path
+                        let text_view = NSTextView::initWithFrame(mtm.alloc(), frame);
+                        text_view.setRichText(false);
+                        text_view.setVerticallyResizable(true);
+                        text_view.setFont(Some(&NSFont::monospacedSystemFontOfSize_weight(12.0, 0.0)));
+                        text_view.setString(&NSString::from_str(&current));
+                        scroll_view.setDocumentView(Some(&text_view));
+                        alert.setAccessoryView(Some(&scroll_view));
+                        alert.addButtonWithTitle(&NSString::from_str("Save"));
+                        alert.addButtonWithTitle(&NSString::from_str("Cancel"));
+                        alert.window().setInitialFirstResponder(Some(&text_view));
+                        #[allow(deprecated)]
+                        NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+                        if alert.runModal() != NSAlertFirstButtonReturn {
+                            return;
+                        }
+                        text_view.string().to_string()
+                    };
+
+                    match StatusBarController::parse_excluded_apps(&input, StatusBarController::bundle_id_at_path) {
+                        Ok(bundle_ids) => StatusBarController::apply_excluded_apps(bundle_ids),
+                        Err(message) => unsafe {
+                            let alert = NSAlert::new(mtm);
+                            alert.setAlertStyle(NSAlertStyle::Warning);
+                            alert.setMessageText(&NSString::from_str("Invalid Excluded App"));
+                            alert.setInformativeText(&NSString::from_str(&message));
+                            alert.addButtonWithTitle(&NSString::from_str("OK"));
+                            alert.runModal();
+                        },
+                    }
+                }));
+            });
+        }
+
+        #[method(toggleNotifySensitive:)]
+        fn toggle_notify_sensitive(&self, _sender: &AnyObject) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                if let Some(data_dir) = SHARED_DATA_DIR.get() {
+                    let mut config = AppConfig::load(data_dir);
+                    config.notify_sensitive = !config.notify_sensitive;
+
+                    if let Err(e) = config.save(data_dir) {
+                        log::error!("Failed to save config: {}", e);
+                        return;
+                    }
+
+                    if config.notify_sensitive {
+                        crate::request_notification_permission();
+                    }
+
+                    log::info!("Sensitive item notifications: {}", if config.notify_sensitive { "enabled" } else { "disabled" });
+                }
+            }));
+        }
+
+        #[method(toggleExcludedType:)]
+        fn toggle_excluded_type(&self, sender: &AnyObject) {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let data_type = unsafe {
+                    let represented: Option<Retained<NSString>> = msg_send_id![sender, representedObject];
+                    represented.map(|s| s.to_string())
+                };
+                let (Some(data_type), Some(data_dir)) = (data_type, SHARED_DATA_DIR.get()) else { return };
+
+                let mut config = AppConfig::load(data_dir);
+                if config.should_store(&data_type) {
+                    config.excluded_data_types.push(data_type.clone());
+                } else {
+                    config.excluded_data_types.retain(|t| *t != data_type);
+                }
+
+                if let Err(e) = config.save(data_dir) {
+                    log::error!("Failed to save config: {}", e);
+                    return;
+                }
+
+                log::info!("Save '{}' items: {}", data_type, if config.should_store(&data_type) { "enabled" } else { "disabled" });
+            }));
+        }
+
         #[method(enterLicense:)]
         fn enter_license(&self, _sender: &AnyObject) {
             log::info!("Enter License Key clicked");
@@ -293,6 +891,9 @@ declare_class!(
                 let mtm = MainThreadMarker::new()
                     .expect("menuNeedsUpdate: must be called on main thread");
 
+                StatusBarController::update_icon_frame(mtm);
+                StatusBarController::update_tooltip(mtm);
+                StatusBarController::update_badge();
                 StatusBarController::populate_menu(menu, self, mtm);
             }
         }
@@ -319,13 +920,22 @@ impl StatusBarController {
         encryptor: Arc<Mutex<Encryptor>>,
         data_dir: PathBuf,
         pro_flag: Arc<AtomicBool>,
+        monitoring_paused: Arc<AtomicBool>,
+        poll_interval: Arc<AtomicU64>,
     ) -> Self {
         let _ = SHARED_DB.set(Arc::clone(&db));
         let _ = crate::ui::popup::POPUP_FOR_KEYS.set(Arc::clone(&popup));
+        let icon_frame = Arc::new(Mutex::new(None));
+        if let Some(popup) = popup.lock_or_log() {
+            popup.set_icon_frame_source(Arc::clone(&icon_frame));
+        }
+        let _ = SHARED_ICON_FRAME.set(icon_frame);
         let _ = SHARED_POPUP.set(popup);
         let _ = SHARED_ENCRYPTOR.set(encryptor);
         let _ = SHARED_DATA_DIR.set(data_dir);
         let _ = SHARED_PRO_FLAG.set(pro_flag);
+        let _ = SHARED_PAUSED_FLAG.set(monitoring_paused);
+        let _ = SHARED_POLL_INTERVAL.set(poll_interval);
 
         unsafe {
             let mtm = MainThreadMarker::new().expect("Must be on main thread");
@@ -335,7 +945,7 @@ impl StatusBarController {
             let status_item = status_bar.statusItemWithLength(NSVariableStatusItemLength);
 
             if let Some(button) = status_item.button(mtm) {
-                button.setTitle(&NSString::from_str("📋"));
+                Self::set_icon_from_template_image(&button, STATUS_ICON_PNG, STATUS_ICON_PNG_2X);
             }
 
             let menu = NSMenu::new(mtm);
@@ -347,12 +957,385 @@ impl StatusBarController {
             Self::populate_menu(&menu, &menu_target, mtm);
 
             status_item.setMenu(Some(&menu));
+            STATUS_ITEM.with(|cell| *cell.borrow_mut() = Some(status_item.clone()));
+            Self::update_icon_frame(mtm);
+            Self::update_tooltip(mtm);
+            Self::update_badge();
             log::info!("Status bar icon created");
 
             StatusBarController { status_item, menu_target }
         }
     }
 
+    /// Generate a new encryption key, re-encrypt every encrypted blob with it and
+    /// install it as the live key. The database and encryptor stay locked
+    /// throughout so nothing is sealed under the old key mid-rotation.
+    fn rotate_key() -> Result<usize, String> {
+        let (Some(db_arc), Some(enc_arc), Some(data_dir)) =
+            (SHARED_DB.get(), SHARED_ENCRYPTOR.get(), SHARED_DATA_DIR.get())
+        else {
+            return Err("Storage is unavailable".to_string());
+        };
+        let key_path = data_dir.join("encryption.key");
+        let new_key_path = data_dir.join("encryption.key.new");
+
+        let db = db_arc.lock_or_log().ok_or("Database unavailable")?;
+        let mut enc = enc_arc.lock_or_log().ok_or("Encryptor unavailable")?;
+
+        let new_enc = enc.rotate_key(&new_key_path)?;
+        let count = match db.reencrypt_all_blobs(&enc, &new_enc) {
+            Ok(count) => count,
+            Err(e) => {
+                // Rolled back: the old key still opens everything
+                Encryptor::discard_key(&new_key_path);
+                return Err(format!("Failed to re-encrypt items: {}", e));
+            }
+        };
+
+        // The data is now sealed under the new key, so keep using it even if it can't be installed
+        let installed = new_enc.install_key(&new_key_path, &key_path);
+        *enc = new_enc;
+        installed.map_err(|e| format!("{}\n\nThe new key is at {}", e, new_key_path.display()))?;
+
+        log::info!("🔑 Rotated encryption key ({} items re-encrypted)", count);
+        Ok(count)
+    }
+
+    /// Write the history to `path` (see `Database::export_json_file`), decrypting
+    /// encrypted items when their data is included
+    fn export_history(path: &std::path::Path, include_blobs: bool) -> Result<usize, String> {
+        let Some(db_arc) = SHARED_DB.get() else {
+            return Err("Storage is unavailable".to_string());
+        };
+        let db = db_arc.lock_or_log().ok_or("Database unavailable")?;
+        let enc = SHARED_ENCRYPTOR.get().and_then(|enc| enc.lock_or_log());
+        db.export_json_file(path, include_blobs, enc.as_deref())
+            .map_err(|e| format!("Failed to export history: {}", e))
+    }
+
+    /// Template image from a 1x PNG and its @2x Retina version, or `None` if
+    /// either fails to decode
+    fn template_image(png_bytes: &[u8], retina_png_bytes: &[u8]) -> Option<Retained<NSImage>> {
+        unsafe {
+            let image = NSImage::initWithData(NSImage::alloc(), &NSData::with_bytes(png_bytes))?;
+            let retina = NSBitmapImageRep::imageRepWithData(&NSData::with_bytes(retina_png_bytes))?;
+            let size = NSSize::new(STATUS_ICON_SIZE, STATUS_ICON_SIZE);
+            retina.setSize(size);
+            image.addRepresentation(&retina);
+            image.setSize(size);
+            image.setTemplate(true); // Tinted by AppKit for light and dark menu bars
+            Some(image)
+        }
+    }
+
+    /// Show a template image instead of the emoji title (the emoji stays as a fallback)
+    fn set_icon_from_template_image(button: &NSButton, png_bytes: &[u8], retina_png_bytes: &[u8]) {
+        unsafe {
+            match Self::template_image(png_bytes, retina_png_bytes) {
+                Some(image) => {
+                    button.setImage(Some(&image));
+                    button.setImagePosition(NSCellImagePosition::NSImageLeft);
+                    button.setTitle(&NSString::from_str(""));
+                }
+                None => {
+                    log::warn!("Failed to decode status bar icon, using emoji title");
+                    button.setTitle(&NSString::from_str("📋"));
+                }
+            }
+        }
+    }
+
+    /// Whether clipboard monitoring is currently paused from the menu
+    fn monitoring_paused() -> bool {
+        SHARED_PAUSED_FLAG.get().is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Title of the menu item that flips the pause flag
+    fn pause_menu_title(paused: bool) -> &'static str {
+        if paused { "Resume Monitoring" } else { "Pause Monitoring" }
+    }
+
+    /// Milliseconds typed into the polling interval prompt, within the allowed range
+    fn parse_polling_interval(input: &str) -> Result<u64, String> {
+        let range = MIN_POLLING_INTERVAL_MS..=MAX_POLLING_INTERVAL_MS;
+        match input.trim().trim_end_matches("ms").trim().parse::<u64>() {
+            Ok(ms) if range.contains(&ms) => Ok(ms),
+            _ => Err(format!(
+                "Enter a whole number of milliseconds from {} to {}.",
+                MIN_POLLING_INTERVAL_MS, MAX_POLLING_INTERVAL_MS
+            )),
+        }
+    }
+
+    /// Save the polling interval and hand it to the running monitor
+    fn apply_polling_interval(interval_ms: u64) {
+        let Some(data_dir) = SHARED_DATA_DIR.get() else { return };
+        let mut config = AppConfig::load(data_dir);
+        config.polling_interval_ms = interval_ms;
+        if let Err(e) = config.save(data_dir) {
+            log::error!("Failed to save config: {}", e);
+            return;
+        }
+        if let Some(interval) = SHARED_POLL_INTERVAL.get() {
+            interval.store(interval_ms, Ordering::Relaxed);
+        }
+        log::info!("Clipboard polling interval set to {}ms", interval_ms);
+    }
+
+    /// Bundle ID of the frontmost app when it has items in the history.
+    /// ClipVault itself never qualifies.
+    fn clearable_frontmost_app() -> Option<String> {
+        let bundle_id = crate::clipboard::ClipboardMonitor::frontmost_app().bundle_id?;
+        let own_id = unsafe { NSBundle::mainBundle().bundleIdentifier() }.map(|id| id.to_string());
+        if own_id.as_deref() == Some(bundle_id.as_str()) {
+            return None;
+        }
+        let db = SHARED_DB.get()?.lock_or_log()?;
+        let has_items = db.get_items_from_app(&bundle_id, 1).map(|items| !items.is_empty()).unwrap_or(false);
+        has_items.then_some(bundle_id)
+    }
+
+    /// Name recorded for the app's most recent item, or the bundle ID
+    fn source_app_name(bundle_id: &str) -> String {
+        SHARED_DB.get()
+            .and_then(|db| db.lock_or_log())
+            .and_then(|db| db.get_items_from_app(bundle_id, 1).ok())
+            .and_then(|items| items.into_iter().next())
+            .and_then(|item| item.source_app_name)
+            .unwrap_or_else(|| bundle_id.to_string())
+    }
+
+    /// Bundle IDs from the Excluded Apps editor: one per line, either typed
+    /// or an app path dropped from Finder (resolved with `resolve`).
+    /// Duplicates are dropped, keeping the first spelling.
+    fn parse_excluded_apps(input: &str, resolve: fn(&str) -> Option<String>) -> Result<Vec<String>, String> {
+        let mut bundle_ids: Vec<String> = Vec::new();
+        for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let bundle_id = if line.starts_with('/') {
+                resolve(line).ok_or_else(|| format!("{} is not an app with a bundle ID", line))?
+            } else if line.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+                line.to_string()
+            } else {
+                return Err(format!("\"{}\" is not a bundle ID", line));
+            };
+            if !bundle_ids.iter().any(|id| id.eq_ignore_ascii_case(&bundle_id)) {
+                bundle_ids.push(bundle_id);
+            }
+        }
+        Ok(bundle_ids)
+    }
+
+    /// Bundle ID of the app at `path`, e.g. "/Applications/Safari.app"
+    fn bundle_id_at_path(path: &str) -> Option<String> {
+        unsafe {
+            let bundle = NSBundle::bundleWithPath(&NSString::from_str(path))?;
+            bundle.bundleIdentifier().map(|id| id.to_string())
+        }
+    }
+
+    /// Save the excluded apps; the capture loop picks them up from its next
+    /// copy, since saving bumps `AppConfig::save_generation`
+    fn apply_excluded_apps(bundle_ids: Vec<String>) {
+        let Some(data_dir) = SHARED_DATA_DIR.get() else { return };
+        let mut config = AppConfig::load(data_dir);
+        config.excluded_bundle_ids = bundle_ids;
+        if let Err(e) = config.save(data_dir) {
+            log::error!("Failed to save config: {}", e);
+            return;
+        }
+        log::info!("Excluded apps: {:?}", config.excluded_bundle_ids);
+    }
+
+    /// Swap the status bar icon between the recording and paused images
+    pub fn set_paused_icon(paused: bool) {
+        let Some(mtm) = MainThreadMarker::new() else { return };
+        let (png, retina_png) = if paused {
+            (PAUSED_ICON_PNG, PAUSED_ICON_PNG_2X)
+        } else {
+            (STATUS_ICON_PNG, STATUS_ICON_PNG_2X)
+        };
+        STATUS_ITEM.with(|cell| unsafe {
+            if let Some(button) = cell.borrow().as_ref().and_then(|item| item.button(mtm)) {
+                Self::set_icon_from_template_image(&button, png, retina_png);
+            }
+        });
+        Self::update_badge();
+    }
+
+    /// Record the status bar icon's screen frame for popup placement
+    fn update_icon_frame(mtm: MainThreadMarker) {
+        let frame = STATUS_ITEM.with(|cell| unsafe {
+            let item = cell.borrow();
+            let button = item.as_ref()?.button(mtm)?;
+            button.window().map(|window| window.frame())
+        });
+        if let (Some(frame), Some(shared)) = (frame, SHARED_ICON_FRAME.get()) {
+            if let Some(mut icon_frame) = shared.lock_or_log() {
+                *icon_frame = Some(frame);
+            }
+        }
+    }
+
+    /// Show item and sensitive-item counts when hovering the icon
+    fn update_tooltip(mtm: MainThreadMarker) {
+        let Some(db) = SHARED_DB.get() else { return };
+        let stats = {
+            let Some(db) = db.lock_or_log() else { return };
+            match db.get_statistics() {
+                Ok(stats) => stats,
+                Err(e) => {
+                    log::error!("Failed to read statistics for tooltip: {}", e);
+                    return;
+                }
+            }
+        };
+        let mut tooltip = format!("ClipVault — {} items ({} sensitive)", stats.total_items, stats.sensitive_items);
+        if Self::monitoring_paused() {
+            tooltip.push_str(" — paused");
+        }
+
+        STATUS_ITEM.with(|cell| unsafe {
+            if let Some(button) = cell.borrow().as_ref().and_then(|item| item.button(mtm)) {
+                button.setToolTip(Some(&NSString::from_str(&tooltip)));
+            }
+        });
+    }
+
+    /// Menu bar title for `count` items next to the icon: empty for 0, capped
+    /// at "999+". Without an icon image the title carries the emoji instead.
+    fn badge_title(count: usize, has_icon_image: bool) -> String {
+        let icon = if has_icon_image { "" } else { "📋" };
+        match count {
+            0 => icon.to_string(),
+            n if n > MAX_BADGE_COUNT => format!("{} {}+", icon, MAX_BADGE_COUNT).trim_start().to_string(),
+            n => format!("{} {}", icon, n).trim_start().to_string(),
+        }
+    }
+
+    /// Show `n` next to the menu bar icon (0 shows the icon alone), in a 12pt system font
+    pub fn set_badge_count(n: usize) {
+        let Some(mtm) = MainThreadMarker::new() else { return };
+
+        STATUS_ITEM.with(|cell| unsafe {
+            let Some(button) = cell.borrow().as_ref().and_then(|item| item.button(mtm)) else { return };
+            let title = NSString::from_str(&Self::badge_title(n, button.image().is_some()));
+            let attributed = NSMutableAttributedString::initWithString(NSMutableAttributedString::alloc(), &title);
+            let font = NSFont::systemFontOfSize(12.0);
+            let font_key = NSString::from_str("NSFont");
+            let range = NSRange::new(0, title.length());
+            let _: () = msg_send![&attributed, addAttribute: &*font_key, value: &*font, range: range];
+            button.setAttributedTitle(&attributed);
+        });
+    }
+
+    /// Refresh the badge from the item count, or clear it when the badge is disabled
+    pub fn update_badge() {
+        let show_count = SHARED_DATA_DIR.get()
+            .map(|dir| AppConfig::load(dir).show_item_count_badge)
+            .unwrap_or(false);
+        if !show_count {
+            Self::set_badge_count(0);
+            return;
+        }
+
+        let Some(db) = SHARED_DB.get() else { return };
+        let Some(count) = db.lock_or_log().map(|db| db.count_items()) else { return };
+        match count {
+            Ok(count) => Self::set_badge_count(count.max(0) as usize),
+            Err(e) => log::error!("Failed to count items for badge: {}", e),
+        }
+    }
+
+    /// Body of the Statistics alert: one line per type (most items first),
+    /// then totals and the date range covered
+    fn statistics_summary(stats: &DatabaseStatistics) -> String {
+        let mut types: Vec<(&String, &i64)> = stats.items_by_type.iter().collect();
+        types.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut lines: Vec<String> = types.iter()
+            .map(|(data_type, count)| {
+                let bytes = stats.bytes_by_type.get(*data_type).copied().unwrap_or(0);
+                format!("{}: {} items, {} KB", data_type, count, bytes / 1024)
+            })
+            .collect();
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("Total: {} items, {} KB on disk", stats.total_items, stats.total_blob_bytes / 1024));
+        lines.push(format!(
+            "Sensitive: {} ({:.0}%), encrypted: {}",
+            stats.sensitive_items, stats.sensitive_fraction * 100.0, stats.encrypted_items
+        ));
+
+        let date = |timestamp: i64| {
+            chrono::DateTime::from_timestamp(timestamp, 0)
+                .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%b %-d, %Y").to_string())
+                .unwrap_or_default()
+        };
+        if let (Some(oldest), Some(newest)) = (stats.oldest_timestamp, stats.newest_timestamp) {
+            lines.push(format!("From {} to {}", date(oldest), date(newest)));
+        }
+        lines.join("\n")
+    }
+
+    /// One chart row per storage bucket: label, bar of `\u{2588}` blocks scaled
+    /// to the largest bucket, and a size caption
+    fn storage_chart_rows(breakdown: &StorageBreakdown) -> Vec<(String, String, String)> {
+        let buckets = [
+            ("Live", breakdown.live_items_bytes, breakdown.blob_count),
+            ("Deleted", breakdown.deleted_items_bytes, breakdown.deleted_blob_count),
+        ];
+        let max_bytes = buckets.iter().map(|(_, bytes, _)| *bytes).max().unwrap_or(0).max(1);
+        buckets.iter()
+            .map(|(label, bytes, count)| {
+                let mut width = (*bytes as f64 / max_bytes as f64 * STORAGE_BAR_WIDTH as f64).round() as usize;
+                // Keep non-empty buckets visible next to a much larger one
+                if *bytes > 0 {
+                    width = width.max(1);
+                }
+                (
+                    format!("{:<8}", label),
+                    "\u{2588}".repeat(width),
+                    format!(" {} KB ({} items)", bytes / 1024, count),
+                )
+            })
+            .collect()
+    }
+
+    /// Monospaced bar chart of `storage_chart_rows`, bars in the accent color
+    unsafe fn storage_chart(breakdown: &StorageBreakdown) -> Retained<NSMutableAttributedString> {
+        let rows = Self::storage_chart_rows(breakdown);
+        let text: String = rows.iter()
+            .map(|(label, bar, caption)| format!("{}{}{}", label, bar, caption))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ns_text = NSString::from_str(&text);
+        let chart = NSMutableAttributedString::initWithString(NSMutableAttributedString::alloc(), &ns_text);
+
+        let font = NSFont::monospacedSystemFontOfSize_weight(12.0, 0.0);
+        let font_key = NSString::from_str("NSFont");
+        let fg_key = NSString::from_str("NSColor");
+        let _: () = msg_send![&chart, addAttribute: &*font_key, value: &*font, range: NSRange::new(0, ns_text.length())];
+
+        let bar_color = NSColor::controlAccentColor();
+        let mut offset = 0;
+        for (label, bar, caption) in &rows {
+            let label_len = label.encode_utf16().count();
+            let bar_len = bar.encode_utf16().count();
+            let _: () = msg_send![&chart, addAttribute: &*fg_key, value: &*bar_color,
+                                  range: NSRange::new(offset + label_len, bar_len)];
+            offset += label_len + bar_len + caption.encode_utf16().count() + 1;
+        }
+        chart
+    }
+
+    /// Check the "Launch at Login" item when the LaunchAgent really is installed,
+    /// regardless of what the config file says
+    unsafe fn update_launch_at_login_checkmark(item: &NSMenuItem) {
+        let state: isize = if launch_at_login::is_enabled() { 1 } else { 0 }; // NSOnState / NSOffState
+        let _: () = msg_send![item, setState: state];
+    }
+
     /// Populate (or repopulate) the given menu with all standard items.
     unsafe fn populate_menu(
         menu: &NSMenu,
@@ -361,7 +1344,7 @@ impl StatusBarController {
     ) {
         // Toggle history window
         let history_label = if let Some(popup_arc) = SHARED_POPUP.get() {
-            if let Ok(popup) = popup_arc.lock() {
+            if let Some(popup) = popup_arc.lock_or_log() {
                 if popup.is_visible() { "Hide History Window" } else { "Show All History" }
             } else {
                 "Show All History"
@@ -370,11 +1353,16 @@ impl StatusBarController {
             "Show All History"
         };
         Self::add_action_item(menu, history_label, Some("h"), sel!(showHistory:), target, mtm);
+        let paused = Self::monitoring_paused();
+        Self::add_action_item(menu, Self::pause_menu_title(paused), None, sel!(togglePauseMonitoring:), target, mtm);
+        if paused {
+            Self::add_disabled_item(menu, "⏸ Clipboard capture paused", mtm);
+        }
         Self::add_separator(menu, mtm);
 
         // Recent clipboard items
         if let Some(db_arc) = SHARED_DB.get() {
-            if let Ok(db) = db_arc.lock() {
+            if let Some(db) = db_arc.lock_or_log() {
                 match db.get_recent_items(10) {
                     Ok(items) if items.is_empty() => {
                         Self::add_disabled_item(menu, "(No clipboard history yet)", mtm);
@@ -386,6 +1374,9 @@ impl StatusBarController {
                                     let icon = match item.data_type.as_str() {
                                         "image" => "🖼️ ",
                                         "url" => "🔗 ",
+                                        "credential" => "🔑 ",
+                                        "certificate" => "📜 ",
+                                        "email" => "✉️ ",
                                         _ => "📝 ",
                                     };
                                     let short = if preview.chars().count() > 50 {
@@ -393,7 +1384,7 @@ impl StatusBarController {
                                     } else {
                                         preview.clone()
                                     };
-                                    let lock = if item.is_sensitive { " 🔒" } else { "" };
+                                    let lock = if item.is_encrypted { " 🔒" } else { "" };
                                     let count = if item.copy_count > 1 {
                                         format!(" (×{})", item.copy_count)
                                     } else {
@@ -405,6 +1396,9 @@ impl StatusBarController {
                                     let icon = match item.data_type.as_str() {
                                         "image" => "🖼️ ",
                                         "url" => "🔗 ",
+                                        "credential" => "🔑 ",
+                                        "certificate" => "📜 ",
+                                        "email" => "✉️ ",
                                         _ => "📝 ",
                                     };
                                     format!("{}{} item", icon, item.data_type)
@@ -418,7 +1412,7 @@ impl StatusBarController {
                             );
                             mi.setEnabled(true);
                             mi.setTarget(Some(target));
-                            mi.setTag(item.id as isize);
+                            mi.setTag(item.id.as_tag());
                             menu.addItem(&mi);
 
                             if i == 4 && items.len() > 5 {
@@ -434,14 +1428,59 @@ impl StatusBarController {
         }
 
         Self::add_separator(menu, mtm);
-        Self::add_action_item(menu, "Clear History", None, sel!(clearHistory:), target, mtm);
-        Self::add_separator(menu, mtm);
+        let clear = Self::add_submenu(menu, "Clear History", mtm);
+        Self::add_action_item(&clear, "Clear All History", None, sel!(clearHistory:), target, mtm);
+        Self::add_separator(&clear, mtm);
+        for data_type in CLEARABLE_TYPES {
+            let title = format!("Clear {}", Self::type_label(data_type));
+            let item = Self::add_action_item(&clear, &title, None, sel!(clearByType:), target, mtm);
+            let type_ns = NSString::from_str(data_type);
+            let represented: &AnyObject = &type_ns;
+            item.setRepresentedObject(Some(represented));
+        }
 
-        // Launch at Login toggle (with checkmark for current state)
-        let launch_enabled = SHARED_DATA_DIR.get()
-            .map(|dir| AppConfig::load(dir).launch_at_login)
+        // The app in front while the menu is open, if it has anything in the history
+        if let Some(bundle_id) = Self::clearable_frontmost_app() {
+            let title = format!("Clear Items from {}…", Self::source_app_name(&bundle_id));
+            let item = Self::add_action_item(menu, &title, None, sel!(clearBySourceApp:), target, mtm);
+            let bundle_ns = NSString::from_str(&bundle_id);
+            let represented: &AnyObject = &bundle_ns;
+            item.setRepresentedObject(Some(represented));
+        }
+
+        // Trash summary; disabled rather than opening an empty submenu
+        let (deleted_count, deleted_size) = SHARED_DB.get()
+            .and_then(|db| db.lock_or_log())
+            .and_then(|db| Some((db.get_deleted_item_count().ok()?, db.get_total_deleted_size().ok()?)))
+            .unwrap_or((0, 0));
+        let deleted_title = format!("Recently Deleted ({})", deleted_count);
+        let deleted = Self::add_submenu(menu, &deleted_title, mtm);
+        deleted.setTitle(&NSString::from_str(&deleted_title));
+        if deleted_count == 0 {
+            if let Some(parent) = menu.itemAtIndex(menu.numberOfItems() - 1) {
+                parent.setEnabled(false);
+            }
+        } else {
+            let summary = format!("{} items, {} KB (purged after 7 days)", deleted_count, deleted_size / 1024);
+            Self::add_disabled_item(&deleted, &summary, mtm);
+        }
+
+        let is_pro = SHARED_PRO_FLAG.get()
+            .map(|f| f.load(Ordering::Relaxed))
             .unwrap_or(false);
 
+        let maintenance = Self::add_submenu(menu, "Maintenance", mtm);
+        Self::add_action_item(&maintenance, "Vacuum Database", None, sel!(vacuumDatabase:), target, mtm);
+        Self::add_action_item(&maintenance, "Change Data Directory…", None, sel!(changeDataDirectory:), target, mtm);
+        Self::add_action_item(&maintenance, "Storage Usage…", None, sel!(showStorageUsage:), target, mtm);
+        Self::add_action_item(&maintenance, "Statistics…", None, sel!(showStatistics:), target, mtm);
+        if is_pro {
+            Self::add_action_item(&maintenance, "Export History…", None, sel!(exportHistory:), target, mtm);
+            Self::add_action_item(&maintenance, "Rotate Encryption Key…", None, sel!(rotateEncryptionKey:), target, mtm);
+        }
+        Self::add_separator(menu, mtm);
+
+        // Launch at Login toggle (with checkmark for current state)
         let login_title = NSString::from_str("Launch at Login");
         let login_key = NSString::from_str("");
         let login_item = NSMenuItem::initWithTitle_action_keyEquivalent(
@@ -449,17 +1488,48 @@ impl StatusBarController {
         );
         login_item.setEnabled(true);
         login_item.setTarget(Some(target));
-        if launch_enabled {
-            let _: () = msg_send![&login_item, setState: 1_isize]; // NSOnState = 1
-        }
+        Self::update_launch_at_login_checkmark(&login_item);
         menu.addItem(&login_item);
-        Self::add_separator(menu, mtm);
 
-        // License status
-        let is_pro = SHARED_PRO_FLAG.get()
-            .map(|f| f.load(Ordering::Relaxed))
+        let auto_paste_enabled = SHARED_DATA_DIR.get()
+            .map(|dir| AppConfig::load(dir).auto_paste_after_select)
             .unwrap_or(false);
 
+        let auto_paste_title = NSString::from_str("Paste After Selecting");
+        let auto_paste_item = NSMenuItem::initWithTitle_action_keyEquivalent(
+            mtm.alloc(), &auto_paste_title, Some(sel!(toggleAutoPaste:)), &login_key,
+        );
+        auto_paste_item.setEnabled(true);
+        auto_paste_item.setTarget(Some(target));
+        if auto_paste_enabled {
+            let _: () = msg_send![&auto_paste_item, setState: 1_isize]; // NSOnState = 1
+        }
+        menu.addItem(&auto_paste_item);
+
+        let config = SHARED_DATA_DIR.get().map(AppConfig::load).unwrap_or_default();
+        let notify_item = Self::add_action_item(menu, "Notify on Sensitive Items", None, sel!(toggleNotifySensitive:), target, mtm);
+        if config.notify_sensitive {
+            let _: () = msg_send![&notify_item, setState: 1_isize]; // NSOnState = 1
+        }
+        let polling_title = format!("Polling Interval ({} ms)…", config.polling_interval_ms);
+        Self::add_action_item(menu, &polling_title, None, sel!(setPollingInterval:), target, mtm);
+        Self::add_action_item(menu, "Excluded Apps…", None, sel!(showExcludedApps:), target, mtm);
+
+        // Which data types are saved (checked = saved)
+        let saved_types = Self::add_submenu(menu, "Save to History", mtm);
+        for data_type in ProcessedDataType::ALL {
+            let data_type = data_type.as_str();
+            let item = Self::add_action_item(&saved_types, Self::type_label(data_type), None, sel!(toggleExcludedType:), target, mtm);
+            let type_ns = NSString::from_str(data_type);
+            let represented: &AnyObject = &type_ns;
+            item.setRepresentedObject(Some(represented));
+            if config.should_store(data_type) {
+                let _: () = msg_send![&item, setState: 1_isize]; // NSOnState = 1
+            }
+        }
+        Self::add_separator(menu, mtm);
+
+        // License status
         if is_pro {
             Self::add_disabled_item(menu, "ClipVault Pro ✓", mtm);
             Self::add_action_item(menu, "Deactivate License", None, sel!(deactivateLicense:), target, mtm);
@@ -492,7 +1562,7 @@ impl StatusBarController {
         action: objc2::runtime::Sel,
         target: &MenuTarget,
         mtm: MainThreadMarker,
-    ) {
+    ) -> Retained<NSMenuItem> {
         let title_ns = NSString::from_str(title);
         let key_ns = NSString::from_str(key_equiv.unwrap_or(""));
         let item = NSMenuItem::initWithTitle_action_keyEquivalent(
@@ -501,6 +1571,23 @@ impl StatusBarController {
         item.setEnabled(true);
         item.setTarget(Some(target));
         menu.addItem(&item);
+        item
+    }
+
+    /// Plural menu/alert label for a data type ("Images", "URLs", "Text")
+    fn type_label(data_type: &str) -> &'static str {
+        match data_type {
+            "image" => "Images",
+            "url" => "URLs",
+            "text" => "Text",
+            "rtf" => "Rich Text",
+            "html" => "HTML",
+            "file" => "Files",
+            "credential" => "Credentials",
+            "certificate" => "Certificates",
+            "email" => "Emails",
+            _ => "Items",
+        }
     }
 
     unsafe fn add_disabled_item(menu: &NSMenu, title: &str, mtm: MainThreadMarker) {
@@ -513,7 +1600,129 @@ impl StatusBarController {
         menu.addItem(&item);
     }
 
+    /// Append an item that opens a submenu, returning the (empty) submenu
+    unsafe fn add_submenu(menu: &NSMenu, title: &str, mtm: MainThreadMarker) -> Retained<NSMenu> {
+        let title_ns = NSString::from_str(title);
+        let key_ns = NSString::from_str("");
+        let item = NSMenuItem::initWithTitle_action_keyEquivalent(
+            mtm.alloc(), &title_ns, None, &key_ns,
+        );
+        let submenu = NSMenu::new(mtm);
+        submenu.setAutoenablesItems(false);
+        item.setSubmenu(Some(&submenu));
+        menu.addItem(&item);
+        submenu
+    }
+
     unsafe fn add_separator(menu: &NSMenu, mtm: MainThreadMarker) {
         menu.addItem(&NSMenuItem::separatorItem(mtm));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_polling_interval() {
+        assert_eq!(StatusBarController::parse_polling_interval("250"), Ok(250));
+        assert_eq!(StatusBarController::parse_polling_interval(" 1000 ms "), Ok(1000));
+        assert_eq!(StatusBarController::parse_polling_interval("100"), Ok(100));
+        assert_eq!(StatusBarController::parse_polling_interval("5000"), Ok(5000));
+        assert!(StatusBarController::parse_polling_interval("99").is_err());
+        assert!(StatusBarController::parse_polling_interval("5001").is_err());
+        assert!(StatusBarController::parse_polling_interval("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_excluded_apps() {
+        fn resolve(path: &str) -> Option<String> {
+            (path == "/Applications/iTerm.app").then(|| "com.googlecode.iterm2".to_string())
+        }
+        let input = "com.1password.1password\n\n  /Applications/iTerm.app \nCOM.1PASSWORD.1PASSWORD\n";
+        assert_eq!(
+            StatusBarController::parse_excluded_apps(input, resolve),
+            Ok(vec!["com.1password.1password".to_string(), "com.googlecode.iterm2".to_string()])
+        );
+        assert_eq!(StatusBarController::parse_excluded_apps("", resolve), Ok(Vec::new()));
+        assert!(StatusBarController::parse_excluded_apps("/Applications/Notes.txt", resolve).is_err());
+        assert!(StatusBarController::parse_excluded_apps("not a bundle id", resolve).is_err());
+    }
+
+    #[test]
+    fn test_pause_menu_title_follows_flag() {
+        let flag = Arc::new(AtomicBool::new(false));
+        assert_eq!(StatusBarController::pause_menu_title(flag.load(Ordering::Relaxed)), "Pause Monitoring");
+
+        // Same flip the menu action does
+        let was_paused = flag.fetch_xor(true, Ordering::Relaxed);
+        assert!(!was_paused);
+        assert_eq!(StatusBarController::pause_menu_title(flag.load(Ordering::Relaxed)), "Resume Monitoring");
+
+        flag.fetch_xor(true, Ordering::Relaxed);
+        assert_eq!(StatusBarController::pause_menu_title(flag.load(Ordering::Relaxed)), "Pause Monitoring");
+    }
+
+    #[test]
+    fn test_badge_title() {
+        assert_eq!(StatusBarController::badge_title(0, false), "📋");
+        assert_eq!(StatusBarController::badge_title(1, false), "📋 1");
+        assert_eq!(StatusBarController::badge_title(999, false), "📋 999");
+        assert_eq!(StatusBarController::badge_title(1000, false), "📋 999+");
+
+        assert_eq!(StatusBarController::badge_title(0, true), "");
+        assert_eq!(StatusBarController::badge_title(42, true), "42");
+        assert_eq!(StatusBarController::badge_title(1000, true), "999+");
+    }
+
+    #[test]
+    fn test_embedded_icons_decode() {
+        for (png, retina_png) in [(STATUS_ICON_PNG, STATUS_ICON_PNG_2X), (PAUSED_ICON_PNG, PAUSED_ICON_PNG_2X)] {
+            let image = StatusBarController::template_image(png, retina_png).expect("icon decodes");
+            assert!(unsafe { image.isTemplate() });
+        }
+    }
+
+    #[test]
+    fn test_statistics_summary_lists_types_by_count() {
+        let stats = DatabaseStatistics {
+            total_items: 6,
+            sensitive_items: 3,
+            sensitive_fraction: 0.5,
+            encrypted_items: 2,
+            total_blob_bytes: 8 * 1024,
+            items_by_type: [("url".to_string(), 1), ("text".to_string(), 4), ("image".to_string(), 1)].into(),
+            bytes_by_type: [("url".to_string(), 100), ("text".to_string(), 2048), ("image".to_string(), 6144)].into(),
+            oldest_timestamp: None,
+            newest_timestamp: None,
+        };
+        let summary = StatusBarController::statistics_summary(&stats);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "text: 4 items, 2 KB");
+        assert_eq!(lines[1], "image: 1 items, 6 KB");
+        assert_eq!(lines[2], "url: 1 items, 0 KB");
+        assert_eq!(lines[4], "Total: 6 items, 8 KB on disk");
+        assert_eq!(lines[5], "Sensitive: 3 (50%), encrypted: 2");
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn test_storage_chart_rows_scale_to_largest_bucket() {
+        let breakdown = StorageBreakdown {
+            live_items_bytes: 10 * 1024,
+            deleted_items_bytes: 1024,
+            blob_count: 4,
+            deleted_blob_count: 1,
+            avg_blob_bytes: 2560.0,
+            largest_blob_bytes: 4096,
+        };
+        let rows = StatusBarController::storage_chart_rows(&breakdown);
+        assert_eq!(rows[0].1.chars().count(), STORAGE_BAR_WIDTH);
+        assert_eq!(rows[1].1.chars().count(), 3);
+        assert!(rows[0].1.chars().all(|c| c == '\u{2588}'));
+        assert_eq!(rows[0].2, " 10 KB (4 items)");
+
+        let empty = StorageBreakdown { deleted_items_bytes: 0, deleted_blob_count: 0, ..breakdown };
+        assert!(StatusBarController::storage_chart_rows(&empty)[1].1.is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 // Integration tests for image processing (TIFF→PNG conversion, thumbnails)
+use clipboard_manager::storage::config::AppConfig;
+use clipboard_manager::storage::metadata::ItemMetadata;
 use clipboard_manager::storage::processor::DataProcessor;
 
 /// Create a simple 10x10 red PNG image
@@ -83,7 +85,8 @@ fn test_process_tiff_image() {
 #[test]
 fn test_tiff_to_png_conversion() {
     let tiff_data = create_test_tiff();
-    let result = DataProcessor::process_image(&tiff_data, "public.tiff");
+    let config = AppConfig { image_tiff_to_jpeg: false, ..AppConfig::default() };
+    let result = DataProcessor::process_image_with_config(&tiff_data, "public.tiff", &config);
 
     assert!(result.is_ok());
     let processed = result.unwrap();
@@ -94,6 +97,40 @@ fn test_tiff_to_png_conversion() {
     assert_eq!(png_header, &expected_png_header, "Output should be PNG format");
 }
 
+#[test]
+fn test_opaque_tiff_to_jpeg_conversion() {
+    let tiff_data = create_test_tiff();
+    let processed = DataProcessor::process_image(&tiff_data, "public.tiff").unwrap();
+
+    // JPEG SOI marker
+    assert_eq!(&processed.blob[0..2], &[0xFF, 0xD8], "Opaque TIFF should be stored as JPEG");
+    let metadata = processed.metadata.unwrap();
+    assert!(matches!(&metadata, ItemMetadata::Image { stored_format, .. } if stored_format == "JPEG"));
+    assert_eq!(metadata.image_pasteboard_type(), "public.jpeg");
+}
+
+#[test]
+fn test_transparent_tiff_stays_png() {
+    use image::{ImageBuffer, Rgba};
+    use std::io::Cursor;
+
+    let img = ImageBuffer::from_fn(10, 10, |x, _| Rgba([0u8, 0u8, 255u8, if x < 5 { 0 } else { 255 }]));
+    let mut buf = Vec::new();
+    image::DynamicImage::ImageRgba8(img)
+        .write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Tiff)
+        .unwrap();
+
+    let processed = DataProcessor::process_image(&buf, "public.tiff").unwrap();
+    assert_eq!(&processed.blob[0..4], &[0x89, 0x50, 0x4E, 0x47], "Transparent TIFF should stay PNG");
+    assert!(matches!(processed.metadata, Some(ItemMetadata::Image { ref stored_format, .. }) if stored_format == "PNG"));
+}
+
+#[test]
+fn test_png_source_is_never_jpeg() {
+    let processed = DataProcessor::process_image(&create_test_png(), "public.png").unwrap();
+    assert_eq!(&processed.blob[0..4], &[0x89, 0x50, 0x4E, 0x47]);
+}
+
 #[test]
 fn test_compression_ratio_calculation() {
     let tiff_data = create_test_tiff();
@@ -123,29 +160,33 @@ fn test_thumbnail_generation() {
 
     // Check metadata contains thumbnail info
     assert!(processed.metadata.is_some());
-    let metadata = processed.metadata.unwrap();
+    let metadata = processed.metadata.unwrap().to_json();
 
     assert!(metadata.contains("thumbnail_width"));
     assert!(metadata.contains("thumbnail_height"));
 
-    // Thumbnail should be max 200x200
-    assert!(metadata.contains(r#""thumbnail_width":200"#) || metadata.contains(r#""thumbnail_height":200"#),
-            "Thumbnail should be scaled to 200px max: {}", metadata);
+    // Thumbnail should be max 48x48
+    assert!(metadata.contains(r#""thumbnail_width":48"#) && metadata.contains(r#""thumbnail_height":48"#),
+            "Thumbnail should be scaled to 48px max: {}", metadata);
+
+    // The stored thumbnail is a PNG of that size
+    let thumbnail = image::load_from_memory(&processed.thumbnail.expect("image should have a thumbnail")).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (48, 48));
 }
 
 #[test]
 fn test_small_image_no_upscaling() {
-    // Create a 50x50 image (smaller than 200x200 thumbnail size)
-    let small_image = create_large_test_image(50, 50);
+    // Create a 30x30 image (smaller than the 48x48 thumbnail size)
+    let small_image = create_large_test_image(30, 30);
     let result = DataProcessor::process_image(&small_image, "public.png");
 
     assert!(result.is_ok());
     let processed = result.unwrap();
 
-    let metadata = processed.metadata.unwrap();
+    let metadata = processed.metadata.unwrap().to_json();
     // Small image should not be upscaled
-    assert!(metadata.contains(r#""thumbnail_width":50"#));
-    assert!(metadata.contains(r#""thumbnail_height":50"#));
+    assert!(metadata.contains(r#""thumbnail_width":30"#));
+    assert!(metadata.contains(r#""thumbnail_height":30"#));
 }
 
 #[test]
@@ -160,10 +201,10 @@ fn test_aspect_ratio_preservation() {
     let preview = processed.preview_text.unwrap();
     assert!(preview.contains("400x200"), "Preview should show original dimensions");
 
-    let metadata = processed.metadata.unwrap();
-    // Width should be 200, height should be 100 to preserve 2:1 ratio
-    assert!(metadata.contains(r#""thumbnail_width":200"#));
-    assert!(metadata.contains(r#""thumbnail_height":100"#));
+    let metadata = processed.metadata.unwrap().to_json();
+    // Width should be 48, height should be 24 to preserve 2:1 ratio
+    assert!(metadata.contains(r#""thumbnail_width":48"#));
+    assert!(metadata.contains(r#""thumbnail_height":24"#));
 }
 
 #[test]
@@ -191,7 +232,7 @@ fn test_metadata_format() {
     let processed = result.unwrap();
 
     assert!(processed.metadata.is_some());
-    let metadata = processed.metadata.unwrap();
+    let metadata = processed.metadata.unwrap().to_json();
 
     // Should be valid JSON
     let parsed: serde_json::Value = serde_json::from_str(&metadata)
@@ -253,10 +294,10 @@ fn test_very_large_image() {
     assert!(result.is_ok(), "Large image should be processed");
     let processed = result.unwrap();
 
-    let metadata = processed.metadata.unwrap();
-    // Should be scaled down to 200x200
-    assert!(metadata.contains(r#""thumbnail_width":200"#));
-    assert!(metadata.contains(r#""thumbnail_height":200"#));
+    let metadata = processed.metadata.unwrap().to_json();
+    // Should be scaled down to 48x48
+    assert!(metadata.contains(r#""thumbnail_width":48"#));
+    assert!(metadata.contains(r#""thumbnail_height":48"#));
 }
 
 #[test]
@@ -0,0 +1,85 @@
+// Integration tests for the IPC socket (server + client over a real Unix socket)
+use clipboard_manager::ipc::{IpcClient, IpcServer};
+use clipboard_manager::storage::{
+    database::{Database, ItemId},
+    encryption::Encryptor,
+};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn insert_text(db: &Database, text: &str) -> ItemId {
+    let blob_id = db.store_blob(text.as_bytes()).unwrap();
+    db.store_item(
+        chrono::Utc::now().timestamp(),
+        "text",
+        false,
+        false,
+        Some(text),
+        text.len() as i64,
+        blob_id,
+        None,
+        1,
+    ).unwrap()
+}
+
+/// The server binds asynchronously, so retry until the socket accepts
+async fn connect_with_retry(socket_path: &Path) -> IpcClient {
+    for _ in 0..50 {
+        if let Ok(client) = IpcClient::connect(socket_path).await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("IPC server never came up at {:?}", socket_path);
+}
+
+#[tokio::test]
+async fn test_ipc_end_to_end() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new_in_memory().unwrap();
+    let enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+
+    let first = insert_text(&db, "first clipboard entry");
+    let second = insert_text(&db, "second entry about rust");
+
+    let db = Arc::new(Mutex::new(db));
+    let socket_path = temp_dir.path().join("ipc.sock");
+    let handle = IpcServer::start(&socket_path, Arc::clone(&db), Arc::new(Mutex::new(enc)));
+
+    let mut client = connect_with_retry(&socket_path).await;
+
+    // Owner-only socket
+    let mode = std::fs::metadata(&socket_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    // list
+    let items = client.list(10).await.unwrap();
+    assert_eq!(items.len(), 2);
+    let ids: Vec<ItemId> = items.iter().map(|i| i.id).collect();
+    assert!(ids.contains(&first) && ids.contains(&second));
+
+    // search
+    let results = client.search("rust").await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, second);
+
+    // paste
+    client.paste(first).await.unwrap();
+    assert!(client.paste(ItemId(9999)).await.is_err());
+
+    // delete (soft)
+    client.delete(first).await.unwrap();
+    let items = client.list(10).await.unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].id, second);
+    assert!(client.delete(first).await.is_err());
+
+    // focus: no popup in a headless test, so the server reports it unavailable
+    let err = client.focus(second).await.unwrap_err();
+    assert!(err.contains("not available"), "{}", err);
+
+    handle.abort();
+}
@@ -1,22 +1,27 @@
 // Integration tests for fuzzy search engine
 use clipboard_manager::storage::{
-    database::ClipboardItem,
+    database::{BlobId, ClipboardItem, Database, ItemId},
     search::SearchEngine,
 };
 
 fn create_test_item(id: i64, preview: &str, data_type: &str, timestamp: i64) -> ClipboardItem {
     ClipboardItem {
-        id,
+        id: ItemId(id),
         timestamp,
         data_type: data_type.to_string(),
         is_sensitive: false,
         is_encrypted: false,
         preview_text: Some(preview.to_string()),
         data_size: preview.len() as i64,
-        data_blob_id: id,
+        data_blob_id: BlobId(id),
         metadata: None,
         copy_count: 1,
         is_pinned: false,
+        thumbnail_blob_id: None,
+        source_app_name: None,
+        source_bundle_id: None,
+        sensitivity_override: None,
+        tags: Vec::new(),
     }
 }
 
@@ -30,7 +35,7 @@ fn test_exact_match() {
 
     let results = engine.search(&items, "Hello");
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].1.id, 1);
+    assert_eq!(results[0].1.id, ItemId(1));
 }
 
 #[test]
@@ -135,7 +140,7 @@ fn test_multiple_matches_same_score() {
     assert_eq!(results.len(), 2);
 
     // Same score, so should be sorted by timestamp (newer first)
-    assert_eq!(results[0].1.id, 1, "Newer item should come first");
+    assert_eq!(results[0].1.id, ItemId(1), "Newer item should come first");
 }
 
 #[test]
@@ -148,7 +153,7 @@ fn test_partial_word_match() {
 
     let results = engine.search(&items, "java");
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].1.id, 1);
+    assert_eq!(results[0].1.id, ItemId(1));
 }
 
 #[test]
@@ -194,7 +199,7 @@ fn test_unicode_search() {
 
     let results = engine.search(&items, "你好");
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].1.id, 1);
+    assert_eq!(results[0].1.id, ItemId(1));
 }
 
 #[test]
@@ -249,7 +254,7 @@ fn test_search_numbers() {
 
     let results = engine.search(&items, "12345");
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].1.id, 1);
+    assert_eq!(results[0].1.id, ItemId(1));
 }
 
 #[test]
@@ -306,3 +311,205 @@ fn test_mixed_content_types() {
     let results_api = engine.search(&items, "API");
     assert_eq!(results_api.len(), 1);
 }
+
+#[test]
+fn test_empty_query_keeps_recent_items_order() {
+    let db = Database::new_in_memory().unwrap();
+    for (i, ts) in [300, 100, 500, 200, 400].iter().enumerate() {
+        let text = format!("entry {}", i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(*ts, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap();
+    }
+    let recent = db.get_recent_items(10).unwrap();
+
+    let expected: Vec<ItemId> = recent.iter().map(|item| item.id).collect();
+    let ranked: Vec<ItemId> = SearchEngine::ranked_by_recency(&recent).iter().map(|(_, item)| item.id).collect();
+    let searched: Vec<ItemId> = SearchEngine::new().search(&recent, " ").iter().map(|(_, item)| item.id).collect();
+
+    assert_eq!(ranked, expected);
+    assert_eq!(searched, expected);
+}
+
+fn with_metadata(mut item: ClipboardItem, metadata: &str) -> ClipboardItem {
+    item.metadata = Some(metadata.to_string());
+    item
+}
+
+#[test]
+fn test_image_search_by_dimensions() {
+    let engine = SearchEngine::new();
+    let items = vec![
+        with_metadata(create_test_item(1, "Screenshot", "image", 100),
+            r#"{"width":1920,"height":1080,"format":"PNG"}"#),
+        with_metadata(create_test_item(2, "Icon", "image", 200),
+            r#"{"width":64,"height":64,"format":"PNG"}"#),
+        create_test_item(3, "Meeting notes", "text", 300),
+    ];
+
+    let results = engine.search_metadata(&items, "1920");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.id, ItemId(1));
+
+    // `search` includes metadata matches alongside preview matches
+    let results = engine.search(&items, "1920");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.id, ItemId(1));
+}
+
+#[test]
+fn test_search_by_source_uti() {
+    let engine = SearchEngine::new();
+    let items = vec![
+        with_metadata(create_test_item(1, "Formatted paragraph", "rtf", 100),
+            r#"{"uti_types":["public.rtf","public.utf8-plain-text"]}"#),
+        with_metadata(create_test_item(2, "Plain paragraph", "text", 200),
+            r#"{"uti_types":["public.utf8-plain-text"]}"#),
+    ];
+
+    let results = engine.search_metadata(&items, "public.rtf");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.id, ItemId(1));
+
+    // Metadata is cached per slice; a different slice is matched afresh
+    let reordered = vec![items[1].clone(), items[0].clone()];
+    let results = engine.search_metadata(&reordered, "public.rtf");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.id, ItemId(1));
+}
+
+#[test]
+fn test_search_by_metadata_field() {
+    let engine = SearchEngine::new();
+    let items = vec![
+        with_metadata(create_test_item(1, "Screenshot", "image", 100),
+            r#"{"width":1920,"height":1080,"format":"PNG"}"#),
+        with_metadata(create_test_item(2, "Photo", "image", 200),
+            r#"{"width":800,"height":600,"format":"JPEG"}"#),
+        with_metadata(create_test_item(3, "Styled text", "rtf", 300),
+            r#"{"uti_types":["public.rtf","public.utf8-plain-text"]}"#),
+        create_test_item(4, "No metadata", "text", 400),
+    ];
+
+    let png: Vec<ItemId> = engine.search_by_metadata_field(&items, "format", "png").iter().map(|i| i.id).collect();
+    assert_eq!(png, vec![ItemId(1)]);
+
+    let rtf: Vec<ItemId> = engine.search_by_metadata_field(&items, "uti_types", "public.rtf").iter().map(|i| i.id).collect();
+    assert_eq!(rtf, vec![ItemId(3)]);
+
+    let wide: Vec<ItemId> = engine.search_by_metadata_field(&items, "width", "800").iter().map(|i| i.id).collect();
+    assert_eq!(wide, vec![ItemId(2)]);
+
+    assert!(engine.search_by_metadata_field(&items, "missing", "x").is_empty());
+}
+
+fn store_preview(db: &Database, preview: &str, data_type: &str, timestamp: i64) -> ItemId {
+    let blob_id = db.store_blob(preview.as_bytes()).unwrap();
+    db.store_item(timestamp, data_type, false, false, Some(preview), preview.len() as i64, blob_id, None, 1).unwrap()
+}
+
+fn fts_ids(db: &Database, query: &str) -> Vec<ItemId> {
+    db.fts_search(query, 20).unwrap().iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_fts_bm25_ranks_denser_matches_first() {
+    let db = Database::new_in_memory().unwrap();
+    let sparse = store_preview(&db, "release notes mention rust once among many other unrelated words here", "text", 300);
+    let dense = store_preview(&db, "rust rust rust", "text", 100);
+    store_preview(&db, "nothing relevant", "text", 200);
+
+    assert_eq!(fts_ids(&db, "rust"), vec![dense, sparse]);
+}
+
+#[test]
+fn test_fts_tokenizes_urls_and_code() {
+    let db = Database::new_in_memory().unwrap();
+    let url = store_preview(&db, "https://github.com/rust-lang/rust/issues?q=label%3AE-easy", "url", 100);
+    let code = store_preview(&db, "fn parse_config(path: &Path) -> Result<Config>", "text", 200);
+
+    assert_eq!(fts_ids(&db, "github"), vec![url]);
+    assert_eq!(fts_ids(&db, "github.com/rust-lang"), vec![url]);
+    assert_eq!(fts_ids(&db, "parse_config"), vec![code]);
+    // Prefix matching, and underscores keep identifiers whole
+    assert_eq!(fts_ids(&db, "pars"), vec![code]);
+    assert!(fts_ids(&db, "config").contains(&code)); // via Result<Config>
+    assert!(fts_ids(&db, "parse").contains(&code));
+
+    // FTS syntax in user input is matched literally instead of failing
+    assert!(db.fts_search("\"unterminated OR NEAR(", 20).is_ok());
+    assert!(db.fts_search("-> ::", 20).unwrap().is_empty());
+    assert!(db.fts_search("   ", 20).unwrap().is_empty());
+}
+
+#[test]
+fn test_fts_stays_consistent_with_inserts_updates_and_deletes() {
+    let db = Database::new_in_memory().unwrap();
+    let first = store_preview(&db, "alpha token", "text", 100);
+    let second = store_preview(&db, "alpha beta", "text", 200);
+    assert_eq!(fts_ids(&db, "alpha").len(), 2);
+
+    db.delete_item(first).unwrap();
+    assert_eq!(fts_ids(&db, "alpha"), vec![second]);
+    assert!(fts_ids(&db, "token").is_empty());
+
+    db.update_preview_text(second, "gamma beta").unwrap();
+    assert!(fts_ids(&db, "alpha").is_empty());
+    assert_eq!(fts_ids(&db, "gamma"), vec![second]);
+
+    db.soft_delete_item(second).unwrap();
+    assert!(fts_ids(&db, "gamma").is_empty());
+
+    db.rebuild_fts_index().unwrap();
+    assert!(fts_ids(&db, "gamma").is_empty());
+    let third = store_preview(&db, "gamma again", "text", 300);
+    db.rebuild_fts_index().unwrap();
+    assert_eq!(fts_ids(&db, "gamma"), vec![third]);
+}
+
+#[test]
+fn test_search_database_falls_back_to_fuzzy() {
+    let db = Database::new_in_memory().unwrap();
+    let clipman = store_preview(&db, "clipboard manager notes", "text", 100);
+    let engine = SearchEngine::new();
+
+    // Indexed word match
+    let hits: Vec<ItemId> = engine.search_database(&db, "manager", 10).unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(hits, vec![clipman]);
+
+    // Not a token prefix, but still a fuzzy match
+    let hits: Vec<ItemId> = engine.search_database(&db, "clpmgr", 10).unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(hits, vec![clipman]);
+}
+
+#[test]
+fn test_tag_query_filters_items() {
+    let engine = SearchEngine::new();
+    let mut items = vec![
+        create_test_item(1, "quarterly report draft", "text", 100),
+        create_test_item(2, "quarterly budget", "text", 200),
+        create_test_item(3, "lunch order", "text", 300),
+    ];
+    items[0].tags = vec!["work".to_string()];
+    items[2].tags = vec!["work".to_string(), "food".to_string()];
+
+    let ids = |results: Vec<(i64, &ClipboardItem)>| results.iter().map(|(_, i)| i.id).collect::<Vec<_>>();
+    assert_eq!(ids(engine.search(&items, "#work")), vec![ItemId(1), ItemId(3)]);
+    assert_eq!(ids(engine.search(&items, "#WORK quarterly")), vec![ItemId(1)]);
+    assert_eq!(ids(engine.search(&items, "#work #food")), vec![ItemId(3)]);
+    assert!(engine.search(&items, "#travel").is_empty());
+    // A lone '#' is ordinary text
+    assert_eq!(SearchEngine::split_tags("# #work c#"), (vec!["work".to_string()], "# c#".to_string()));
+}
+
+#[test]
+fn test_search_database_with_tags() {
+    let db = Database::new_in_memory().unwrap();
+    let report = store_preview(&db, "quarterly report", "text", 100);
+    store_preview(&db, "quarterly budget", "text", 200);
+    db.add_tag(report, "work").unwrap();
+    let engine = SearchEngine::new();
+
+    let hits: Vec<ItemId> = engine.search_database(&db, "#work quarterly", 10).unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(hits, vec![report]);
+    assert!(engine.search_database(&db, "#work budget", 10).unwrap().is_empty());
+}
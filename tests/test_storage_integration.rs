@@ -1,7 +1,11 @@
 // Integration tests for storage layer (database + encryption)
 use clipboard_manager::storage::{
-    database::Database,
+    config::{AppConfig, RetentionPolicy},
+    database::{ClipboardItem, Database, ItemId, PendingItem, APP_ICON_PIXELS},
     encryption::Encryptor,
+    metadata::{ItemMetadata, SourceApp},
+    migrations::Migrator,
+    processor::{DataProcessor, ProcessedData, ProcessedDataType},
 };
 use tempfile::TempDir;
 
@@ -17,9 +21,7 @@ fn test_database_creation() {
 
 #[test]
 fn test_insert_and_retrieve_text() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     let text = b"Hello, World!";
     let blob_id = db.store_blob(text).unwrap();
@@ -35,7 +37,7 @@ fn test_insert_and_retrieve_text() {
         None,
         1,
     ).unwrap();
-    assert!(item_id > 0);
+    assert!(item_id.0 > 0);
 
     let items = db.get_recent_items(10).unwrap();
     assert_eq!(items.len(), 1);
@@ -43,13 +45,55 @@ fn test_insert_and_retrieve_text() {
     assert_eq!(items[0].preview_text, Some("Hello, World!".to_string()));
 }
 
+#[test]
+fn test_insert_item_from_processed_data() {
+    let db = Database::new_in_memory().unwrap();
+
+    let processed = ProcessedData::builder()
+        .data_type(ProcessedDataType::Url)
+        .blob("https://example.com/page")
+        .preview_text("https://example.com/page")
+        .sensitive(true)
+        .metadata(ItemMetadata::Text { uti_types: vec!["public.url".to_string()], embedded_urls: vec![] })
+        .build();
+
+    let blob_id = db.store_blob(b"ciphertext").unwrap();
+    let item_id = db.insert_item(&processed, blob_id, 1_700_000_000, 3, true).unwrap();
+
+    let item = db.get_item(item_id).unwrap();
+    assert_eq!(item.id, item_id);
+    assert_eq!(item.timestamp, 1_700_000_000);
+    assert_eq!(item.data_type, "url");
+    assert!(item.is_sensitive);
+    assert!(item.is_encrypted);
+    assert_eq!(item.preview_text.as_deref(), Some("https://example.com/page"));
+    // Size of the original data, not the stored (encrypted) blob
+    assert_eq!(item.data_size, processed.blob.len() as i64);
+    assert_eq!(item.data_blob_id, blob_id);
+    assert_eq!(item.copy_count, 3);
+    assert!(!item.is_pinned);
+    assert_eq!(
+        ItemMetadata::from_json(item.metadata.as_deref().unwrap()),
+        ItemMetadata::Text { uti_types: vec!["public.url".to_string()], embedded_urls: vec![] }
+    );
+}
+
+#[test]
+fn test_processed_data_builder_defaults() {
+    let processed = ProcessedData::builder().build();
+    assert_eq!(processed.data_type, ProcessedDataType::PlainText);
+    assert!(processed.blob.is_empty());
+    assert!(processed.preview_text.is_none());
+    assert!(!processed.is_sensitive);
+    assert!(processed.metadata.is_none());
+}
+
 #[test]
 fn test_insert_encrypted_item() {
     let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
     let key_path = temp_dir.path().join("test.key");
 
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
     let encryptor = Encryptor::new(key_path).unwrap();
 
     let sensitive_text = b"sk-1234567890abcdefghijklmnopqrstuvwxyz";
@@ -69,7 +113,7 @@ fn test_insert_encrypted_item() {
         None,
         1,
     ).unwrap();
-    assert!(item_id > 0);
+    assert!(item_id.0 > 0);
 
     let items = db.get_recent_items(10).unwrap();
     assert_eq!(items.len(), 1);
@@ -82,10 +126,315 @@ fn test_insert_encrypted_item() {
 }
 
 #[test]
-fn test_multiple_items_ordering() {
+fn test_encrypted_blob_is_bound_to_item() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new_in_memory().unwrap();
+    let encryptor = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+
+    let secret = ProcessedData::builder()
+        .blob(b"password=hunter2".to_vec())
+        .preview_text("🔒 Sensitive data")
+        .sensitive(true)
+        .build();
+    let other = ProcessedData::builder()
+        .blob(b"password=letmein".to_vec())
+        .preview_text("🔒 Sensitive data 2")
+        .sensitive(true)
+        .build();
+
+    // Placeholder blob first, then seal it with the item ID (as the monitor does)
+    let mut ids = Vec::new();
+    for processed in [&secret, &other] {
+        let blob_id = db.store_blob(&[]).unwrap();
+        let item_id = db.insert_item(processed, blob_id, 1_700_000_000, 1, true).unwrap();
+        let encrypted = encryptor.encrypt_for_item(&processed.blob, item_id).unwrap();
+        db.update_blob(blob_id, &encrypted).unwrap();
+        ids.push(item_id);
+    }
+
+    let first = db.get_item(ids[0]).unwrap();
+    let second = db.get_item(ids[1]).unwrap();
+    let first_blob = db.get_blob(first.data_blob_id).unwrap();
+    assert_eq!(encryptor.decrypt_for_item(&first_blob, first.id).unwrap(), secret.blob);
+
+    // A blob moved onto another item no longer authenticates
+    assert!(encryptor.decrypt_for_item(&first_blob, second.id).is_err());
+}
+
+/// Store a sensitive item sealed with `encryptor`, as the monitor does
+fn store_sealed(db: &Database, encryptor: &Encryptor, secret: &[u8], timestamp: i64) -> ItemId {
+    let processed = ProcessedData::builder()
+        .blob(secret.to_vec())
+        .preview_text("🔒 Sensitive data")
+        .sensitive(true)
+        .build();
+    let blob_id = db.store_blob(&[]).unwrap();
+    let item_id = db.insert_item(&processed, blob_id, timestamp, 1, true).unwrap();
+    db.update_blob(blob_id, &encryptor.encrypt_for_item(secret, item_id).unwrap()).unwrap();
+    item_id
+}
+
+#[test]
+fn test_reencrypt_all_blobs_rotates_key() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new_in_memory().unwrap();
+    let old_enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+
+    let live = store_sealed(&db, &old_enc, b"password=hunter2", 100);
+    let trashed = store_sealed(&db, &old_enc, b"password=letmein", 200);
+    let plain_blob = db.store_blob(b"hello").unwrap();
+    let plain = db.store_item(300, "text", false, false, Some("hello"), 5, plain_blob, None, 1).unwrap();
+    db.soft_delete_item(trashed).unwrap();
+
+    let new_enc = old_enc.rotate_key(&temp_dir.path().join("test.key.new")).unwrap();
+    assert_eq!(db.reencrypt_all_blobs(&old_enc, &new_enc).unwrap(), 2);
+
+    let live_blob = db.get_blob(db.get_item(live).unwrap().data_blob_id).unwrap();
+    assert_eq!(new_enc.decrypt_for_item(&live_blob, live).unwrap(), b"password=hunter2");
+    assert!(old_enc.decrypt_for_item(&live_blob, live).is_err());
+
+    // The keyed content hash follows the new key, so repeat copies still dedup
+    let new_hash = Database::content_hash_for(b"password=hunter2", true, &new_enc).unwrap();
+    assert_eq!(db.find_item_by_content_hash(&new_hash).unwrap().map(|item| item.id), Some(live));
+    let old_hash = Database::content_hash_for(b"password=hunter2", true, &old_enc).unwrap();
+    assert!(db.find_item_by_content_hash(&old_hash).unwrap().is_none());
+
+    // Plaintext blobs are left alone
+    assert_eq!(db.get_blob(db.get_item(plain).unwrap().data_blob_id).unwrap(), b"hello");
+
+    // Trashed blobs were rotated too: nothing is left under the old key
+    let trashed_blob = db.get_deleted_blob(trashed).unwrap();
+    assert_eq!(new_enc.decrypt_for_item(&trashed_blob, trashed).unwrap(), b"password=letmein");
+    assert!(old_enc.decrypt_for_item(&trashed_blob, trashed).is_err());
+}
+
+#[test]
+fn test_reencrypt_all_blobs_rolls_back_on_failure() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Database::new_in_memory().unwrap();
+    let old_enc = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+    let stranger = Encryptor::new(temp_dir.path().join("other.key")).unwrap();
+
+    let first = store_sealed(&db, &old_enc, b"password=hunter2", 100);
+    // Sealed under a key we don't hold, so re-encryption fails part-way
+    store_sealed(&db, &stranger, b"password=letmein", 200);
+
+    let new_enc = old_enc.rotate_key(&temp_dir.path().join("test.key.new")).unwrap();
+    assert!(db.reencrypt_all_blobs(&old_enc, &new_enc).is_err());
+
+    // The first blob was rolled back and still opens with the old key
+    let blob = db.get_blob(db.get_item(first).unwrap().data_blob_id).unwrap();
+    assert_eq!(old_enc.decrypt_for_item(&blob, first).unwrap(), b"password=hunter2");
+    assert!(new_enc.decrypt_for_item(&blob, first).is_err());
+}
+
+#[test]
+fn test_sensitivity_override_persists() {
     let temp_dir = TempDir::new().unwrap();
     let db_path = temp_dir.path().join("test.db");
+    let (public_key, false_positive) = {
+        let db = Database::new(db_path.clone()).unwrap();
+        let public_key = db.insert_item(
+            &DataProcessor::process_text("ssh-ed25519 AAAAC3Nza me@laptop", &[]), db.store_blob(b"k").unwrap(), 100, 1, false,
+        ).unwrap();
+        let flagged = ProcessedData::builder().blob(b"Passw0rd!-style".to_vec()).sensitive(true).build();
+        let false_positive = db.insert_item(&flagged, db.store_blob(b"p").unwrap(), 200, 1, false).unwrap();
+
+        let item = db.get_item(public_key).unwrap();
+        assert_eq!(item.sensitivity_override, None);
+        assert_eq!(item.effective_sensitivity(), item.is_sensitive);
+        assert_eq!(db.count_sensitive_items().unwrap(), 1);
+
+        db.set_sensitivity_override(public_key, Some(true)).unwrap();
+        db.set_sensitivity_override(false_positive, Some(false)).unwrap();
+        assert!(db.set_sensitivity_override(ItemId(999), Some(true)).is_err());
+        (public_key, false_positive)
+    };
+
+    // Overrides survive reopening the database and win over detection
     let db = Database::new(db_path).unwrap();
+    let item = db.get_item(public_key).unwrap();
+    assert!(!item.is_sensitive);
+    assert_eq!(item.sensitivity_override, Some(true));
+    assert!(item.effective_sensitivity());
+    let item = db.get_item(false_positive).unwrap();
+    assert!(item.is_sensitive);
+    assert!(!item.effective_sensitivity());
+    assert_eq!(db.count_sensitive_items().unwrap(), 1);
+
+    // Clearing the override falls back to detection
+    db.set_sensitivity_override(false_positive, None).unwrap();
+    let item = db.get_item(false_positive).unwrap();
+    assert_eq!(item.sensitivity_override, None);
+    assert!(item.effective_sensitivity());
+}
+
+#[test]
+fn test_item_tags() {
+    let db = Database::new_in_memory().unwrap();
+    let url = db.insert_item(
+        &DataProcessor::process_text("https://example.com/docs", &[]), db.store_blob(b"u").unwrap(), 100, 1, false,
+    ).unwrap();
+    let note = db.insert_item(
+        &DataProcessor::process_text("standup notes", &[]), db.store_blob(b"n").unwrap(), 200, 1, false,
+    ).unwrap();
+
+    // Auto-tagged at capture
+    assert_eq!(db.get_item(url).unwrap().tags, vec!["link".to_string()]);
+    assert!(db.get_item(note).unwrap().tags.is_empty());
+
+    // Tags are normalized and de-duplicated
+    db.add_tag(note, "#Work").unwrap();
+    db.add_tag(note, "work").unwrap();
+    db.add_tag(note, "todo").unwrap();
+    db.add_tag(url, "work").unwrap();
+    assert_eq!(db.get_item(note).unwrap().tags, vec!["work".to_string(), "todo".to_string()]);
+    assert!(db.get_item(note).unwrap().has_tag("#WORK"));
+    assert!(db.add_tag(note, "two words").is_err());
+    assert!(db.add_tag(ItemId(999), "work").is_err());
+
+    let tagged: Vec<ItemId> = db.get_items_by_tag("work", 10).unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(tagged, vec![note, url]);
+    // Matches whole tags only
+    assert!(db.get_items_by_tag("wor", 10).unwrap().is_empty());
+
+    db.remove_tag(note, "work").unwrap();
+    db.remove_tag(note, "missing").unwrap();
+    assert_eq!(db.get_item(note).unwrap().tags, vec!["todo".to_string()]);
+    db.remove_tag(note, "todo").unwrap();
+    assert!(db.get_item(note).unwrap().tags.is_empty());
+
+    db.set_tags(url, &["Reading".to_string(), "reading".to_string(), "docs".to_string()]).unwrap();
+    assert_eq!(db.get_item(url).unwrap().tags, vec!["reading".to_string(), "docs".to_string()]);
+    db.set_tags(url, &[]).unwrap();
+    assert!(db.get_items_by_tag("link", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_export_import_json_file_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_enc = Encryptor::new(temp_dir.path().join("source.key")).unwrap();
+    let target_enc = Encryptor::new(temp_dir.path().join("target.key")).unwrap();
+    let export_path = temp_dir.path().join("history.json");
+
+    let source = Database::new_in_memory().unwrap();
+    let text = source.insert_item(
+        &DataProcessor::process_text("meeting notes", &[]), source.store_blob(b"meeting notes").unwrap(), 100, 2, false,
+    ).unwrap();
+    source.add_tag(text, "work").unwrap();
+    source.toggle_pin(text).unwrap();
+    let png = [0x89, b'P', b'N', b'G', 0, 1, 2, 3];
+    let image = ProcessedData::builder()
+        .data_type(ProcessedDataType::Image)
+        .blob(png.to_vec())
+        .preview_text("1x1 PNG")
+        .build();
+    source.insert_item(&image, source.store_blob(&png).unwrap(), 200, 1, false).unwrap();
+    store_sealed(&source, &source_enc, b"sk-live-secret", 300);
+
+    assert_eq!(source.export_json_file(&export_path, true, Some(&source_enc)).unwrap(), 3);
+    assert!(!temp_dir.path().join("history.json.tmp").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&export_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let target = Database::new_in_memory().unwrap();
+    assert_eq!(target.import_json(&export_path, &target_enc).unwrap(), 3);
+    // Importing again adds nothing
+    assert_eq!(target.import_json(&export_path, &target_enc).unwrap(), 0);
+
+    let mut items = target.get_recent_items(10).unwrap();
+    items.sort_by_key(|item| item.timestamp);
+    let [note, picture, secret] = &items[..] else { panic!("expected 3 items") };
+    assert!(secret.is_encrypted);
+    let sealed = target.get_blob(secret.data_blob_id).unwrap();
+    assert_eq!(target_enc.decrypt_for_item(&sealed, secret.id).unwrap(), b"sk-live-secret");
+    assert_eq!(picture.data_type, "image");
+    assert_eq!(target.get_blob(picture.data_blob_id).unwrap(), png);
+    assert_eq!(target.get_blob(note.data_blob_id).unwrap(), b"meeting notes");
+    assert_eq!(note.tags, vec!["work".to_string()]);
+    assert!(note.is_pinned);
+    assert_eq!(note.copy_count, 2);
+
+    // Without blobs (or without the key for encrypted ones) there's no data to import
+    source.export_json_file(&export_path, false, Some(&source_enc)).unwrap();
+    let exported = std::fs::read_to_string(&export_path).unwrap();
+    assert!(!exported.contains("\"data\""));
+    assert!(Database::new_in_memory().unwrap().import_json(&export_path, &target_enc).is_err());
+    source.export_json_file(&export_path, true, None).unwrap();
+    let fresh = Database::new_in_memory().unwrap();
+    assert_eq!(fresh.import_json(&export_path, &target_enc).unwrap(), 2);
+}
+
+#[test]
+fn test_identical_blobs_are_stored_once() {
+    let db = Database::new_in_memory().unwrap();
+    let png = [0x89, b'P', b'N', b'G', 7, 7, 7];
+    let image = ProcessedData::builder()
+        .data_type(ProcessedDataType::Image)
+        .blob(png.to_vec())
+        .build();
+
+    let first_blob = db.store_blob(&png).unwrap();
+    let first = db.insert_item(&image, first_blob, 100, 1, false).unwrap();
+    let second_blob = db.store_blob(&png).unwrap();
+    let second = db.insert_item(&image, second_blob, 200, 1, false).unwrap();
+
+    assert_eq!(first_blob, second_blob);
+    assert_eq!(db.count_items().unwrap(), 2);
+    assert_eq!(db.get_blob_count().unwrap(), 1);
+    assert_eq!(db.get_unique_blob_count().unwrap(), 1);
+
+    // Placeholders for encrypted data are overwritten later, so never shared
+    assert_ne!(db.store_blob(&[]).unwrap(), db.store_blob(&[]).unwrap());
+
+    // The shared blob outlives the first item
+    db.delete_item(first).unwrap();
+    assert_eq!(db.get_blob(db.get_item(second).unwrap().data_blob_id).unwrap(), png);
+    db.delete_item(second).unwrap();
+    assert!(db.get_blob(second_blob).is_err());
+}
+
+#[test]
+fn test_blob_compression() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let encryptor = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+    let log = "GET /api/items 200 OK\n".repeat(500);
+
+    // Written before compression was turned on
+    let legacy = {
+        let db = Database::new(db_path.clone()).unwrap();
+        db.store_blob(b"written uncompressed").unwrap()
+    };
+
+    let mut db = Database::new(db_path).unwrap();
+    db.set_compression(true);
+    assert_eq!(db.get_blob(legacy).unwrap(), b"written uncompressed");
+
+    let before = db.get_storage_breakdown().unwrap().live_items_bytes;
+    let blob_id = db.store_blob(log.as_bytes()).unwrap();
+    let stored = db.get_storage_breakdown().unwrap().live_items_bytes - before;
+    assert!(stored < log.len() as i64 / 10);
+    assert_eq!(db.get_blob(blob_id).unwrap(), log.as_bytes());
+
+    // Compressed, then encrypted; read back through decrypt and decompress
+    let processed = ProcessedData::builder().blob(log.as_bytes().to_vec()).sensitive(true).build();
+    let placeholder = db.store_blob(&[]).unwrap();
+    let item_id = db.insert_item(&processed, placeholder, 100, 1, true).unwrap();
+    let sealed_len = db.seal_blob(placeholder, item_id, log.as_bytes(), &encryptor).unwrap();
+    assert!(sealed_len < log.len() / 10);
+    let item = db.get_item(item_id).unwrap();
+    assert_eq!(db.read_item_data(&item, Some(&encryptor)).unwrap(), log.as_bytes());
+    assert!(db.read_item_data(&item, None).is_err());
+}
+
+#[test]
+fn test_multiple_items_ordering() {
+    let db = Database::new_in_memory().unwrap();
 
     // Use explicit timestamps so ordering is deterministic
     let base_ts = chrono::Utc::now().timestamp();
@@ -113,9 +462,7 @@ fn test_multiple_items_ordering() {
 
 #[test]
 fn test_cleanup_old_items() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     // Insert an item with a timestamp 2 days in the past
     let blob_id = db.store_blob(b"Test item").unwrap();
@@ -136,22 +483,114 @@ fn test_cleanup_old_items() {
     assert_eq!(items.len(), 1);
 
     // Cleanup items older than 1 day — should catch our 2-day-old item
-    let deleted = db.cleanup_old_items(1).unwrap();
+    let config = AppConfig { retention_days: 1, ..AppConfig::default() };
+    let deleted = db.cleanup_old_items(&config).unwrap();
     assert_eq!(deleted, 1);
 
     let items = db.get_recent_items(10).unwrap();
     assert_eq!(items.len(), 0);
 }
 
+#[test]
+fn test_sensitive_items_use_shorter_retention() {
+    let db = Database::new_in_memory().unwrap();
+    let config = AppConfig { retention_days: 7, sensitive_retention_days: Some(1), ..AppConfig::default() };
+    let now = chrono::Utc::now().timestamp();
+
+    let insert = |age_days: i64, sensitive: bool, preview: &str| {
+        let processed = ProcessedData::builder()
+            .blob(preview.as_bytes().to_vec())
+            .preview_text(preview)
+            .sensitive(sensitive)
+            .build();
+        let blob_id = db.store_blob(&processed.blob).unwrap();
+        db.insert_item(&processed, blob_id, now - age_days * 86400, 1, false).unwrap()
+    };
+    let old_secret = insert(2, true, "old secret");
+    let new_secret = insert(0, true, "new secret");
+    let old_note = insert(2, false, "old note");
+    let ancient_note = insert(8, false, "ancient note");
+
+    let items = db.get_recent_items(10).unwrap();
+    let expired: Vec<_> = items.iter().filter(|i| i.is_expired(&config, now)).map(|i| i.id).collect();
+    assert_eq!(expired.len(), 2);
+    assert!(expired.contains(&old_secret) && expired.contains(&ancient_note));
+    let secret = items.iter().find(|i| i.id == old_secret).unwrap();
+    assert_eq!(secret.effective_retention_days(&config), 1);
+    let note = items.iter().find(|i| i.id == old_note).unwrap();
+    assert_eq!(note.effective_retention_days(&config), 7);
+
+    assert_eq!(db.cleanup_old_items(&config).unwrap(), 2);
+    let remaining: Vec<_> = db.get_recent_items(10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining.contains(&new_secret) && remaining.contains(&old_note));
+
+    // Without an override, sensitive items follow the normal retention
+    let config = AppConfig { retention_days: 7, ..AppConfig::default() };
+    let item = db.get_item(new_secret).unwrap();
+    assert_eq!(item.effective_retention_days(&config), 7);
+}
+
+#[test]
+fn test_cleanup_with_policy_keeps_text_past_image_window() {
+    let db = Database::new_in_memory().unwrap();
+    let five_days_ago = chrono::Utc::now().timestamp() - 5 * 86400;
+    let text = store_text_item(&db, "text", "meeting notes", five_days_ago, 1);
+    store_text_item(&db, "image", "Image 10x10", five_days_ago, 1);
+
+    let policy = RetentionPolicy { text_days: 30, image_days: 3, ..RetentionPolicy::default() };
+    assert_eq!(db.cleanup_with_policy(&policy, None).unwrap(), 1);
+
+    let remaining: Vec<_> = db.get_recent_items(10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(remaining, vec![text]);
+    assert_eq!(db.get_storage_breakdown().unwrap().blob_count, 1);
+}
+
+#[test]
+fn test_cleanup_with_policy_keeps_image_past_text_window() {
+    let db = Database::new_in_memory().unwrap();
+    let five_days_ago = chrono::Utc::now().timestamp() - 5 * 86400;
+    store_text_item(&db, "text", "meeting notes", five_days_ago, 1);
+    store_text_item(&db, "code", "fn main() {}", five_days_ago, 1);
+    let image = store_text_item(&db, "image", "Image 10x10", five_days_ago, 1);
+
+    // Types without their own window (code here) follow text_days
+    let policy = RetentionPolicy { text_days: 3, image_days: 30, ..RetentionPolicy::default() };
+    assert_eq!(db.cleanup_with_policy(&policy, None).unwrap(), 2);
+
+    let remaining: Vec<_> = db.get_recent_items(10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(remaining, vec![image]);
+}
+
+#[test]
+fn test_deleted_item_count_and_size() {
+    let mut db = Database::new_in_memory().unwrap();
+    assert_eq!(db.get_deleted_item_count().unwrap(), 0);
+    assert_eq!(db.get_total_deleted_size().unwrap(), 0);
+
+    let ids: Vec<_> = ["one", "three", "fivee"].iter().enumerate().map(|(i, text)| {
+        let processed = ProcessedData::builder().blob(text.as_bytes().to_vec()).preview_text(*text).build();
+        let blob_id = db.store_blob(&processed.blob).unwrap();
+        db.insert_item(&processed, blob_id, 1_700_000_000 + i as i64, 1, false).unwrap()
+    }).collect();
+
+    db.soft_delete_item(ids[0]).unwrap();
+    assert_eq!(db.get_deleted_item_count().unwrap(), 1);
+    assert_eq!(db.get_total_deleted_size().unwrap(), 3);
+
+    db.soft_delete_all_items().unwrap();
+    assert_eq!(db.get_deleted_item_count().unwrap(), 3);
+    assert_eq!(db.get_total_deleted_size().unwrap(), 3 + 5 + 5);
+}
+
 #[test]
 fn test_image_storage() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     let image_data = vec![0x89, 0x50, 0x4E, 0x47];
     let blob_id = db.store_blob(&image_data).unwrap();
     let timestamp = chrono::Utc::now().timestamp();
+    let metadata = ItemMetadata::from_json(r#"{"width":640,"height":480,"format":"PNG"}"#);
     let item_id = db.store_item(
         timestamp,
         "image",
@@ -160,15 +599,18 @@ fn test_image_storage() {
         Some("640x480 PNG"),
         image_data.len() as i64,
         blob_id,
-        Some(r#"{"width":640,"height":480,"format":"PNG"}"#),
+        Some(&metadata),
         1,
     ).unwrap();
 
-    assert!(item_id > 0);
+    assert!(item_id.0 > 0);
     let items = db.get_recent_items(10).unwrap();
     assert_eq!(items.len(), 1);
     assert_eq!(items[0].data_type, "image");
 
+    let stored = ItemMetadata::from_json(items[0].metadata.as_deref().unwrap());
+    assert!(matches!(stored, ItemMetadata::Image { width: 640, height: 480, .. }));
+
     let blob = db.get_blob(items[0].data_blob_id).unwrap();
     assert_eq!(blob, image_data);
 }
@@ -196,9 +638,7 @@ fn test_encryption_roundtrip() {
 
 #[test]
 fn test_item_count() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     // Insert 4 items
     let base_ts = chrono::Utc::now().timestamp();
@@ -225,9 +665,7 @@ fn test_item_count() {
 
 #[test]
 fn test_get_item_count() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     // Initially should be 0
     let count = db.count_items().unwrap();
@@ -257,9 +695,7 @@ fn test_get_item_count() {
 
 #[test]
 fn test_pin_and_delete() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     let blob_id = db.store_blob(b"pinnable").unwrap();
     let ts = chrono::Utc::now().timestamp();
@@ -291,11 +727,98 @@ fn test_pin_and_delete() {
     assert_eq!(db.count_items().unwrap(), 0);
 }
 
+#[test]
+fn test_pinned_item_listed_first() {
+    let db = Database::new_in_memory().unwrap();
+
+    let now = chrono::Utc::now().timestamp();
+    let ids: Vec<ItemId> = (0..5).map(|i| {
+        let text = format!("item {}", i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(now + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap()
+    }).collect();
+
+    // The oldest item jumps ahead of newer ones once pinned
+    db.pin_item(ids[0]).unwrap();
+    let items = db.get_recent_items(10).unwrap();
+    assert_eq!(items[0].id, ids[0]);
+    assert!(items[0].is_pinned);
+    assert_eq!(items[1].id, ids[4]);
+
+    db.unpin_item(ids[0]).unwrap();
+    assert_eq!(db.get_recent_items(10).unwrap()[0].id, ids[4]);
+    assert!(db.unpin_item(ItemId(9999)).is_err());
+}
+
+#[test]
+fn test_cleanup_skips_pinned_items() {
+    let db = Database::new_in_memory().unwrap();
+    let now = chrono::Utc::now().timestamp();
+    let pinned = store_text_item(&db, "text", "SELECT * FROM users", now - 30 * 86400, 1);
+    let pinned_image = store_text_item(&db, "image", "Image 10x10", now - 30 * 86400, 1);
+    store_text_item(&db, "text", "old note", now - 30 * 86400, 1);
+    db.pin_item(pinned).unwrap();
+    db.pin_item(pinned_image).unwrap();
+
+    let config = AppConfig { retention_days: 7, ..AppConfig::default() };
+    let item = db.get_item(pinned).unwrap();
+    assert!(!item.is_expired(&config, now));
+    assert_eq!(db.cleanup_old_items(&config).unwrap(), 1);
+    assert_eq!(db.cleanup_with_policy(&RetentionPolicy::uniform(1), Some(1)).unwrap(), 0);
+
+    let remaining: Vec<_> = db.get_recent_items(10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining.contains(&pinned) && remaining.contains(&pinned_image));
+}
+
+#[test]
+fn test_search_metadata_fields() {
+    let db = Database::new_in_memory().unwrap();
+
+    let image = ProcessedData::builder()
+        .data_type(ProcessedDataType::Image)
+        .blob(vec![0u8; 16])
+        .preview_text("Image 10x10")
+        .metadata(ItemMetadata::Image {
+            width: 10,
+            height: 10,
+            format: "PNG".to_string(),
+            thumbnail_width: 10,
+            thumbnail_height: 10,
+            thumbnail_size: 16,
+            stored_format: "PNG".to_string(),
+        })
+        .build();
+    let rtf = DataProcessor::process_rtf_to_plain(br"{\rtf1\ansi Hello {\b world}}").unwrap();
+    let text = DataProcessor::process_text("plain words", &["public.utf8-plain-text".to_string()]);
+
+    let mut ids = Vec::new();
+    for (i, processed) in [&image, &rtf, &text].into_iter().enumerate() {
+        let blob_id = db.store_blob(&processed.blob).unwrap();
+        ids.push(db.insert_item(processed, blob_id, 1_700_000_000 + i as i64, 1, false).unwrap());
+    }
+    // Rows without metadata never match
+    let blob_id = db.store_blob(b"legacy").unwrap();
+    db.store_item(1_600_000_000, "text", false, false, Some("legacy"), 6, blob_id, None, 1).unwrap();
+
+    let png: Vec<ItemId> = db.search_metadata_field("format", "PNG").unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(png, vec![ids[0]]);
+    let any_format = db.search_metadata_field("format", "%").unwrap();
+    assert_eq!(any_format.len(), 1);
+
+    let rtf_items: Vec<ItemId> = db.search_metadata_contains("uti_types", "public.rtf").unwrap().iter().map(|i| i.id).collect();
+    assert_eq!(rtf_items, vec![ids[1]]);
+    let plain_items = db.search_metadata_contains("uti_types", "public.utf8-plain-text").unwrap();
+    assert_eq!(plain_items.len(), 1);
+    assert_eq!(plain_items[0].id, ids[2]);
+
+    assert!(db.search_metadata_field("format') OR 1=1 --", "PNG").is_err());
+    assert!(db.search_metadata_contains("", "public.rtf").is_err());
+}
+
 #[test]
 fn test_remove_duplicates() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     // Insert two items with the same preview
     for i in 0..2 {
@@ -314,10 +837,8 @@ fn test_remove_duplicates() {
 }
 
 #[test]
-fn test_enforce_history_limit() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+fn test_enforce_max_items() {
+    let db = Database::new_in_memory().unwrap();
 
     // Insert 10 items
     for i in 0..10 {
@@ -330,7 +851,7 @@ fn test_enforce_history_limit() {
     assert_eq!(db.count_items().unwrap(), 10);
 
     // Enforce limit of 5
-    let trimmed = db.enforce_history_limit(5).unwrap();
+    let trimmed = db.enforce_max_items(5).unwrap();
     assert_eq!(trimmed, 5);
     assert_eq!(db.count_items().unwrap(), 5);
 
@@ -340,11 +861,51 @@ fn test_enforce_history_limit() {
     assert_eq!(items[0].preview_text, Some("Item 9".to_string()));
 }
 
+#[test]
+fn test_max_items_holds_across_repeated_inserts() {
+    let db = Database::new_in_memory().unwrap();
+
+    for i in 0..25 {
+        store_text_item(&db, "text", &format!("Item {}", i), 1_700_000_000 + i, 1);
+        db.enforce_max_items(10).unwrap();
+        assert!(db.count_items().unwrap() <= 10);
+    }
+
+    let items = db.get_recent_items(20).unwrap();
+    assert_eq!(items.len(), 10);
+    assert_eq!(items[0].preview_text.as_deref(), Some("Item 24"));
+    assert_eq!(items[9].preview_text.as_deref(), Some("Item 15"));
+    // Evicted items took their blobs with them
+    assert_eq!(db.get_storage_breakdown().unwrap().blob_count, 10);
+}
+
+#[test]
+fn test_max_items_never_evicts_pinned_items() {
+    let db = Database::new_in_memory().unwrap();
+
+    let pinned: Vec<ItemId> = (0..3)
+        .map(|i| store_text_item(&db, "text", &format!("Pinned {}", i), 1_000 + i, 1))
+        .collect();
+    for id in &pinned {
+        db.pin_item(*id).unwrap();
+    }
+    for i in 0..10 {
+        store_text_item(&db, "text", &format!("Item {}", i), 2_000 + i, 1);
+    }
+
+    // The limit applies to unpinned items; the old pinned ones all stay
+    assert_eq!(db.enforce_max_items(5).unwrap(), 5);
+    let remaining: Vec<_> = db.get_recent_items(20).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(remaining.len(), 8);
+    assert!(pinned.iter().all(|id| remaining.contains(id)));
+
+    assert_eq!(db.enforce_max_items(0).unwrap(), 5);
+    assert_eq!(db.count_items().unwrap(), 3);
+}
+
 #[test]
 fn test_soft_delete_and_purge() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let mut db = Database::new_in_memory().unwrap();
 
     // Insert 3 items
     for i in 0..3 {
@@ -366,11 +927,205 @@ fn test_soft_delete_and_purge() {
     assert_eq!(purged, 0);
 }
 
+#[test]
+fn test_delete_items_by_type() {
+    let db = Database::new_in_memory().unwrap();
+
+    let now = chrono::Utc::now().timestamp();
+    for (i, data_type) in ["text", "image", "url", "image", "text"].iter().enumerate() {
+        let text = format!("{} {}", data_type, i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(now + i as i64, data_type, false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap();
+    }
+
+    assert_eq!(db.delete_items_by_type("image").unwrap(), 2);
+    assert_eq!(db.count_items().unwrap(), 3);
+
+    // Only the images are gone; the others keep their blobs
+    let items = db.get_recent_items(10).unwrap();
+    assert!(items.iter().all(|item| item.data_type != "image"));
+    assert_eq!(items.iter().filter(|item| item.data_type == "text").count(), 2);
+    assert_eq!(items.iter().filter(|item| item.data_type == "url").count(), 1);
+    for item in &items {
+        assert_eq!(db.get_blob(item.data_blob_id).unwrap(), item.preview_text.as_ref().unwrap().as_bytes());
+    }
+
+    // Nothing left to delete for that type
+    assert_eq!(db.delete_items_by_type("image").unwrap(), 0);
+    assert_eq!(db.delete_items_by_type("credential").unwrap(), 0);
+    assert_eq!(db.count_items().unwrap(), 3);
+}
+
+#[test]
+fn test_vacuum_blobs_removes_only_orphans() {
+    let db = Database::new_in_memory().unwrap();
+
+    let now = chrono::Utc::now().timestamp();
+    let mut referenced = Vec::new();
+    for i in 0..3 {
+        let text = format!("kept {}", i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(now + i, "text", false, false, Some(&text), text.len() as i64, blob_id, None, 1).unwrap();
+        referenced.push(blob_id);
+    }
+
+    // Blobs with no item, as a crash between the two deletes would leave
+    let orphans: Vec<_> = (0..2).map(|i| db.store_blob(format!("orphan {}", i).as_bytes()).unwrap()).collect();
+
+    // A soft-deleted item keeps its copy in deleted_data
+    let trashed_blob = db.store_blob(b"trashed").unwrap();
+    let trashed = db.store_item(now + 10, "text", false, false, Some("trashed"), 7, trashed_blob, None, 1).unwrap();
+    db.soft_delete_item(trashed).unwrap();
+
+    assert_eq!(db.vacuum_blobs().unwrap(), 2);
+    for blob_id in orphans {
+        assert!(db.get_blob(blob_id).is_err());
+    }
+    for blob_id in referenced {
+        assert!(db.get_blob(blob_id).is_ok());
+    }
+    assert_eq!(db.count_items().unwrap(), 3);
+
+    // Nothing left to remove
+    assert_eq!(db.vacuum_blobs().unwrap(), 0);
+}
+
+#[test]
+fn test_image_thumbnail_blob_lifecycle() {
+    let mut db = Database::new_in_memory().unwrap();
+
+    let img = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(96, 64, image::Rgb([0, 128, 255])));
+    let mut png = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+    let processed = DataProcessor::process_image(&png, "public.png").unwrap();
+
+    let pending = PendingItem::from_processed(&processed, processed.blob.clone(), 1_700_000_000, false);
+    let (_, image_id) = db.store_item_transactional(&pending).unwrap();
+    let text = DataProcessor::process_text("no thumbnail here", &[]);
+    let pending = PendingItem::from_processed(&text, text.blob.clone(), 1_700_000_001, false);
+    let (_, text_id) = db.store_item_transactional(&pending).unwrap();
+
+    let thumbnail_blob_id = db.get_item(image_id).unwrap().thumbnail_blob_id.expect("image should have a thumbnail");
+    let thumbnail = image::load_from_memory(&db.get_thumbnail(thumbnail_blob_id).unwrap()).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (48, 32));
+    assert_eq!(db.get_item(text_id).unwrap().thumbnail_blob_id, None);
+
+    // Referenced by its item, so not an orphan; deleting the item removes it
+    assert_eq!(db.vacuum_blobs().unwrap(), 0);
+    db.delete_item(image_id).unwrap();
+    assert!(db.get_thumbnail(thumbnail_blob_id).is_err());
+
+    // Encrypted items never keep a plaintext thumbnail
+    let pending = PendingItem::from_processed(&processed, Vec::new(), 1_700_000_002, true);
+    let (_, encrypted_id) = db.store_item_transactional(&pending).unwrap();
+    assert_eq!(db.get_item(encrypted_id).unwrap().thumbnail_blob_id, None);
+}
+
+#[test]
+fn test_source_app_metadata_roundtrip() {
+    let mut db = Database::new_in_memory().unwrap();
+    let safari = SourceApp { name: Some("Safari".to_string()), bundle_id: Some("com.apple.Safari".to_string()) };
+    let notes = SourceApp { name: Some("Notes".to_string()), bundle_id: Some("com.apple.Notes".to_string()) };
+
+    let mut store = |text: &str, timestamp: i64, source: &SourceApp| {
+        let processed = DataProcessor::process_text(text, &["public.utf8-plain-text".to_string()]);
+        let pending = PendingItem::from_processed(&processed, processed.blob.clone(), timestamp, false)
+            .with_source_app(source);
+        db.store_item_transactional(&pending).unwrap().1
+    };
+    let first = store("see https://example.com", 100, &safari);
+    let second = store("shopping list", 200, &notes);
+    let third = store("another link https://rust-lang.org", 300, &safari);
+    let unknown = store("no source", 400, &SourceApp::default());
+
+    let item = db.get_item(first).unwrap();
+    assert_eq!(item.source_app_name.as_deref(), Some("Safari"));
+    assert_eq!(item.source_bundle_id.as_deref(), Some("com.apple.Safari"));
+    // The typed metadata is untouched by the extra keys
+    match ItemMetadata::from_json(item.metadata.as_deref().unwrap()) {
+        ItemMetadata::Text { uti_types, embedded_urls } => {
+            assert_eq!(uti_types, vec!["public.utf8-plain-text".to_string()]);
+            assert_eq!(embedded_urls, vec!["https://example.com".to_string()]);
+        }
+        other => panic!("expected Text, got {:?}", other),
+    }
+    assert_eq!(db.get_item(unknown).unwrap().source_app_name, None);
+
+    let from_safari: Vec<_> = db.get_items_from_app("com.apple.Safari", 10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(from_safari, vec![third, first]);
+    let from_notes: Vec<_> = db.get_items_from_app("com.apple.Notes", 10).unwrap().into_iter().map(|i| i.id).collect();
+    assert_eq!(from_notes, vec![second]);
+    assert!(db.get_items_from_app("com.example.none", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_soft_delete_items_by_source_app() {
+    let mut db = Database::new_in_memory().unwrap();
+    let slack = SourceApp { name: Some("Slack".to_string()), bundle_id: Some("com.tinyspeck.slackmacgap".to_string()) };
+    let notes = SourceApp { name: Some("Notes".to_string()), bundle_id: Some("com.apple.Notes".to_string()) };
+
+    let mut store = |text: &str, timestamp: i64, source: &SourceApp| {
+        let processed = DataProcessor::process_text(text, &[]);
+        let pending = PendingItem::from_processed(&processed, processed.blob.clone(), timestamp, false)
+            .with_source_app(source);
+        db.store_item_transactional(&pending).unwrap().1
+    };
+    store("standup notes", 100, &slack);
+    let kept = store("groceries", 200, &notes);
+    store("deploy link https://example.com", 300, &slack);
+    let unknown = store("no source", 400, &SourceApp::default());
+
+    assert_eq!(db.soft_delete_items_by_source_app("com.tinyspeck.slackmacgap").unwrap(), 2);
+
+    // Only Slack's items are gone, and they are recoverable
+    let remaining: Vec<_> = db.get_recent_items(10).unwrap().into_iter().map(|item| item.id).collect();
+    assert_eq!(remaining, vec![unknown, kept]);
+    assert_eq!(db.get_blob(db.get_item(kept).unwrap().data_blob_id).unwrap(), b"groceries");
+    assert_eq!(db.get_deleted_item_count().unwrap(), 2);
+    assert!(db.get_deleted_items(10).unwrap().iter().all(|item| item.preview_text.as_deref() != Some("groceries")));
+
+    assert_eq!(db.soft_delete_items_by_source_app("com.tinyspeck.slackmacgap").unwrap(), 0);
+    assert_eq!(db.soft_delete_items_by_source_app("com.example.none").unwrap(), 0);
+    assert_eq!(db.count_items().unwrap(), 2);
+}
+
+#[test]
+fn test_get_items_with_embedded_url() {
+    let db = Database::new_in_memory().unwrap();
+
+    let texts = [
+        "See https://example.com/a and https://rust-lang.org",
+        "Only https://example.com/a here",
+        "No links at all",
+    ];
+    let now = chrono::Utc::now().timestamp();
+    let mut ids = Vec::new();
+    for (i, text) in texts.iter().enumerate() {
+        let processed = DataProcessor::process_text(text, &[]);
+        let blob_id = db.store_blob(&processed.blob).unwrap();
+        ids.push(db.insert_item(&processed, blob_id, now + i as i64, 1, false).unwrap());
+    }
+
+    // Legacy metadata that is not an object must not break the query
+    let blob_id = db.store_blob(b"legacy").unwrap();
+    let bad = ItemMetadata::Unknown(serde_json::Value::String("{not json".to_string()));
+    db.store_item(now + 10, "text", false, false, Some("legacy"), 6, blob_id, Some(&bad), 1).unwrap();
+
+    let found: Vec<_> = db.get_items_with_embedded_url("https://example.com/a", 10).unwrap()
+        .into_iter().map(|item| item.id).collect();
+    assert_eq!(found, vec![ids[1], ids[0]]);
+
+    let found = db.get_items_with_embedded_url("https://rust-lang.org", 10).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, ids[0]);
+
+    assert!(db.get_items_with_embedded_url("https://nowhere.test", 10).unwrap().is_empty());
+    assert_eq!(db.get_items_with_embedded_url("https://example.com/a", 1).unwrap().len(), 1);
+}
+
 #[test]
 fn test_copy_count_tracking() {
-    let temp_dir = TempDir::new().unwrap();
-    let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path).unwrap();
+    let db = Database::new_in_memory().unwrap();
 
     // Insert item with copy_count = 3
     let blob_id = db.store_blob(b"repeated").unwrap();
@@ -381,12 +1136,561 @@ fn test_copy_count_tracking() {
     assert_eq!(items[0].copy_count, 3);
 }
 
+#[test]
+fn test_repeat_copy_found_by_content_hash() {
+    let db = Database::new_in_memory().unwrap();
+
+    let processed = DataProcessor::process_text("copied twice", &[]);
+    assert!(db.find_item_by_content_hash(&processed.content_hash).unwrap().is_none());
+
+    let blob_id = db.store_blob(&processed.blob).unwrap();
+    let timestamp = chrono::Utc::now().timestamp();
+    let item_id = db.insert_item(&processed, blob_id, timestamp, 1, false).unwrap();
+
+    // A later item pushes the original down the history
+    let other = DataProcessor::process_text("something else", &[]);
+    let other_blob = db.store_blob(&other.blob).unwrap();
+    db.insert_item(&other, other_blob, timestamp + 1, 1, false).unwrap();
+
+    let again = DataProcessor::process_text("copied twice", &[]);
+    let existing = db.find_item_by_content_hash(&again.content_hash).unwrap().unwrap();
+    assert_eq!(existing.id, item_id);
+
+    db.record_repeat_copy(existing.id, timestamp + 2).unwrap();
+
+    let items = db.get_recent_items(10).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].id, item_id);
+    assert_eq!(items[0].copy_count, 2);
+    assert_eq!(items[0].timestamp, timestamp + 2);
+}
+
+#[test]
+fn test_store_item_transactional() {
+    let mut db = Database::new_in_memory().unwrap();
+
+    let processed = DataProcessor::process_text("stored atomically", &[]);
+    let pending = PendingItem::from_processed(&processed, processed.blob.clone(), 1_700_000_000, false);
+    let (blob_id, item_id) = db.store_item_transactional(&pending).unwrap();
+
+    let item = db.get_item(item_id).unwrap();
+    assert_eq!(item.data_blob_id, blob_id);
+    assert_eq!(item.preview_text.as_deref(), Some("stored atomically"));
+    assert_eq!(item.data_size, processed.blob.len() as i64);
+    assert_eq!(item.copy_count, 1);
+    assert_eq!(db.get_blob(blob_id).unwrap(), processed.blob);
+    assert_eq!(db.find_item_by_content_hash(&processed.content_hash).unwrap().unwrap().id, item_id);
+}
+
+#[test]
+fn test_encrypted_items_never_store_the_plain_content_hash() {
+    let mut db = Database::new_in_memory().unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let encryptor = Encryptor::new(temp_dir.path().join("test.key")).unwrap();
+
+    let processed = DataProcessor::process_text("sk-live-1234567890abcdef", &[]);
+    let content_hash = Database::content_hash_for(&processed.blob, true, &encryptor);
+    let pending = PendingItem::from_processed(&processed, Vec::new(), 1_700_000_000, true)
+        .with_content_hash(content_hash.clone());
+    let (blob_id, item_id) = db.store_item_transactional(&pending).unwrap();
+    db.seal_blob(blob_id, item_id, &processed.blob, &encryptor).unwrap();
+
+    // A dictionary of plain SHA-256 hashes finds nothing
+    assert!(db.find_item_by_content_hash(&processed.content_hash).unwrap().is_none());
+    // The same scheme with the key still finds the repeat copy
+    let again = Database::content_hash_for(b"sk-live-1234567890abcdef", true, &encryptor).unwrap();
+    assert_eq!(db.find_item_by_content_hash(&again).unwrap().unwrap().id, item_id);
+
+    // Without a key there is no hash at all
+    let plain = PendingItem::from_processed(&processed, Vec::new(), 1_700_000_001, true);
+    assert_eq!(plain.content_hash, None);
+    db.insert_item(&processed, db.store_blob(b"").unwrap(), 1_700_000_002, 1, true).unwrap();
+    assert!(db.find_item_by_content_hash(&processed.content_hash).unwrap().is_none());
+}
+
+#[test]
+fn test_update_preview_text() {
+    let db = Database::new_in_memory().unwrap();
+
+    let blob_id = db.store_blob(b"  padded  ").unwrap();
+    let item_id = db.store_item(1_700_000_000, "text", false, false, Some("  padded  "), 10, blob_id, None, 1).unwrap();
+
+    db.update_preview_text(item_id, "padded").unwrap();
+    let item = db.get_item(item_id).unwrap();
+    assert_eq!(item.preview_text.as_deref(), Some("padded"));
+    assert_eq!(db.get_blob(blob_id).unwrap(), b"  padded  ");
+
+    assert!(db.update_preview_text(ItemId(item_id.0 + 1), "x").is_err());
+}
+
+#[test]
+fn test_storage_breakdown() {
+    let db = Database::new_in_memory().unwrap();
+
+    let empty = db.get_storage_breakdown().unwrap();
+    assert_eq!(empty.blob_count, 0);
+    assert_eq!(empty.live_items_bytes, 0);
+    assert_eq!(empty.avg_blob_bytes, 0.0);
+
+    let mut ids = Vec::new();
+    for size in [100usize, 200, 300] {
+        let data = vec![b'x'; size];
+        let blob_id = db.store_blob(&data).unwrap();
+        ids.push(db.store_item(1_700_000_000 + size as i64, "text", false, false, Some("x"), size as i64, blob_id, None, 1).unwrap());
+    }
+    db.soft_delete_item(ids[0]).unwrap();
+
+    let breakdown = db.get_storage_breakdown().unwrap();
+    assert_eq!(breakdown.live_items_bytes, 500);
+    assert_eq!(breakdown.blob_count, 2);
+    assert_eq!(breakdown.deleted_items_bytes, 100);
+    assert_eq!(breakdown.deleted_blob_count, 1);
+    assert_eq!(breakdown.avg_blob_bytes, 250.0);
+    assert_eq!(breakdown.largest_blob_bytes, 300);
+}
+
 #[test]
 fn test_database_size() {
+    let db = Database::new_in_memory().unwrap();
+
+    let size = db.get_db_size().unwrap();
+    assert!(size > 0, "Database should have non-zero size after initialization");
+}
+
+#[test]
+fn test_wal_checkpoint_truncates_wal() {
     let temp_dir = TempDir::new().unwrap();
     let db_path = temp_dir.path().join("test.db");
+    let wal_path = temp_dir.path().join("test.db-wal");
     let db = Database::new(db_path).unwrap();
 
-    let size = db.get_db_size().unwrap();
-    assert!(size > 0, "Database should have non-zero size after initialization");
+    let blob_id = db.store_blob(b"pending write").unwrap();
+    let timestamp = chrono::Utc::now().timestamp();
+    db.store_item(timestamp, "text", false, false, Some("pending write"), 13, blob_id, None, 1).unwrap();
+
+    let wal_before = std::fs::metadata(&wal_path).unwrap().len();
+    assert!(wal_before > 0, "WAL should contain the uncheckpointed write");
+
+    db.checkpoint_wal().unwrap();
+
+    let wal_after = std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+    assert!(wal_after < wal_before, "Checkpoint should shrink the WAL ({} -> {})", wal_before, wal_after);
+    assert_eq!(db.count_items().unwrap(), 1);
+}
+
+#[test]
+fn test_vacuum_reclaims_space() {
+    let db = Database::new_in_memory().unwrap();
+    assert_eq!(db.last_vacuum_at().unwrap(), None);
+    assert!(db.needs_vacuum().unwrap());
+
+    let payload = vec![b'x'; 4096];
+    let timestamp = chrono::Utc::now().timestamp();
+    for i in 0..200 {
+        let blob_id = db.store_blob(&payload).unwrap();
+        db.store_item(timestamp + i, "text", false, false, Some("filler"), 4096, blob_id, None, 1).unwrap();
+    }
+    db.enforce_max_items(10).unwrap();
+    db.checkpoint_wal().unwrap();
+    let size_before = db.get_db_size().unwrap();
+
+    db.vacuum().unwrap();
+
+    assert!(db.get_db_size().unwrap() < size_before);
+    assert!(db.last_vacuum_at().unwrap().is_some());
+    assert!(!db.needs_vacuum().unwrap());
+    assert_eq!(db.count_items().unwrap(), 10);
+}
+
+#[test]
+fn test_named_slots() {
+    let db = Database::new_in_memory().unwrap();
+
+    let timestamp = chrono::Utc::now().timestamp();
+    let mut ids = Vec::new();
+    for (i, text) in ["alpha", "beta", "gamma"].iter().enumerate() {
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        ids.push(db.store_item(timestamp + i as i64, "text", false, false, Some(text), 5, blob_id, None, 1).unwrap());
+    }
+
+    // Everything starts in the default slot, which isn't listed
+    assert!(db.list_slots().unwrap().is_empty());
+
+    db.move_item_to_slot(ids[0], "work").unwrap();
+    db.move_item_to_slot(ids[1], "home").unwrap();
+    db.move_item_to_slot(ids[2], "work").unwrap();
+    assert_eq!(db.list_slots().unwrap(), vec!["home".to_string(), "work".to_string()]);
+
+    // Unknown item IDs are reported as errors
+    let missing = clipboard_manager::storage::ItemId(ids[2].0 + 100);
+    assert!(db.move_item_to_slot(missing, "work").is_err());
+}
+
+#[test]
+fn test_streaming_export_matches_full_export() {
+    let db = Database::new_in_memory().unwrap();
+
+    for i in 0..5 {
+        let text = format!("export item {} with \"quotes\" and ünïcode", i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(1_700_000_000 + i, "text", i % 2 == 0, false, Some(&text),
+                      text.len() as i64, blob_id, None, 1).unwrap();
+    }
+
+    // Chunks cover every row exactly once, in ID order
+    let chunks: Vec<_> = db.iter_items(2).collect::<Result<_, _>>().unwrap();
+    assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+
+    let mut full = Vec::new();
+    let mut streamed = Vec::new();
+    assert_eq!(db.export_json(&mut full).unwrap(), 5);
+    assert_eq!(db.export_json_streaming(&mut streamed).unwrap(), 5);
+    assert_eq!(full, streamed);
+
+    let parsed: serde_json::Value = serde_json::from_slice(&streamed).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 5);
+}
+
+#[test]
+fn test_from_row_field_mapping() {
+    use clipboard_manager::storage::database::{ClipboardItem, ITEM_COLUMNS};
+    use clipboard_manager::storage::{BlobId, ItemId};
+
+    assert!(ClipboardItem::select_sql().contains(ITEM_COLUMNS));
+
+    // One literal per column, in ITEM_COLUMNS order
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    let item = conn.query_row(
+        "SELECT 7, 1700000000, 'text', 1, 0, 'preview', 42, 9, '{}', 3, 1, 11, 0, 'work,todo'",
+        [],
+        ClipboardItem::from_row,
+    ).unwrap();
+
+    assert_eq!(item.id, ItemId(7));
+    assert_eq!(item.timestamp, 1_700_000_000);
+    assert_eq!(item.data_type, "text");
+    assert!(item.is_sensitive);
+    assert!(!item.is_encrypted);
+    assert_eq!(item.preview_text.as_deref(), Some("preview"));
+    assert_eq!(item.data_size, 42);
+    assert_eq!(item.data_blob_id, BlobId(9));
+    assert_eq!(item.metadata.as_deref(), Some("{}"));
+    assert_eq!(item.copy_count, 3);
+    assert!(item.is_pinned);
+    assert_eq!(item.thumbnail_blob_id, Some(BlobId(11)));
+    assert_eq!(item.sensitivity_override, Some(false));
+    assert_eq!(item.tags, vec!["work".to_string(), "todo".to_string()]);
+}
+
+#[test]
+fn test_sensitive_item_counts() {
+    let db = Database::new_in_memory().unwrap();
+
+    assert_eq!(db.count_sensitive_items().unwrap(), 0);
+    assert_eq!(db.sensitive_item_fraction().unwrap(), 0.0);
+
+    for (i, sensitive) in [true, false, false, true, false].iter().enumerate() {
+        let text = format!("item {}", i);
+        let blob_id = db.store_blob(text.as_bytes()).unwrap();
+        db.store_item(1_700_000_000 + i as i64, "text", *sensitive, false, Some(&text),
+                      text.len() as i64, blob_id, None, 1).unwrap();
+    }
+
+    assert_eq!(db.count_sensitive_items().unwrap(), 2);
+    assert!((db.sensitive_item_fraction().unwrap() - 0.4).abs() < f64::EPSILON);
+
+    let stats = db.get_statistics().unwrap();
+    assert_eq!(stats.total_items, 5);
+    assert_eq!(stats.sensitive_items, 2);
+    assert!((stats.sensitive_fraction - 0.4).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_get_statistics_breaks_down_by_type() {
+    let db = Database::new_in_memory().unwrap();
+
+    let empty = db.get_statistics().unwrap();
+    assert_eq!(empty.total_items, 0);
+    assert!(empty.items_by_type.is_empty());
+    assert_eq!((empty.oldest_timestamp, empty.newest_timestamp), (None, None));
+    assert_eq!(empty.total_blob_bytes, 0);
+
+    store_text_item(&db, "text", "hello", 300, 1);
+    store_text_item(&db, "text", "world!", 100, 1);
+    store_text_item(&db, "url", "https://example.com", 200, 1);
+    let blob_id = db.store_blob(&[0u8; 40]).unwrap();
+    db.store_item(400, "text", true, true, Some("sk-secret"), 9, blob_id, None, 1).unwrap();
+    let blob_id = db.store_blob(&[1u8; 1000]).unwrap();
+    db.store_item(250, "image", false, false, Some("Image 10x10"), 1000, blob_id, None, 1).unwrap();
+
+    let stats = db.get_statistics().unwrap();
+    assert_eq!(stats.total_items, 5);
+    assert_eq!(stats.items_by_type.get("text"), Some(&3));
+    assert_eq!(stats.items_by_type.get("url"), Some(&1));
+    assert_eq!(stats.items_by_type.get("image"), Some(&1));
+    assert_eq!(stats.items_by_type.get("email"), None);
+    assert_eq!(stats.bytes_by_type.get("text"), Some(&(5 + 6 + 9)));
+    assert_eq!(stats.bytes_by_type.get("image"), Some(&1000));
+    assert_eq!(stats.sensitive_items, 1);
+    assert_eq!(stats.encrypted_items, 1);
+    assert_eq!(stats.oldest_timestamp, Some(100));
+    assert_eq!(stats.newest_timestamp, Some(400));
+    assert_eq!(stats.total_blob_bytes, db.get_storage_breakdown().unwrap().live_items_bytes);
+}
+
+#[test]
+fn test_get_items_by_type() {
+    let db = Database::new_in_memory().unwrap();
+
+    for (i, text) in ["alice@example.com", "notes", "bob@example.org", "https://example.com"].iter().enumerate() {
+        let processed = DataProcessor::process_text(text, &[]);
+        let blob_id = db.store_blob(&processed.blob).unwrap();
+        db.insert_item(&processed, blob_id, 1_700_000_000 + i as i64, 1, false).unwrap();
+    }
+
+    let emails = db.get_items_by_type("email", 10).unwrap();
+    let previews: Vec<_> = emails.iter().map(|item| item.preview_text.as_deref().unwrap()).collect();
+    assert_eq!(previews, vec!["bob@example.org", "alice@example.com"]);
+
+    assert_eq!(db.get_items_by_type("email", 1).unwrap().len(), 1);
+    assert!(db.get_items_by_type("image", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_get_items_by_sensitivity_and_date_range() {
+    let db = Database::new_in_memory().unwrap();
+    let plain = store_text_item(&db, "text", "notes", 100, 1);
+    store_text_item(&db, "url", "https://example.com", 200, 1);
+    let blob_id = db.store_blob(b"ciphertext").unwrap();
+    let secret = db.store_item(300, "text", true, true, Some("sk-secret"), 9, blob_id, None, 1).unwrap();
+    let flagged = store_text_item(&db, "email", "alice@example.com", 400, 1);
+    db.set_sensitivity_override(flagged, Some(true)).unwrap();
+
+    let ids = |items: Vec<ClipboardItem>| -> Vec<ItemId> {
+        items.into_iter().map(|item| item.id).collect()
+    };
+
+    // The manual override counts, in both directions
+    assert_eq!(ids(db.get_items_by_sensitivity(true, 10).unwrap()), vec![flagged, secret]);
+    db.set_sensitivity_override(secret, Some(false)).unwrap();
+    assert_eq!(ids(db.get_items_by_sensitivity(true, 10).unwrap()), vec![flagged]);
+    assert_eq!(db.get_items_by_sensitivity(false, 10).unwrap().len(), 3);
+    assert_eq!(db.get_items_by_sensitivity(false, 1).unwrap().len(), 1);
+
+    let range = db.get_items_by_date_range(100, 300, 10).unwrap();
+    assert_eq!(range.iter().map(|item| item.timestamp).collect::<Vec<_>>(), vec![300, 200, 100]);
+    assert_eq!(ids(db.get_items_by_date_range(101, 199, 10).unwrap()), Vec::<ItemId>::new());
+    assert_eq!(ids(db.get_items_by_date_range(0, 1000, 1).unwrap()), vec![flagged]);
+    assert!(ids(db.get_items_by_date_range(0, 150, 10).unwrap()).contains(&plain));
+}
+
+#[test]
+fn test_store_item_returning_fills_defaults() {
+    let db = Database::new_in_memory().unwrap();
+    let blob_id = db.store_blob(b"hello").unwrap();
+
+    let item = db.store_item_returning(1_700_000_000, "text", false, false, Some("hello"), 5, blob_id, None, 2).unwrap();
+    assert_eq!(item.preview_text.as_deref(), Some("hello"));
+    assert_eq!(item.copy_count, 2);
+    assert!(!item.is_pinned);
+    assert_eq!(item.tags, Vec::<String>::new());
+    assert_eq!(db.get_item(item.id).unwrap().timestamp, item.timestamp);
+}
+
+fn store_text_item(db: &Database, data_type: &str, text: &str, timestamp: i64, copy_count: i64) -> ItemId {
+    let blob_id = db.store_blob(text.as_bytes()).unwrap();
+    db.store_item(timestamp, data_type, false, false, Some(text), text.len() as i64, blob_id, None, copy_count).unwrap()
+}
+
+#[test]
+fn test_recount_all_copy_counts_merges_duplicates() {
+    let mut db = Database::new_in_memory().unwrap();
+
+    let oldest = store_text_item(&db, "text", "hello", 100, 1);
+    let newest = store_text_item(&db, "text", "hello", 300, 2);
+    store_text_item(&db, "text", "hello", 200, 4);
+    store_text_item(&db, "url", "hello", 150, 5);
+    store_text_item(&db, "text", "other", 400, 1);
+    db.pin_item(oldest).unwrap();
+
+    // Rows without a content hash are never merged on their preview
+    assert!(db.find_copy_count_inconsistencies().unwrap().is_empty());
+    db.compute_content_hash_for_existing_items().unwrap();
+    assert_eq!(db.find_copy_count_inconsistencies().unwrap(), vec![("hello".to_string(), 7, 2)]);
+
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 2);
+    assert_eq!(db.count_items().unwrap(), 3);
+
+    let merged = db.get_item(newest).unwrap();
+    assert_eq!(merged.copy_count, 1 + 2 + 4);
+    assert!(merged.is_pinned, "a pinned duplicate keeps the merged item pinned");
+    assert!(db.get_item(oldest).is_err());
+
+    // Same preview but a different type is a different item
+    let urls = db.get_items_by_type("url", 10).unwrap();
+    assert_eq!(urls.len(), 1);
+    assert_eq!(urls[0].copy_count, 5);
+
+    assert!(db.find_copy_count_inconsistencies().unwrap().is_empty());
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 0);
+}
+
+#[test]
+fn test_recount_keeps_different_content_with_same_preview() {
+    let mut db = Database::new_in_memory().unwrap();
+
+    // Long texts share a truncated preview but not their content hash
+    let shared_prefix = "x".repeat(300);
+    for (i, suffix) in ["a", "b"].iter().enumerate() {
+        let processed = DataProcessor::process_text(&format!("{}{}", shared_prefix, suffix), &[]);
+        let pending = PendingItem::from_processed(&processed, processed.blob.clone(), 1_700_000_000 + i as i64, false);
+        db.store_item_transactional(&pending).unwrap();
+    }
+
+    assert!(db.find_copy_count_inconsistencies().unwrap().is_empty());
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 0);
+    assert_eq!(db.count_items().unwrap(), 2);
+
+    // Without hashes, a shared preview alone doesn't make them duplicates
+    store_text_item(&db, "image", "1920x1080 PNG", 100, 1);
+    store_text_item(&db, "image", "1920x1080 PNG", 200, 1);
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 0);
+    assert_eq!(db.count_items().unwrap(), 4);
+}
+
+#[test]
+fn test_invalid_copy_counts_are_reported_and_reset() {
+    let mut db = Database::new_in_memory().unwrap();
+    let id = store_text_item(&db, "text", "zero", 100, 0);
+
+    assert_eq!(db.find_copy_count_inconsistencies().unwrap(), vec![("zero".to_string(), 1, 0)]);
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 0);
+    assert_eq!(db.get_item(id).unwrap().copy_count, 1);
+}
+
+#[test]
+fn test_schema_v1_upgrade_recounts_copy_counts() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    {
+        let db = Database::new(db_path.clone()).unwrap();
+        store_text_item(&db, "text", "dup", 100, 3);
+        store_text_item(&db, "text", "dup", 200, 1);
+        db.set_config("schema_version", "1").unwrap();
+    }
+
+    // The upgrade can't tell unhashed rows apart, so it keeps both
+    let mut db = Database::new(db_path).unwrap();
+    assert_eq!(db.count_items().unwrap(), 2);
+
+    db.compute_content_hash_for_existing_items().unwrap();
+    assert_eq!(db.recount_all_copy_counts().unwrap(), 1);
+    let items = db.get_recent_items(10).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].copy_count, 4);
+    assert_eq!(items[0].timestamp, 200);
+    let latest = Migrator::new().latest_version().to_string();
+    assert_eq!(db.get_config("schema_version").unwrap().as_deref(), Some(latest.as_str()));
+}
+
+#[test]
+fn test_compute_content_hash_for_existing_items() {
+    let db = Database::new_in_memory().unwrap();
+
+    // Rows stored without a hash, as before the content_hash column existed
+    let texts: Vec<String> = (0..50).map(|i| format!("legacy item {}", i)).collect();
+    for (i, text) in texts.iter().enumerate() {
+        store_text_item(&db, "text", text, 1_700_000_000 + i as i64, 1);
+    }
+    let blob_id = db.store_blob(b"ciphertext").unwrap();
+    let encrypted = db.store_item(1_800_000_000, "text", true, true, Some("secret"), 6, blob_id, None, 1).unwrap();
+
+    assert!(db.content_hash_migration_pending().unwrap());
+    assert!(db.find_item_by_content_hash(&ProcessedData::hash_content(texts[0].as_bytes())).unwrap().is_none());
+
+    assert_eq!(db.compute_content_hash_for_existing_items().unwrap(), 50);
+    assert!(!db.content_hash_migration_pending().unwrap());
+
+    for text in &texts {
+        let found = db.find_item_by_content_hash(&ProcessedData::hash_content(text.as_bytes())).unwrap();
+        assert_eq!(found.unwrap().preview_text.as_deref(), Some(text.as_str()));
+    }
+    // Encrypted blobs are left alone
+    assert!(db.find_item_by_content_hash(&ProcessedData::hash_content(b"ciphertext")).unwrap().is_none());
+    assert!(db.get_item(encrypted).is_ok());
+
+    // Re-running finds nothing left to do
+    assert_eq!(db.compute_content_hash_for_existing_items().unwrap(), 0);
+}
+
+#[test]
+fn test_get_items_paginated() {
+    let db = Database::new_in_memory().unwrap();
+
+    // Two items share a timestamp so the ID tie-break is exercised
+    let ids: Vec<ItemId> = (0..7)
+        .map(|i| store_text_item(&db, "text", &format!("item {}", i), 1_700_000_000 + i.min(5), 1))
+        .collect();
+    db.pin_item(ids[0]).unwrap();
+
+    let all = db.get_recent_items(100).unwrap();
+    let all_ids: Vec<ItemId> = all.iter().map(|item| item.id).collect();
+    assert_eq!(all_ids, vec![ids[0], ids[6], ids[5], ids[4], ids[3], ids[2], ids[1]]);
+
+    for page_size in 1..=4 {
+        let mut paged = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = db.get_items_paginated(offset, page_size).unwrap();
+            if page.is_empty() {
+                break;
+            }
+            offset += page.len() as i64;
+            paged.extend(page.into_iter().map(|item| item.id));
+        }
+        assert_eq!(paged, all_ids, "page size {}", page_size);
+        assert_eq!(paged.len() as i64, db.count_items().unwrap());
+    }
+
+    let page: Vec<ItemId> = db.get_items_paginated(2, 3).unwrap().iter().map(|item| item.id).collect();
+    assert_eq!(page, vec![ids[5], ids[4], ids[3]]);
+    assert!(db.get_items_paginated(7, 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_soft_delete_item_moves_it_to_trash() {
+    let db = Database::new_in_memory().unwrap();
+    let kept = store_text_item(&db, "text", "keep me", 1_700_000_000, 1);
+    let trashed = store_text_item(&db, "url", "https://example.com", 1_700_000_100, 1);
+    assert!(db.get_deleted_items(10).unwrap().is_empty());
+
+    db.soft_delete_item(trashed).unwrap();
+
+    let recent: Vec<ItemId> = db.get_recent_items(10).unwrap().iter().map(|item| item.id).collect();
+    assert_eq!(recent, vec![kept]);
+    assert!(db.get_item(trashed).is_err());
+
+    let trash = db.get_deleted_items(10).unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].original_id, trashed);
+    assert_eq!(trash[0].timestamp, 1_700_000_100);
+    assert_eq!(trash[0].data_type, "url");
+    assert_eq!(trash[0].preview_text.as_deref(), Some("https://example.com"));
+
+    // Unknown ID: nothing moves
+    assert!(db.soft_delete_item(ItemId(9999)).is_err());
+    assert_eq!(db.get_deleted_item_count().unwrap(), 1);
+}
+
+#[test]
+fn test_get_app_icon_falls_back_to_generic_icon() {
+    let db = Database::new_in_memory().unwrap();
+
+    let png = db.get_app_icon("com.example.not-installed").expect("generic app icon");
+    let icon = image::load_from_memory(&png).unwrap();
+    assert_eq!((icon.width(), icon.height()), (APP_ICON_PIXELS, APP_ICON_PIXELS));
+
+    // Served from the cache the second time
+    assert_eq!(db.get_app_icon("com.example.not-installed"), Some(png));
 }